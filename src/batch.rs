@@ -0,0 +1,228 @@
+// Copyright 2016 Kyle Mayes
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Parsing many translation units in parallel.
+
+use std::cmp;
+use std::mem;
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex};
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::time::{Duration, Instant};
+
+use super::{Clang, Index, ParseOptions, SourceError, ThreadOptions, TranslationUnit};
+
+//================================================
+// Structs
+//================================================
+
+// Batch _________________________________________
+
+/// Parses a list of source files in parallel.
+///
+/// Each worker thread has its own index and parses one source file at a time. Errors that occur
+/// while parsing a source file are collected and do not prevent the other source files from being
+/// parsed.
+#[derive(Clone, Debug)]
+pub struct Batch {
+    jobs: Vec<Job>,
+    threads: usize,
+    thread_options: ThreadOptions,
+    options: ParseOptions,
+}
+
+impl Batch {
+    //- Constructors -----------------------------
+
+    /// Constructs a new empty `Batch` which will use the supplied number of worker threads.
+    ///
+    /// At least one worker thread will be used.
+    pub fn new(threads: usize) -> Batch {
+        Batch {
+            jobs: vec![],
+            threads: cmp::max(threads, 1),
+            thread_options: ThreadOptions::default(),
+            options: ParseOptions::default(),
+        }
+    }
+
+    //- Accessors --------------------------------
+
+    /// Returns the source files in this batch.
+    pub fn get_jobs(&self) -> &[Job] {
+        &self.jobs
+    }
+
+    /// Returns the maximum number of worker threads this batch will use.
+    pub fn get_threads(&self) -> usize {
+        self.threads
+    }
+
+    /// Parses the source files in this batch and calls the supplied function with each source file
+    /// that is successfully parsed and the resulting translation unit.
+    ///
+    /// Each worker thread shares the supplied instance of `Clang` and parses the source files with
+    /// its own index. The function is called on the thread that parsed the source file while the
+    /// translation unit is alive. The returned outcomes are in the order the source files were
+    /// added to this batch.
+    ///
+    /// When the `runtime` feature is enabled, the source files are instead parsed one at a time on
+    /// the calling thread since the `libclang` shared library may only be available to the thread
+    /// that loaded it (see `Clang`).
+    ///
+    /// # Panics
+    ///
+    /// * a worker thread could not be spawned
+    /// * the supplied function panics
+    pub fn run<T, F>(&self, clang: &Arc<Clang>, f: F) -> Vec<Outcome<T>>
+        where T: Send + 'static, F: Fn(&Path, &TranslationUnit) -> T + Send + Sync + 'static
+    {
+        let state = Arc::new(State {
+            batch: self.clone(), next: AtomicUsize::new(0), outcomes: Mutex::new(vec![]), f: f
+        });
+        execute(clang, &state);
+
+        let mut outcomes = mem::replace(&mut *state.outcomes.lock().unwrap(), vec![]);
+        outcomes.sort_by_key(|o| o.0);
+        outcomes.into_iter().map(|o| o.1).collect()
+    }
+
+    //- Mutators ---------------------------------
+
+    /// Adds a source file which will be parsed with the supplied compiler arguments to this batch.
+    pub fn add<P: Into<PathBuf>, S: AsRef<str>>(&mut self, path: P, arguments: &[S]) -> &mut Batch {
+        let arguments = arguments.iter().map(|a| a.as_ref().into()).collect();
+        self.jobs.push(Job { path: path.into(), arguments: arguments });
+        self
+    }
+
+    /// Sets the options the source files in this batch will be parsed with.
+    pub fn options(&mut self, options: ParseOptions) -> &mut Batch {
+        self.options = options;
+        self
+    }
+
+    /// Sets the thread options for the index of each worker thread.
+    pub fn thread_options(&mut self, options: ThreadOptions) -> &mut Batch {
+        self.thread_options = options;
+        self
+    }
+}
+
+// Job ___________________________________________
+
+/// A source file in a batch.
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub struct Job {
+    /// The path of the source file.
+    pub path: PathBuf,
+    /// The compiler arguments the source file will be parsed with.
+    pub arguments: Vec<String>,
+}
+
+impl Job {
+    //- Accessors --------------------------------
+
+    fn run<T, F: Fn(&Path, &TranslationUnit) -> T>(
+        &self, index: &Index, options: ParseOptions, f: &F
+    ) -> Outcome<T> {
+        let start = Instant::now();
+        let mut parser = index.parser(&self.path);
+        let tu = parser.arguments(&self.arguments).options(options).parse();
+        let duration = start.elapsed();
+        Outcome {
+            path: self.path.clone(),
+            result: tu.map(|tu| f(&self.path, &tu)),
+            duration: duration,
+        }
+    }
+}
+
+// Outcome _______________________________________
+
+/// The outcome of parsing a source file in a batch.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Outcome<T> {
+    /// The path of the source file.
+    pub path: PathBuf,
+    /// The value returned by the function called with the translation unit or the error that
+    /// prevented the source file from being parsed.
+    pub result: Result<T, SourceError>,
+    /// The time it took to parse the source file, excluding the time spent in the function called
+    /// with the translation unit.
+    pub duration: Duration,
+}
+
+// State _________________________________________
+
+struct State<T, F> {
+    batch: Batch,
+    next: AtomicUsize,
+    outcomes: Mutex<Vec<(usize, Outcome<T>)>>,
+    f: F,
+}
+
+impl<T, F: Fn(&Path, &TranslationUnit) -> T> State<T, F> {
+    //- Accessors --------------------------------
+
+    fn work(&self, clang: &Clang) {
+        let mut index = Index::new(clang, false, false);
+        index.set_thread_options(self.batch.thread_options);
+        loop {
+            let job = self.next.fetch_add(1, Ordering::SeqCst);
+            if job >= self.batch.jobs.len() {
+                break;
+            }
+
+            let outcome = self.batch.jobs[job].run(&index, self.batch.options, &self.f);
+            self.outcomes.lock().unwrap().push((job, outcome));
+        }
+    }
+}
+
+//================================================
+// Functions
+//================================================
+
+#[cfg(not(feature="runtime"))]
+fn execute<T, F>(clang: &Arc<Clang>, state: &Arc<State<T, F>>)
+    where T: Send + 'static, F: Fn(&Path, &TranslationUnit) -> T + Send + Sync + 'static
+{
+    use std::panic;
+    use std::thread;
+
+    let threads = cmp::min(state.batch.threads, state.batch.jobs.len());
+    let handles = (0..threads).map(|i| {
+        let (clang, state) = (clang.clone(), state.clone());
+        let builder = thread::Builder::new().name(format!("clang-batch-{}", i));
+        builder.spawn(move || state.work(&clang)).unwrap()
+    }).collect::<Vec<_>>();
+
+    let mut panic = None;
+    for handle in handles {
+        if let Err(error) = handle.join() {
+            panic = Some(error);
+        }
+    }
+    if let Some(panic) = panic {
+        panic::resume_unwind(panic);
+    }
+}
+
+#[cfg(feature="runtime")]
+fn execute<T, F>(clang: &Arc<Clang>, state: &Arc<State<T, F>>)
+    where T: Send + 'static, F: Fn(&Path, &TranslationUnit) -> T + Send + Sync + 'static
+{
+    state.work(clang);
+}
@@ -0,0 +1,45 @@
+// Copyright 2016 Kyle Mayes
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! A minimal C and C++ language server which communicates over `stdin` and `stdout`.
+//!
+//! Any command-line arguments are passed to `libclang` as compiler arguments when parsing
+//! documents (e.g., `clang-lsp -std=c++11 -Iinclude`).
+
+extern crate clang;
+
+use std::env;
+use std::io::{self, Write};
+use std::process;
+
+use clang::{Clang, Index};
+use clang::lsp::{Server};
+
+fn main() {
+    let clang = Clang::new().unwrap();
+    let index = Index::new(&clang, false, false);
+    let arguments = env::args().skip(1).collect::<Vec<_>>();
+
+    let stdin = io::stdin();
+    let stdout = io::stdout();
+    let mut server = Server::new(&index, &arguments);
+    match server.run(&mut stdin.lock(), &mut stdout.lock()) {
+        Ok(true) => process::exit(0),
+        Ok(false) => process::exit(1),
+        Err(error) => {
+            let _ = writeln!(io::stderr(), "error: {}", error);
+            process::exit(1);
+        },
+    }
+}
@@ -0,0 +1,477 @@
+// Copyright 2016 Kyle Mayes
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Cross-referenced HTML source browsing.
+
+use std::fs;
+use std::io;
+use std::collections::{BTreeMap, HashMap};
+use std::io::{Write};
+use std::path::{Path, PathBuf};
+
+use utility;
+use super::{Entity, TranslationUnit, Usr};
+use super::highlight::{self, SemanticKind};
+use super::source::{File};
+use super::token::{Trivia, TriviaKind};
+
+/// The stylesheet shared by the pages of a site.
+const STYLE: &'static str = "\
+body { font-family: sans-serif; margin: 0; }
+a { color: inherit; text-decoration: none; }
+a:hover { text-decoration: underline; }
+pre { font-family: monospace; margin: 0; }
+table.file { border-collapse: collapse; }
+td.lines { background: #f0f0f0; color: #808080; padding: 0 8px; text-align: right; }
+td.code { padding: 0 8px; }
+:target { background: #ffff80; }
+.comment { color: #808080; }
+.keyword { color: #0000c0; font-weight: bold; }
+.string { color: #008000; }
+.number { color: #c000c0; }
+.macro { color: #804000; }
+.type, .namespace, .template-parameter { color: #008080; }
+.function, .method { color: #000080; }
+.field, .enum-constant { color: #800080; }
+.local-variable, .parameter { color: #404040; }
+.declaration { font-weight: bold; }
+.deprecated { text-decoration: line-through; }
+";
+
+//================================================
+// Enums
+//================================================
+
+// Segment _______________________________________
+
+#[derive(Clone, Debug)]
+enum Segment {
+    Html(String),
+    Symbol { symbol: usize, classes: String, text: String, definition: bool },
+}
+
+//================================================
+// Structs
+//================================================
+
+// Page __________________________________________
+
+#[derive(Clone, Debug)]
+struct Page {
+    path: PathBuf,
+    lines: usize,
+    segments: Vec<Segment>,
+}
+
+// Position ______________________________________
+
+/// A line and column in a source file.
+#[derive(Clone, Debug, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct Position {
+    /// The path of the source file.
+    pub path: PathBuf,
+    /// The line in the source file.
+    pub line: u32,
+    /// The column in the source file.
+    pub column: u32,
+}
+
+// Reference _____________________________________
+
+/// A reference to a symbol.
+#[derive(Clone, Debug, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct Reference {
+    /// The position of the reference.
+    pub position: Position,
+    /// The line the reference is on with leading and trailing whitespace removed.
+    pub text: String,
+}
+
+// Site __________________________________________
+
+/// A static HTML site for browsing the source files of a set of translation units.
+///
+/// Each source file under the root directory of a site gets a page where every identifier that
+/// refers to a symbol links to the definition of that symbol (or the declaration, if the definition
+/// was not found) and has a tooltip that contains the type and documentation of that symbol.
+/// Definitions instead link to the references page for the symbol, which lists every reference to
+/// that symbol in every page. Symbols are identified by their USRs so references in different
+/// translation units are linked to the same definition.
+#[derive(Clone, Debug)]
+pub struct Site {
+    root: PathBuf,
+    pages: BTreeMap<PathBuf, Page>,
+    symbols: Vec<Symbol>,
+    usrs: HashMap<Usr, usize>,
+}
+
+impl Site {
+    //- Constructors -----------------------------
+
+    /// Constructs a new `Site` for the source files in the supplied root directory.
+    pub fn new<P: Into<PathBuf>>(root: P) -> Site {
+        Site { root: root.into(), pages: BTreeMap::new(), symbols: vec![], usrs: HashMap::new() }
+    }
+
+    //- Accessors --------------------------------
+
+    fn get_page_path(&self, path: &Path) -> Option<PathBuf> {
+        path.strip_prefix(&self.root).ok().map(|p| {
+            let mut page = p.as_os_str().to_owned();
+            page.push(".html");
+            page.into()
+        })
+    }
+
+    fn get_position_url(&self, prefix: &str, position: &Position) -> Option<String> {
+        self.get_page_path(&position.path).and_then(|p| {
+            if self.pages.contains_key(&p) {
+                Some(format!("{}{}#L{}", prefix, get_url(&p), position.line))
+            } else {
+                None
+            }
+        })
+    }
+
+    fn get_relative_path(&self, path: &Path) -> String {
+        get_url(path.strip_prefix(&self.root).unwrap_or(path))
+    }
+
+    /// Returns the symbol with the supplied USR, if any.
+    pub fn get_symbol(&self, usr: &Usr) -> Option<&Symbol> {
+        self.usrs.get(usr).map(|i| &self.symbols[*i])
+    }
+
+    /// Returns the symbols referenced in the pages of this site.
+    pub fn get_symbols(&self) -> &[Symbol] {
+        &self.symbols
+    }
+
+    /// Returns the pages of this site as a map from paths relative to the output directory to page
+    /// contents.
+    ///
+    /// The pages consist of an `index.html` page that lists the source files, one page per source
+    /// file (e.g., `src/main.cpp.html`), one references page per symbol (e.g., `refs/0.html`), and
+    /// a `style.css` stylesheet.
+    pub fn render(&self) -> BTreeMap<PathBuf, String> {
+        let mut pages = BTreeMap::new();
+
+        let mut index = get_header("Index", "");
+        index.push_str("<h1>Index</h1>\n<ul class=\"files\">\n");
+        for (path, page) in &self.pages {
+            let name = escape(&self.get_relative_path(&page.path));
+            index.push_str(&format!("<li><a href=\"{}\">{}</a></li>\n", get_url(path), name));
+            pages.insert(path.clone(), self.render_page(path, page));
+        }
+        index.push_str("</ul>\n</body>\n</html>\n");
+        pages.insert("index.html".into(), index);
+
+        for (i, symbol) in self.symbols.iter().enumerate() {
+            pages.insert(format!("refs/{}.html", i).into(), self.render_references(symbol));
+        }
+
+        pages.insert("style.css".into(), STYLE.into());
+        pages
+    }
+
+    fn render_page(&self, path: &Path, page: &Page) -> String {
+        let prefix = get_prefix(path);
+
+        let mut html = get_header(&escape(&self.get_relative_path(&page.path)), &prefix);
+        html.push_str("<table class=\"file\"><tr><td class=\"lines\"><pre>");
+        for line in 1..page.lines + 1 {
+            html.push_str(&format!("<a id=\"L{0}\" href=\"#L{0}\">{0}</a>\n", line));
+        }
+        html.push_str("</pre></td><td class=\"code\"><pre class=\"source\">");
+        for segment in &page.segments {
+            match *segment {
+                Segment::Html(ref text) => html.push_str(text),
+                Segment::Symbol { symbol, ref classes, ref text, definition } => {
+                    let references = format!("{}refs/{}.html", prefix, symbol);
+                    let symbol = &self.symbols[symbol];
+                    let target = symbol.definition.as_ref().or(symbol.declaration.as_ref());
+                    let href = match target {
+                        Some(target) if !definition =>
+                            self.get_position_url(&prefix, target).unwrap_or(references),
+                        _ => references,
+                    };
+                    let title = [&symbol.type_, &symbol.documentation].iter().filter_map(|s| {
+                        s.as_ref().map(|s| &s[..])
+                    }).collect::<Vec<_>>().join("\n\n");
+                    html.push_str(&format!(
+                        "<a class=\"{}\" href=\"{}\" title=\"{}\">{}</a>",
+                        classes,
+                        href,
+                        escape(&title),
+                        text,
+                    ));
+                },
+            }
+        }
+        html.push_str("</pre></td></tr></table>\n</body>\n</html>\n");
+        html
+    }
+
+    fn render_references(&self, symbol: &Symbol) -> String {
+        let position = |position: &Position| {
+            let name = format!(
+                "{}:{}:{}", self.get_relative_path(&position.path), position.line, position.column
+            );
+            match self.get_position_url("../", position) {
+                Some(url) => format!("<a href=\"{}\">{}</a>", url, escape(&name)),
+                None => escape(&name),
+            }
+        };
+
+        let name = escape(&symbol.name);
+        let mut html = get_header(&name, "../");
+        html.push_str(&format!("<h1 class=\"{}\">{}</h1>\n", symbol.kind.get_css_class(), name));
+        if let Some(ref type_) = symbol.type_ {
+            html.push_str(&format!("<pre class=\"type\">{}</pre>\n", escape(type_)));
+        }
+        if let Some(ref documentation) = symbol.documentation {
+            let documentation = escape(documentation);
+            html.push_str(&format!("<pre class=\"documentation\">{}</pre>\n", documentation));
+        }
+        if let Some(ref declaration) = symbol.declaration {
+            html.push_str(&format!("<p>Declaration: {}</p>\n", position(declaration)));
+        }
+        if let Some(ref definition) = symbol.definition {
+            html.push_str(&format!("<p>Definition: {}</p>\n", position(definition)));
+        }
+        html.push_str("<h2>References</h2>\n<ul class=\"references\">\n");
+        for reference in &symbol.references {
+            let text = escape(&reference.text);
+            let position = position(&reference.position);
+            html.push_str(&format!("<li>{} <code>{}</code></li>\n", position, text));
+        }
+        html.push_str("</ul>\n</body>\n</html>\n");
+        html
+    }
+
+    /// Writes the pages of this site to the supplied output directory, creating directories as
+    /// needed.
+    ///
+    /// # Failures
+    ///
+    /// * an I/O error occurs while creating a directory or writing a page
+    pub fn write<P: AsRef<Path>>(&self, directory: P) -> io::Result<()> {
+        for (path, contents) in self.render() {
+            let path = directory.as_ref().join(path);
+            if let Some(parent) = path.parent() {
+                try!(fs::create_dir_all(parent));
+            }
+            try!(try!(fs::File::create(path)).write_all(contents.as_bytes()));
+        }
+        Ok(())
+    }
+
+    //- Mutators ---------------------------------
+
+    /// Adds the source files in the supplied translation unit to this site.
+    ///
+    /// Only the source files under the root directory of this site that are reachable from the
+    /// main file of the translation unit through inclusion directives are added. Source files
+    /// which were added by a previous translation unit are not added again.
+    ///
+    /// # Failures
+    ///
+    /// * an I/O error occurs while reading a source file
+    /// * a source file is not valid UTF-8
+    pub fn add<'tu>(&mut self, tu: &'tu TranslationUnit<'tu>) -> io::Result<()> {
+        let main = tu.get_entity().get_name().and_then(|n| tu.get_file(n));
+        let mut files = main.into_iter().collect::<Vec<_>>();
+        while let Some(file) = files.pop() {
+            let path = match self.get_page_path(&file.get_path()) {
+                Some(path) => if self.pages.contains_key(&path) { continue } else { path },
+                None => continue,
+            };
+            try!(self.add_file(tu, file, path));
+            files.extend(file.get_includes().into_iter().filter_map(|i| i.get_file()));
+        }
+        Ok(())
+    }
+
+    fn add_file<'tu>(
+        &mut self, tu: &'tu TranslationUnit<'tu>, file: File<'tu>, page: PathBuf
+    ) -> io::Result<()> {
+        let path = file.get_path();
+        let contents = try!(utility::read_file(&path));
+        let lines = contents.lines().collect::<Vec<_>>();
+
+        let stream = file.tokenize_contents(&contents);
+        let tokens = stream.tokens.iter().map(|t| t.token).collect::<Vec<_>>();
+        let tokens = highlight::classify(tu, &tokens).into_iter().map(|t| {
+            (t.token.get_location().get_file_location().offset as usize, t)
+        }).collect::<HashMap<_, _>>();
+
+        let mut pieces = vec![];
+        for token in &stream.tokens {
+            pieces.extend(token.leading.iter().map(get_piece));
+            pieces.push((token.offset, &token.text[..], false));
+            pieces.extend(token.trailing.iter().map(get_piece));
+        }
+        pieces.extend(stream.trailing.iter().map(get_piece));
+
+        let mut segments = vec![];
+        let mut entities = HashMap::new();
+        for (offset, text, comment) in pieces {
+            let text = escape(text);
+            let token = match tokens.get(&offset) {
+                Some(token) => token,
+                None if comment => {
+                    let html = format!("<span class=\"comment\">{}</span>", text);
+                    segments.push(Segment::Html(html));
+                    continue;
+                },
+                None => {
+                    segments.push(Segment::Html(text));
+                    continue;
+                },
+            };
+
+            let classes = token.get_css_classes();
+            match token.entity.and_then(|e| e.get_usr().map(|u| (e, u))) {
+                Some((entity, usr)) => {
+                    let symbol = self.add_symbol(entity, usr, token.kind);
+                    entities.insert(symbol, entity);
+                    let definition = token.modifiers.declaration && entity.is_definition();
+                    segments.push(Segment::Symbol {
+                        symbol: symbol, classes: classes, text: text, definition: definition
+                    });
+                },
+                None => {
+                    let html = format!("<span class=\"{}\">{}</span>", classes, text);
+                    segments.push(Segment::Html(html));
+                },
+            }
+        }
+
+        for (symbol, entity) in entities {
+            let references = &mut self.symbols[symbol].references;
+            file.visit_references(entity, |_, r| {
+                let location = r.get_start().get_file_location();
+                let text = lines.get(location.line as usize - 1).map_or("", |l| l.trim()).into();
+                let position = Position {
+                    path: path.clone(), line: location.line, column: location.column
+                };
+                references.push(Reference { position: position, text: text });
+                true
+            });
+            references.sort();
+            references.dedup();
+        }
+
+        self.pages.insert(page, Page { path: path, lines: lines.len(), segments: segments });
+        Ok(())
+    }
+
+    fn add_symbol(&mut self, entity: Entity, usr: Usr, kind: SemanticKind) -> usize {
+        let index = match self.usrs.get(&usr) {
+            Some(index) => *index,
+            None => {
+                self.symbols.push(Symbol {
+                    usr: usr.clone(),
+                    name: entity.get_name().unwrap_or_else(|| usr.0.clone()),
+                    kind: kind,
+                    type_: None,
+                    documentation: None,
+                    declaration: None,
+                    definition: None,
+                    references: vec![],
+                });
+                self.usrs.insert(usr, self.symbols.len() - 1);
+                self.symbols.len() - 1
+            },
+        };
+
+        let symbol = &mut self.symbols[index];
+        if symbol.type_.is_none() {
+            symbol.type_ = entity.get_type().map(|t| t.get_display_name());
+        }
+        if symbol.documentation.is_none() {
+            let comment = entity.get_parsed_comment().map(|c| c.as_text());
+            symbol.documentation = comment.and_then(|c| if c.is_empty() { None } else { Some(c) });
+        }
+        if symbol.declaration.is_none() {
+            symbol.declaration = get_position(entity.get_canonical_entity());
+        }
+        if symbol.definition.is_none() {
+            symbol.definition = entity.get_definition().and_then(get_position);
+        }
+        index
+    }
+}
+
+// Symbol ________________________________________
+
+/// A declaration and the references to it across the pages of a site.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Symbol {
+    /// The USR of the symbol.
+    pub usr: Usr,
+    /// The name of the symbol.
+    pub name: String,
+    /// The semantic categorization of the symbol.
+    pub kind: SemanticKind,
+    /// The type of the symbol, if any.
+    pub type_: Option<String>,
+    /// The documentation comment of the symbol rendered as plain text, if any.
+    pub documentation: Option<String>,
+    /// The position of the canonical declaration of the symbol, if any.
+    pub declaration: Option<Position>,
+    /// The position of the definition of the symbol, if any.
+    pub definition: Option<Position>,
+    /// The references to the symbol, sorted by position.
+    pub references: Vec<Reference>,
+}
+
+//================================================
+// Functions
+//================================================
+
+fn escape(text: &str) -> String {
+    let mut html = String::with_capacity(text.len());
+    utility::escape_html(text, &mut html);
+    html
+}
+
+fn get_header(title: &str, prefix: &str) -> String {
+    format!(
+        "<!DOCTYPE html>\n<html>\n<head>\n<meta charset=\"utf-8\">\n<title>{}</title>\n\
+         <link rel=\"stylesheet\" href=\"{}style.css\">\n</head>\n<body>\n",
+        title,
+        prefix,
+    )
+}
+
+fn get_piece(trivia: &Trivia) -> (usize, &str, bool) {
+    (trivia.offset, &trivia.text, trivia.kind == TriviaKind::Comment)
+}
+
+fn get_position(entity: Entity) -> Option<Position> {
+    entity.get_location().map(|l| {
+        let location = l.get_file_location();
+        Position { path: location.file.get_path(), line: location.line, column: location.column }
+    })
+}
+
+fn get_prefix(path: &Path) -> String {
+    (1..path.components().count()).map(|_| "../").collect()
+}
+
+fn get_url(path: &Path) -> String {
+    let components = path.components().map(|c| c.as_os_str().to_string_lossy().into_owned());
+    components.collect::<Vec<_>>().join("/")
+}
@@ -0,0 +1,364 @@
+// Copyright 2016 Kyle Mayes
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Call graphs constructed from function bodies.
+
+use std::collections::{BTreeMap, BTreeSet};
+use std::path::{PathBuf};
+
+use rustc_serialize::json::{Json, ToJson};
+
+use utility;
+use super::{Entity, EntityKind, EntityVisitResult, TranslationUnit, Usr};
+
+//================================================
+// Enums
+//================================================
+
+// CallKind ______________________________________
+
+/// Indicates how a call site calls its callee.
+#[derive(Copy, Clone, Debug, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum CallKind {
+    /// The callee is known statically.
+    Direct,
+    /// The callee is a virtual method which may be overridden.
+    Virtual,
+    /// The callee is called through a function pointer, a member pointer, or another expression
+    /// that does not refer to a function.
+    Indirect,
+}
+
+impl CallKind {
+    //- Accessors --------------------------------
+
+    /// Returns the name of this call kind (e.g., `virtual`).
+    pub fn get_name(&self) -> &'static str {
+        match *self {
+            CallKind::Direct => "direct",
+            CallKind::Virtual => "virtual",
+            CallKind::Indirect => "indirect",
+        }
+    }
+}
+
+//================================================
+// Structs
+//================================================
+
+// Call __________________________________________
+
+/// A call from one function to another.
+#[derive(Clone, Debug, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct Call {
+    /// The USR of the function that contains the call site.
+    pub caller: Usr,
+    /// The USR of the function that is called, if known.
+    ///
+    /// For indirect calls, this is the USR of the variable, parameter, or field the function
+    /// pointer is stored in, if any.
+    pub callee: Option<Usr>,
+    /// How the call site calls its callee.
+    pub kind: CallKind,
+    /// The path of the source file the call site is in.
+    pub path: PathBuf,
+    /// The line of the call site.
+    pub line: u32,
+    /// The column of the call site.
+    pub column: u32,
+}
+
+impl ToJson for Call {
+    fn to_json(&self) -> Json {
+        object!(
+            "callee" => self.callee.as_ref().map(|c| c.0.clone()),
+            "kind" => self.kind.get_name(),
+            "path" => self.path.to_string_lossy().into_owned(),
+            "line" => self.line,
+            "column" => self.column,
+        )
+    }
+}
+
+// CallGraph _____________________________________
+
+/// A graph of the calls between the functions defined in a set of translation units.
+///
+/// Functions are identified by their USRs so calls in one translation unit to a function defined
+/// in another translation unit are connected to that function.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct CallGraph {
+    functions: BTreeMap<Usr, Function>,
+    calls: BTreeMap<Usr, Vec<Call>>,
+}
+
+impl CallGraph {
+    //- Constructors -----------------------------
+
+    /// Constructs a new empty `CallGraph`.
+    pub fn new() -> CallGraph {
+        CallGraph::default()
+    }
+
+    //- Accessors --------------------------------
+
+    /// Returns the calls made by the function with the supplied USR, sorted by position.
+    pub fn get_callees(&self, caller: &Usr) -> &[Call] {
+        self.calls.get(caller).map_or(&[], |c| &c[..])
+    }
+
+    /// Returns the calls made to the function with the supplied USR.
+    pub fn get_callers(&self, callee: &Usr) -> Vec<&Call> {
+        self.get_calls().into_iter().filter(|c| c.callee.as_ref() == Some(callee)).collect()
+    }
+
+    /// Returns all of the calls in this call graph.
+    pub fn get_calls(&self) -> Vec<&Call> {
+        self.calls.values().flat_map(|c| c.iter()).collect()
+    }
+
+    /// Returns the function with the supplied USR, if any.
+    pub fn get_function(&self, usr: &Usr) -> Option<&Function> {
+        self.functions.get(usr)
+    }
+
+    /// Returns the functions in this call graph, sorted by USR.
+    ///
+    /// This includes functions which are called but not defined in any of the translation units.
+    pub fn get_functions(&self) -> Vec<&Function> {
+        self.functions.values().collect()
+    }
+
+    /// Returns the USRs of the functions that can be reached from the functions with the supplied
+    /// USRs, including the functions with the supplied USRs.
+    ///
+    /// Indirect calls are not followed. Virtual calls are followed to the called method but not to
+    /// the methods that override it.
+    pub fn get_reachable(&self, roots: &[Usr]) -> BTreeSet<Usr> {
+        let mut reachable = BTreeSet::new();
+        let mut stack = roots.to_vec();
+        while let Some(usr) = stack.pop() {
+            if reachable.insert(usr.clone()) {
+                let calls = self.get_callees(&usr).iter().filter(|c| c.kind != CallKind::Indirect);
+                stack.extend(calls.filter_map(|c| c.callee.clone()));
+            }
+        }
+        reachable
+    }
+
+    /// Returns the USRs of the functions that can reach the function with the supplied USR,
+    /// excluding the function with the supplied USR unless it is recursive.
+    ///
+    /// These are the functions that may be affected by a change to the function with the supplied
+    /// USR.
+    pub fn get_transitive_callers(&self, callee: &Usr) -> BTreeSet<Usr> {
+        let mut callers = BTreeSet::new();
+        let mut stack = vec![callee.clone()];
+        while let Some(usr) = stack.pop() {
+            for call in self.get_callers(&usr) {
+                if callers.insert(call.caller.clone()) {
+                    stack.push(call.caller.clone());
+                }
+            }
+        }
+        callers
+    }
+
+    /// Returns the functions defined in this call graph that cannot be reached from the functions
+    /// with the supplied USRs (e.g., `main`).
+    pub fn get_unreachable(&self, roots: &[Usr]) -> Vec<&Function> {
+        let reachable = self.get_reachable(roots);
+        self.functions.values().filter(|f| f.defined && !reachable.contains(&f.usr)).collect()
+    }
+
+    /// Returns this call graph in the DOT graph description language.
+    ///
+    /// Virtual calls are rendered as dashed edges and indirect calls are rendered as dotted edges
+    /// to a shared node labeled `(indirect)`.
+    pub fn to_dot(&self) -> String {
+        let mut dot = String::from("digraph calls {\n");
+        for function in self.functions.values() {
+            let shape = if function.defined { "ellipse" } else { "box" };
+            let usr = utility::quote_dot(&function.usr.0);
+            let name = utility::quote_dot(&function.name);
+            dot.push_str(&format!("    {} [label={}, shape={}];\n", usr, name, shape));
+        }
+        if self.get_calls().iter().any(|c| c.kind == CallKind::Indirect) {
+            dot.push_str("    \"(indirect)\" [label=\"(indirect)\", shape=plaintext];\n");
+        }
+
+        let mut edges = BTreeSet::new();
+        for call in self.get_calls() {
+            let callee = match (call.kind, &call.callee) {
+                (CallKind::Indirect, _) | (_, &None) => "(indirect)".into(),
+                (_, &Some(ref callee)) => callee.0.clone(),
+            };
+            edges.insert((&call.caller.0, callee, call.kind));
+        }
+        for (caller, callee, kind) in edges {
+            let style = match kind {
+                CallKind::Direct => "solid",
+                CallKind::Virtual => "dashed",
+                CallKind::Indirect => "dotted",
+            };
+            let (caller, callee) = (utility::quote_dot(caller), utility::quote_dot(&callee));
+            dot.push_str(&format!("    {} -> {} [style={}];\n", caller, callee, style));
+        }
+        dot.push_str("}\n");
+        dot
+    }
+
+    //- Mutators ---------------------------------
+
+    /// Adds the functions defined in the supplied translation unit and the calls they make to this
+    /// call graph.
+    ///
+    /// Functions defined in system headers are not added.
+    pub fn add<'tu>(&mut self, tu: &'tu TranslationUnit<'tu>) {
+        tu.get_entity().visit_children(|e, _| {
+            if e.is_in_system_header() {
+                EntityVisitResult::Continue
+            } else if is_function(e.get_kind()) {
+                if e.is_definition() {
+                    self.add_function(e);
+                }
+                EntityVisitResult::Continue
+            } else {
+                EntityVisitResult::Recurse
+            }
+        });
+    }
+
+    fn add_function(&mut self, entity: Entity) {
+        let caller = match self.add_node(entity, true) {
+            Some(caller) => caller,
+            None => return,
+        };
+
+        let mut calls = vec![];
+        entity.visit_children(|e, _| {
+            if e.get_kind() == EntityKind::CallExpr {
+                calls.push(e);
+            }
+            EntityVisitResult::Recurse
+        });
+
+        let calls = calls.into_iter().filter_map(|c| {
+            let location = match c.get_location() {
+                Some(location) => location.get_file_location(),
+                None => return None,
+            };
+
+            let reference = c.get_reference();
+            let (callee, kind) = match reference {
+                Some(reference) if is_function(reference.get_kind()) => {
+                    let dynamic = c.is_dynamic_call();
+                    let kind = if dynamic { CallKind::Virtual } else { CallKind::Direct };
+                    (self.add_node(reference, false), kind)
+                },
+                _ => (reference.and_then(|r| r.get_usr()), CallKind::Indirect),
+            };
+
+            Some(Call {
+                caller: caller.clone(),
+                callee: callee,
+                kind: kind,
+                path: location.file.get_path(),
+                line: location.line,
+                column: location.column,
+            })
+        }).collect::<Vec<_>>();
+
+        let existing = self.calls.entry(caller).or_insert_with(Vec::new);
+        existing.extend(calls);
+        existing.sort_by(|a, b| (&a.path, a.line, a.column).cmp(&(&b.path, b.line, b.column)));
+        existing.dedup();
+    }
+
+    fn add_node(&mut self, entity: Entity, defined: bool) -> Option<Usr> {
+        entity.get_usr().map(|usr| {
+            let function = self.functions.entry(usr.clone()).or_insert_with(|| Function {
+                usr: usr.clone(),
+                name: entity.get_display_name().unwrap_or_else(String::new),
+                defined: false,
+                path: None,
+            });
+
+            if defined && !function.defined {
+                function.defined = true;
+                let location = entity.get_location().map(|l| l.get_file_location());
+                function.path = location.map(|l| l.file.get_path());
+            }
+
+            usr
+        })
+    }
+}
+
+impl ToJson for CallGraph {
+    /// Returns this call graph as a JSON object.
+    ///
+    /// The `functions` member maps USRs to objects with `name`, `defined`, and `path` members and
+    /// the `calls` member is an adjacency list that maps the USRs of callers to arrays of call
+    /// objects with `callee`, `kind`, `path`, `line`, and `column` members.
+    fn to_json(&self) -> Json {
+        let functions = self.functions.iter().map(|(u, f)| (u.0.clone(), f.to_json()));
+        let calls = self.calls.iter().map(|(u, c)| (u.0.clone(), c.to_json()));
+        object!(
+            "functions" => functions.collect::<BTreeMap<_, _>>(),
+            "calls" => calls.collect::<BTreeMap<_, _>>(),
+        )
+    }
+}
+
+// Function ______________________________________
+
+/// A function in a call graph.
+#[derive(Clone, Debug, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct Function {
+    /// The USR of the function.
+    pub usr: Usr,
+    /// The display name of the function (e.g., `add(int, int)`).
+    pub name: String,
+    /// Whether the function is defined in one of the translation units in the call graph.
+    pub defined: bool,
+    /// The path of the source file the function is defined in, if it is defined.
+    pub path: Option<PathBuf>,
+}
+
+impl ToJson for Function {
+    fn to_json(&self) -> Json {
+        object!(
+            "name" => self.name,
+            "defined" => self.defined,
+            "path" => self.path.as_ref().map(|p| p.to_string_lossy().into_owned()),
+        )
+    }
+}
+
+//================================================
+// Functions
+//================================================
+
+fn is_function(kind: EntityKind) -> bool {
+    match kind {
+        EntityKind::FunctionDecl |
+        EntityKind::FunctionTemplate |
+        EntityKind::Method |
+        EntityKind::Constructor |
+        EntityKind::Destructor |
+        EntityKind::ConversionFunction => true,
+        _ => false,
+    }
+}
@@ -28,6 +28,8 @@ use libc::{c_uint};
 
 use utility;
 use super::{Availability, EntityKind, TranslationUnit, Unsaved, Usr};
+#[cfg(feature="gte_clang_3_7")]
+use super::{Entity, EntityVisitResult};
 use super::diagnostic::{Diagnostic};
 
 //================================================
@@ -123,6 +125,81 @@ impl<'r> CompletionChunk<'r> {
     }
 }
 
+// CompletionItemKind ____________________________
+
+/// Indicates the categorization of a completion item as defined by the Language Server Protocol.
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash)]
+pub enum CompletionItemKind {
+    /// Plain text (e.g., a macro or a keyword).
+    Text = 1,
+    /// A method.
+    Method = 2,
+    /// A function.
+    Function = 3,
+    /// A constructor or destructor.
+    Constructor = 4,
+    /// A field.
+    Field = 5,
+    /// A variable or parameter.
+    Variable = 6,
+    /// A class or type alias.
+    Class = 7,
+    /// An interface (e.g., an Objective-C protocol).
+    Interface = 8,
+    /// A module or namespace.
+    Module = 9,
+    /// A property.
+    Property = 10,
+    /// An enum.
+    Enum = 13,
+    /// A keyword.
+    Keyword = 14,
+    /// A code pattern.
+    Snippet = 15,
+    /// An enum constant.
+    EnumMember = 20,
+    /// A struct or union.
+    Struct = 22,
+    /// A template parameter.
+    TypeParameter = 25,
+}
+
+impl CompletionItemKind {
+    //- Constructors -----------------------------
+
+    /// Constructs a new `CompletionItemKind` from the categorization of an AST entity.
+    pub fn from_entity_kind(kind: EntityKind) -> CompletionItemKind {
+        match kind {
+            EntityKind::StructDecl | EntityKind::UnionDecl => CompletionItemKind::Struct,
+            EntityKind::ClassDecl |
+            EntityKind::ClassTemplate |
+            EntityKind::ClassTemplatePartialSpecialization |
+            EntityKind::ObjCInterfaceDecl |
+            EntityKind::ObjCImplementationDecl |
+            EntityKind::TypedefDecl |
+            EntityKind::TypeAliasDecl => CompletionItemKind::Class,
+            EntityKind::EnumDecl => CompletionItemKind::Enum,
+            EntityKind::EnumConstantDecl => CompletionItemKind::EnumMember,
+            EntityKind::FieldDecl | EntityKind::ObjCIvarDecl => CompletionItemKind::Field,
+            EntityKind::FunctionDecl |
+            EntityKind::FunctionTemplate |
+            EntityKind::ConversionFunction => CompletionItemKind::Function,
+            EntityKind::Method |
+            EntityKind::ObjCInstanceMethodDecl |
+            EntityKind::ObjCClassMethodDecl => CompletionItemKind::Method,
+            EntityKind::Constructor | EntityKind::Destructor => CompletionItemKind::Constructor,
+            EntityKind::VarDecl | EntityKind::ParmDecl => CompletionItemKind::Variable,
+            EntityKind::Namespace | EntityKind::NamespaceAlias => CompletionItemKind::Module,
+            EntityKind::TemplateTypeParameter |
+            EntityKind::NonTypeTemplateParameter |
+            EntityKind::TemplateTemplateParameter => CompletionItemKind::TypeParameter,
+            EntityKind::ObjCPropertyDecl => CompletionItemKind::Property,
+            EntityKind::ObjCProtocolDecl => CompletionItemKind::Interface,
+            _ => CompletionItemKind::Text,
+        }
+    }
+}
+
 //================================================
 // Structs
 //================================================
@@ -185,6 +262,23 @@ impl<'tu> Completer<'tu> {
             CompletionResults::from_ptr(ptr)
         }
     }
+
+    /// Runs code completion and returns the signatures of the overloads that are candidates for the
+    /// function call that contains the code completion location.
+    ///
+    /// Signatures without a documentation comment brief are given the documentation comment brief
+    /// of a matching declaration in the translation unit, if any.
+    #[cfg(feature="gte_clang_3_7")]
+    pub fn signature_help(&self) -> SignatureHelp {
+        let results = self.complete();
+        let mut help = results.get_signature_help();
+        for signature in &mut help.signatures {
+            if signature.documentation.is_none() {
+                signature.documentation = find_brief(self.tu, signature);
+            }
+        }
+        help
+    }
 }
 
 // CompletionContext _____________________________
@@ -242,6 +336,30 @@ options! {
     }
 }
 
+// CompletionItem ________________________________
+
+/// A code completion result rendered for display and insertion by an editor.
+///
+/// The fields of this struct correspond to those of a Language Server Protocol completion item.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct CompletionItem {
+    /// The text that identifies this completion item (e.g., `f(int a, int b)`).
+    pub label: String,
+    /// The result type of this completion item, if any.
+    pub detail: Option<String>,
+    /// The documentation comment brief for this completion item, if any.
+    pub documentation: Option<String>,
+    /// The categorization of this completion item.
+    pub kind: CompletionItemKind,
+    /// The text that should be used to filter this completion item, if any.
+    pub filter_text: Option<String>,
+    /// The snippets that may be inserted for this completion item.
+    ///
+    /// The first snippet contains only the required chunks. Each subsequent snippet also contains
+    /// the optional chunks nested one level deeper than those in the previous snippet.
+    pub snippets: Vec<String>,
+}
+
 // CompletionResult ______________________________
 
 /// A code completion result.
@@ -260,6 +378,49 @@ impl<'r> CompletionResult<'r> {
         let kind = unsafe { mem::transmute(raw.CursorKind) };
         CompletionResult { kind: kind, string: CompletionString::from_raw(raw.CompletionString) }
     }
+
+    //- Accessors --------------------------------
+
+    /// Returns this code completion result rendered as a completion item.
+    pub fn as_item(&self) -> CompletionItem {
+        let chunks = self.string.get_chunks();
+        let detail = chunks.iter().filter_map(|c| {
+            match *c {
+                CompletionChunk::ResultType(ref text) => Some(text.clone()),
+                _ => None,
+            }
+        }).next();
+        CompletionItem {
+            label: self.string.get_label(),
+            detail: detail,
+            documentation: self.string.get_comment_brief(),
+            kind: CompletionItemKind::from_entity_kind(self.kind),
+            filter_text: self.string.get_typed_text(),
+            snippets: self.string.get_snippets(),
+        }
+    }
+
+    /// Returns this code completion result rendered as a signature if it is an overload candidate.
+    #[cfg(feature="gte_clang_3_7")]
+    pub fn as_signature(&self) -> Option<Signature> {
+        if self.kind != EntityKind::OverloadCandidate {
+            return None;
+        }
+
+        let mut signature = Signature {
+            label: String::new(),
+            name: String::new(),
+            result_type: None,
+            parameters: vec![],
+            active_parameter: None,
+            documentation: self.string.get_comment_brief(),
+        };
+        render_signature(&self.string, &mut signature);
+        if let Some(ref result_type) = signature.result_type {
+            signature.label = format!("{} {}", result_type, signature.label);
+        }
+        Some(signature)
+    }
 }
 
 impl<'r> cmp::PartialOrd for CompletionResult<'r> {
@@ -340,6 +501,15 @@ impl CompletionResults {
             raws.iter().cloned().map(CompletionResult::from_raw).collect()
         }
     }
+
+    /// Returns the signatures of the overload candidates in this set of code completion results.
+    #[cfg(feature="gte_clang_3_7")]
+    pub fn get_signature_help(&self) -> SignatureHelp {
+        let results = self.get_results();
+        let signatures = results.iter().filter_map(|r| r.as_signature()).collect::<Vec<_>>();
+        let active = signatures.iter().position(|s| s.active_parameter.is_some());
+        SignatureHelp { signatures: signatures, active_signature: active }
+    }
 }
 
 impl Drop for CompletionResults {
@@ -406,6 +576,36 @@ impl<'r> CompletionString<'r> {
         unsafe { utility::to_string_option(clang_getCompletionParent(self.raw, ptr::null_mut())) }
     }
 
+    /// Returns the text that identifies this completion string in a list of completion strings.
+    ///
+    /// The label contains the text of every required chunk except the result type chunk.
+    pub fn get_label(&self) -> String {
+        let mut label = String::new();
+        for chunk in self.get_chunks() {
+            match chunk {
+                CompletionChunk::Comma => label.push_str(", "),
+                CompletionChunk::Optional(_) | CompletionChunk::ResultType(_) => { },
+                CompletionChunk::VerticalSpace(_) => label.push(' '),
+                other => label.push_str(&other.get_text().unwrap()),
+            }
+        }
+        label
+    }
+
+    /// Returns the snippets that may be inserted for this completion string.
+    ///
+    /// Placeholders are rendered as numbered tab stops (e.g., `${1:int a}`) and the other chunks
+    /// are rendered as escaped text. The first snippet contains only the required chunks. Each
+    /// subsequent snippet also contains the optional chunks nested one level deeper than those in
+    /// the previous snippet.
+    pub fn get_snippets(&self) -> Vec<String> {
+        (0..get_optional_depth(self) + 1).map(|d| {
+            let mut snippet = String::new();
+            render_snippet(self, d, &mut 0, &mut snippet);
+            snippet
+        }).collect()
+    }
+
     /// Returns the text of the typed text chunk for this completion string, if any.
     pub fn get_typed_text(&self) -> Option<String> {
         for chunk in self.get_chunks() {
@@ -490,3 +690,163 @@ impl<'r> cmp::Ord for CompletionString<'r> {
         }
     }
 }
+
+// Signature _____________________________________
+
+/// A signature of an overload that is a candidate for a function call.
+#[cfg(feature="gte_clang_3_7")]
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Signature {
+    /// The text that identifies this signature (e.g., `int f(int a, int b)`).
+    pub label: String,
+    /// The name of the function this signature belongs to.
+    pub name: String,
+    /// The result type of this signature, if any.
+    pub result_type: Option<String>,
+    /// The parameters of this signature (e.g., `int a`), including any optional parameters.
+    pub parameters: Vec<String>,
+    /// The index of the parameter that corresponds to the argument being completed, if any.
+    pub active_parameter: Option<usize>,
+    /// The documentation comment brief for this signature, if any.
+    pub documentation: Option<String>,
+}
+
+// SignatureHelp _________________________________
+
+/// The signatures of the overloads that are candidates for a function call.
+#[cfg(feature="gte_clang_3_7")]
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct SignatureHelp {
+    /// The signatures of the candidate overloads.
+    pub signatures: Vec<Signature>,
+    /// The index of the first signature which has a parameter that corresponds to the argument
+    /// being completed, if any.
+    pub active_signature: Option<usize>,
+}
+
+#[cfg(feature="gte_clang_3_7")]
+impl SignatureHelp {
+    //- Accessors --------------------------------
+
+    /// Returns the index of the active parameter of the active signature, if any.
+    pub fn get_active_parameter(&self) -> Option<usize> {
+        self.active_signature.and_then(|s| self.signatures[s].active_parameter)
+    }
+}
+
+//================================================
+// Functions
+//================================================
+
+fn escape_snippet(text: &str, snippet: &mut String) {
+    for c in text.chars() {
+        if c == '$' || c == '}' || c == '\\' {
+            snippet.push('\\');
+        }
+        snippet.push(c);
+    }
+}
+
+#[cfg(feature="gte_clang_3_7")]
+fn find_brief(tu: &TranslationUnit, signature: &Signature) -> Option<String> {
+    let mut brief = None;
+    tu.get_entity().visit_children(|e, _| {
+        match e.get_kind() {
+            EntityKind::FunctionDecl |
+            EntityKind::Method |
+            EntityKind::Constructor |
+            EntityKind::FunctionTemplate => {
+                let name = e.get_name();
+                if name.as_ref() == Some(&signature.name) && is_signature_match(&e, signature) {
+                    brief = e.get_comment_brief();
+                    if brief.is_some() {
+                        return EntityVisitResult::Break;
+                    }
+                }
+                EntityVisitResult::Continue
+            },
+            _ => EntityVisitResult::Recurse,
+        }
+    });
+    brief
+}
+
+fn get_optional_depth(string: &CompletionString) -> usize {
+    string.get_chunks().iter().filter_map(|c| {
+        match *c {
+            CompletionChunk::Optional(ref optional) => Some(get_optional_depth(optional) + 1),
+            _ => None,
+        }
+    }).max().unwrap_or(0)
+}
+
+#[cfg(feature="gte_clang_3_7")]
+fn is_signature_match(entity: &Entity, signature: &Signature) -> bool {
+    let arguments = match entity.get_arguments() {
+        Some(arguments) => arguments,
+        None => return true,
+    };
+
+    if arguments.len() != signature.parameters.len() {
+        return false;
+    }
+
+    arguments.iter().zip(signature.parameters.iter()).all(|(a, p)| {
+        let type_ = a.get_type().map_or(String::new(), |t| t.get_display_name());
+        let expected = match a.get_name() {
+            Some(name) => format!("{}{}", type_, name),
+            None => type_,
+        };
+        let parameter = p.chars().filter(|c| !c.is_whitespace()).collect::<String>();
+        let expected = expected.chars().filter(|c| !c.is_whitespace()).collect::<String>();
+        parameter == expected || parameter.starts_with(&format!("{}=", expected))
+    })
+}
+
+#[cfg(feature="gte_clang_3_7")]
+fn render_signature(string: &CompletionString, signature: &mut Signature) {
+    for chunk in string.get_chunks() {
+        match chunk {
+            CompletionChunk::Optional(ref optional) => render_signature(optional, signature),
+            CompletionChunk::CurrentParameter(text) => {
+                signature.active_parameter = Some(signature.parameters.len());
+                signature.label.push_str(&text);
+                signature.parameters.push(text);
+            },
+            CompletionChunk::Placeholder(text) => {
+                signature.label.push_str(&text);
+                signature.parameters.push(text);
+            },
+            CompletionChunk::ResultType(text) => signature.result_type = Some(text),
+            CompletionChunk::Text(text) | CompletionChunk::TypedText(text) => {
+                if signature.name.is_empty() {
+                    signature.name = text.clone();
+                }
+                signature.label.push_str(&text);
+            },
+            CompletionChunk::Comma => signature.label.push_str(", "),
+            CompletionChunk::VerticalSpace(_) => signature.label.push(' '),
+            other => signature.label.push_str(&other.get_text().unwrap()),
+        }
+    }
+}
+
+fn render_snippet(string: &CompletionString, depth: usize, stop: &mut usize, snippet: &mut String) {
+    for chunk in string.get_chunks() {
+        match chunk {
+            CompletionChunk::Optional(ref optional) => if depth != 0 {
+                render_snippet(optional, depth - 1, stop, snippet);
+            },
+            CompletionChunk::CurrentParameter(ref text) |
+            CompletionChunk::Placeholder(ref text) => {
+                *stop += 1;
+                snippet.push_str(&format!("${{{}:", stop));
+                escape_snippet(text, snippet);
+                snippet.push('}');
+            },
+            CompletionChunk::Informative(_) | CompletionChunk::ResultType(_) => { },
+            CompletionChunk::Comma => snippet.push_str(", "),
+            other => escape_snippet(&other.get_text().unwrap(), snippet),
+        }
+    }
+}
@@ -0,0 +1,779 @@
+// Copyright 2016 Kyle Mayes
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Statement-level control flow graphs constructed from function bodies.
+
+use std::mem;
+use std::collections::{BTreeSet, HashMap};
+
+use utility;
+use super::{Entity, EntityKind};
+use super::expression::{self, Literal};
+use super::source::{SourceLocation};
+
+/// The index of the entry block of a control flow graph.
+const ENTRY: usize = 0;
+
+/// The index of the exit block of a control flow graph.
+const EXIT: usize = 1;
+
+//================================================
+// Enums
+//================================================
+
+// EdgeKind ______________________________________
+
+/// Indicates when control flows along an edge in a control flow graph.
+#[derive(Copy, Clone, Debug, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum EdgeKind {
+    /// Control always flows along the edge.
+    Unconditional,
+    /// Control flows along the edge when the condition of the terminator is true.
+    True,
+    /// Control flows along the edge when the condition of the terminator is false.
+    False,
+    /// Control flows along the edge from a `switch` statement to one of its `case` statements.
+    Case,
+    /// Control flows along the edge from a `switch` statement to its `default` statement or, if it
+    /// has none, to the statement that follows it.
+    Default,
+    /// Control flows along the edge when an exception is thrown.
+    Exception,
+}
+
+impl EdgeKind {
+    //- Accessors --------------------------------
+
+    /// Returns the name of this edge kind (e.g., `true`).
+    pub fn get_name(&self) -> &'static str {
+        match *self {
+            EdgeKind::Unconditional => "unconditional",
+            EdgeKind::True => "true",
+            EdgeKind::False => "false",
+            EdgeKind::Case => "case",
+            EdgeKind::Default => "default",
+            EdgeKind::Exception => "exception",
+        }
+    }
+}
+
+//================================================
+// Structs
+//================================================
+
+// Block _________________________________________
+
+/// A basic block in a control flow graph.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Block<'tu> {
+    /// The label, `case`, `default`, or `catch` statement that begins this block, if any.
+    pub label: Option<Entity<'tu>>,
+    /// The statements executed in this block, in order.
+    ///
+    /// The conditions of `if`, `switch`, and loop statements are included as statements in the
+    /// block the statement terminates.
+    pub statements: Vec<Entity<'tu>>,
+    /// The statement which determines the successor control flows to, if any.
+    pub terminator: Option<Entity<'tu>>,
+    /// The edges to the blocks control may flow to after this block.
+    pub successors: Vec<Edge>,
+}
+
+// Builder _______________________________________
+
+struct Builder<'tu> {
+    blocks: Vec<Block<'tu>>,
+    current: Option<usize>,
+    targets: Vec<Target>,
+    switches: Vec<(usize, bool)>,
+    handlers: Vec<Vec<usize>>,
+    labels: HashMap<String, usize>,
+    gotos: Vec<(usize, Option<String>)>,
+}
+
+impl<'tu> Builder<'tu> {
+    //- Constructors -----------------------------
+
+    fn new() -> Builder<'tu> {
+        let mut builder = Builder {
+            blocks: vec![],
+            current: None,
+            targets: vec![],
+            switches: vec![],
+            handlers: vec![],
+            labels: HashMap::new(),
+            gotos: vec![],
+        };
+        builder.add_block();
+        builder.add_block();
+        builder
+    }
+
+    //- Mutators ---------------------------------
+
+    fn add_block(&mut self) -> usize {
+        let block = Block { label: None, statements: vec![], terminator: None, successors: vec![] };
+        self.blocks.push(block);
+        self.blocks.len() - 1
+    }
+
+    fn add_edge(&mut self, source: usize, target: usize, kind: EdgeKind) {
+        self.blocks[source].successors.push(Edge { target: target, kind: kind });
+    }
+
+    fn add_statement(&mut self, statement: Entity<'tu>) -> usize {
+        let block = self.get_current();
+        self.blocks[block].statements.push(statement);
+        block
+    }
+
+    fn get_current(&mut self) -> usize {
+        match self.current {
+            Some(current) => current,
+            None => {
+                let block = self.add_block();
+                self.current = Some(block);
+                block
+            },
+        }
+    }
+
+    fn join(&mut self, sources: Vec<(usize, EdgeKind)>) {
+        if sources.is_empty() {
+            self.current = None;
+        } else {
+            let block = self.add_block();
+            for (source, kind) in sources {
+                self.add_edge(source, block, kind);
+            }
+            self.current = Some(block);
+        }
+    }
+
+    fn jump(&mut self, target: usize) {
+        if let Some(current) = self.current {
+            self.add_edge(current, target, EdgeKind::Unconditional);
+        }
+    }
+
+    fn start(&mut self, label: Entity<'tu>) -> usize {
+        let block = self.add_block();
+        self.jump(block);
+        self.blocks[block].label = Some(label);
+        self.current = Some(block);
+        block
+    }
+
+    fn terminate(&mut self, statement: Entity<'tu>) -> usize {
+        let block = self.get_current();
+        self.blocks[block].terminator = Some(statement);
+        self.current = None;
+        block
+    }
+
+    fn visit(&mut self, statement: Entity<'tu>) {
+        match statement.get_kind() {
+            EntityKind::CompoundStmt => {
+                for child in statement.get_children() {
+                    self.visit(child);
+                }
+            },
+            EntityKind::IfStmt => self.visit_if(statement),
+            EntityKind::WhileStmt | EntityKind::ForRangeStmt => self.visit_while(statement),
+            EntityKind::DoStmt => self.visit_do(statement),
+            EntityKind::ForStmt => self.visit_for(statement),
+            EntityKind::SwitchStmt => self.visit_switch(statement),
+            EntityKind::CaseStmt | EntityKind::DefaultStmt => self.visit_case(statement),
+            EntityKind::LabelStmt => {
+                let block = self.start(statement);
+                if let Some(name) = statement.get_name() {
+                    self.labels.insert(name, block);
+                }
+                if let Some(child) = statement.get_children().last() {
+                    self.visit(*child);
+                }
+            },
+            EntityKind::GotoStmt => {
+                let label = get_label(statement);
+                let block = self.terminate(statement);
+                if label.is_some() {
+                    self.gotos.push((block, label));
+                }
+            },
+            EntityKind::IndirectGotoStmt => {
+                let block = self.terminate(statement);
+                self.gotos.push((block, None));
+            },
+            EntityKind::BreakStmt => {
+                let block = self.terminate(statement);
+                if let Some(target) = self.targets.last_mut() {
+                    target.breaks.push(block);
+                }
+            },
+            EntityKind::ContinueStmt => {
+                let block = self.terminate(statement);
+                let targets = self.targets.iter_mut().rev();
+                if let Some(continues) = targets.filter_map(|t| t.continues.as_mut()).next() {
+                    continues.push(block);
+                }
+            },
+            EntityKind::ReturnStmt => {
+                let block = self.add_statement(statement);
+                self.add_edge(block, EXIT, EdgeKind::Unconditional);
+                self.current = None;
+            },
+            EntityKind::TryStmt => self.visit_try(statement),
+            EntityKind::NullStmt => { },
+            _ if is_throw(statement) => {
+                let block = self.add_statement(statement);
+                let handlers = self.handlers.last().cloned().unwrap_or_else(|| vec![EXIT]);
+                for handler in handlers {
+                    self.add_edge(block, handler, EdgeKind::Exception);
+                }
+                self.current = None;
+            },
+            _ => { self.add_statement(statement); },
+        }
+    }
+
+    fn visit_branch(
+        &mut self, source: usize, kind: EdgeKind, statement: Entity<'tu>
+    ) -> Option<usize> {
+        let block = self.add_block();
+        self.add_edge(source, block, kind);
+        self.current = Some(block);
+        self.visit(statement);
+        self.current
+    }
+
+    fn visit_case(&mut self, statement: Entity<'tu>) {
+        let block = self.start(statement);
+        let default = statement.get_kind() == EntityKind::DefaultStmt;
+        let header = self.switches.last_mut().map(|s| {
+            s.1 = s.1 || default;
+            s.0
+        });
+        if let Some(header) = header {
+            let kind = if default { EdgeKind::Default } else { EdgeKind::Case };
+            self.add_edge(header, block, kind);
+        }
+        if let Some(child) = statement.get_children().last() {
+            self.visit(*child);
+        }
+    }
+
+    fn visit_do(&mut self, statement: Entity<'tu>) {
+        let children = statement.get_children();
+        let (body, conditions) = match children.split_first() {
+            Some(split) => split,
+            None => return,
+        };
+
+        let block = self.add_block();
+        self.jump(block);
+        self.current = Some(block);
+        self.targets.push(Target { breaks: vec![], continues: Some(vec![]) });
+        self.visit(*body);
+        let target = self.targets.pop().unwrap();
+
+        let header = self.add_block();
+        self.jump(header);
+        for source in target.continues.unwrap() {
+            self.add_edge(source, header, EdgeKind::Unconditional);
+        }
+        self.blocks[header].statements.extend_from_slice(conditions);
+        self.blocks[header].terminator = Some(statement);
+
+        let infinite = is_constant_true(conditions);
+        let kind = if infinite { EdgeKind::Unconditional } else { EdgeKind::True };
+        self.add_edge(header, block, kind);
+
+        let mut sources = vec![];
+        if !infinite {
+            sources.push((header, EdgeKind::False));
+        }
+        sources.extend(target.breaks.into_iter().map(|b| (b, EdgeKind::Unconditional)));
+        self.join(sources);
+    }
+
+    fn visit_for(&mut self, statement: Entity<'tu>) {
+        let children = statement.get_children();
+        let (body, clauses) = match children.split_last() {
+            Some(split) => split,
+            None => return,
+        };
+
+        let (first, second) = get_semicolons(statement).unwrap_or((0, u32::max_value()));
+        let offset = |e: &Entity| e.get_range().map_or(0, |r| get_offset(r.get_start()));
+        let conditions = clauses.iter().filter(|c| offset(c) >= first && offset(c) < second);
+        let conditions = conditions.cloned().collect::<Vec<_>>();
+        let increments = clauses.iter().filter(|c| offset(c) >= second);
+        let increments = increments.cloned().collect::<Vec<_>>();
+
+        for initializer in clauses.iter().filter(|c| offset(c) < first) {
+            self.add_statement(*initializer);
+        }
+
+        let header = self.add_block();
+        self.jump(header);
+        self.blocks[header].statements.extend(conditions.iter().cloned());
+        self.blocks[header].terminator = Some(statement);
+
+        let infinite = conditions.is_empty() || is_constant_true(&conditions);
+        let kind = if infinite { EdgeKind::Unconditional } else { EdgeKind::True };
+        self.targets.push(Target { breaks: vec![], continues: Some(vec![]) });
+        let end = self.visit_branch(header, kind, *body);
+        let target = self.targets.pop().unwrap();
+
+        let sources = end.into_iter().chain(target.continues.unwrap()).collect::<Vec<_>>();
+        let next = if increments.is_empty() {
+            header
+        } else {
+            let block = self.add_block();
+            self.blocks[block].statements = increments;
+            self.add_edge(block, header, EdgeKind::Unconditional);
+            block
+        };
+        for source in sources {
+            self.add_edge(source, next, EdgeKind::Unconditional);
+        }
+
+        let mut sources = vec![];
+        if !infinite {
+            sources.push((header, EdgeKind::False));
+        }
+        sources.extend(target.breaks.into_iter().map(|b| (b, EdgeKind::Unconditional)));
+        self.join(sources);
+    }
+
+    fn visit_if(&mut self, statement: Entity<'tu>) {
+        let children = statement.get_children();
+        let count = utility::get_condition_count(&children);
+
+        let header = self.get_current();
+        self.blocks[header].statements.extend_from_slice(&children[..count]);
+        self.blocks[header].terminator = Some(statement);
+        self.current = None;
+
+        let mut sources = vec![];
+        for (index, kind) in vec![(count, EdgeKind::True), (count + 1, EdgeKind::False)] {
+            if let Some(branch) = children.get(index) {
+                if let Some(end) = self.visit_branch(header, kind, *branch) {
+                    sources.push((end, EdgeKind::Unconditional));
+                }
+            } else {
+                sources.push((header, kind));
+            }
+        }
+        self.join(sources);
+    }
+
+    fn visit_switch(&mut self, statement: Entity<'tu>) {
+        let children = statement.get_children();
+        let (body, conditions) = match children.split_last() {
+            Some(split) => split,
+            None => return,
+        };
+
+        let header = self.get_current();
+        self.blocks[header].statements.extend_from_slice(conditions);
+        self.terminate(statement);
+
+        self.switches.push((header, false));
+        self.targets.push(Target { breaks: vec![], continues: None });
+        self.visit(*body);
+        let target = self.targets.pop().unwrap();
+        let (_, default) = self.switches.pop().unwrap();
+
+        let current = self.current.map(|c| (c, EdgeKind::Unconditional));
+        let mut sources = current.into_iter().collect::<Vec<_>>();
+        sources.extend(target.breaks.into_iter().map(|b| (b, EdgeKind::Unconditional)));
+        if !default {
+            sources.push((header, EdgeKind::Default));
+        }
+        self.join(sources);
+    }
+
+    fn visit_try(&mut self, statement: Entity<'tu>) {
+        let children = statement.get_children();
+        let (body, catches) = match children.split_first() {
+            Some(split) => split,
+            None => return,
+        };
+
+        let header = self.terminate(statement);
+        let block = self.add_block();
+        self.add_edge(header, block, EdgeKind::Unconditional);
+        let handlers = catches.iter().map(|c| {
+            let handler = self.add_block();
+            self.blocks[handler].label = Some(*c);
+            self.add_edge(header, handler, EdgeKind::Exception);
+            handler
+        }).collect::<Vec<_>>();
+
+        self.handlers.push(handlers.clone());
+        self.current = Some(block);
+        self.visit(*body);
+        self.handlers.pop();
+
+        let current = self.current.map(|c| (c, EdgeKind::Unconditional));
+        let mut sources = current.into_iter().collect::<Vec<_>>();
+        for (catch, handler) in catches.iter().zip(handlers) {
+            self.current = Some(handler);
+            let children = catch.get_children();
+            if let Some((handler, declarations)) = children.split_last() {
+                for declaration in declarations {
+                    self.add_statement(*declaration);
+                }
+                self.visit(*handler);
+            }
+            sources.extend(self.current.map(|c| (c, EdgeKind::Unconditional)));
+        }
+        self.join(sources);
+    }
+
+    fn visit_while(&mut self, statement: Entity<'tu>) {
+        let children = statement.get_children();
+        let (body, conditions) = match children.split_last() {
+            Some(split) => split,
+            None => return,
+        };
+
+        let header = self.add_block();
+        self.jump(header);
+        self.blocks[header].statements.extend_from_slice(conditions);
+        self.blocks[header].terminator = Some(statement);
+
+        let infinite = is_constant_true(conditions);
+        let kind = if infinite { EdgeKind::Unconditional } else { EdgeKind::True };
+        self.targets.push(Target { breaks: vec![], continues: Some(vec![]) });
+        let end = self.visit_branch(header, kind, *body);
+        let target = self.targets.pop().unwrap();
+        for source in end.into_iter().chain(target.continues.unwrap()) {
+            self.add_edge(source, header, EdgeKind::Unconditional);
+        }
+
+        let mut sources = vec![];
+        if !infinite {
+            sources.push((header, EdgeKind::False));
+        }
+        sources.extend(target.breaks.into_iter().map(|b| (b, EdgeKind::Unconditional)));
+        self.join(sources);
+    }
+
+    //- Consumers --------------------------------
+
+    fn build(mut self, entity: Entity<'tu>, body: Entity<'tu>) -> ControlFlowGraph<'tu> {
+        let block = self.add_block();
+        self.add_edge(ENTRY, block, EdgeKind::Unconditional);
+        self.current = Some(block);
+        self.visit(body);
+        self.jump(EXIT);
+
+        let mut labels = self.labels.values().cloned().collect::<Vec<_>>();
+        labels.sort();
+        for (source, label) in mem::replace(&mut self.gotos, vec![]) {
+            let targets = match label {
+                Some(label) => self.labels.get(&label).cloned().into_iter().collect(),
+                None => labels.clone(),
+            };
+            for target in targets {
+                self.add_edge(source, target, EdgeKind::Unconditional);
+            }
+        }
+
+        ControlFlowGraph { entity: entity, blocks: self.blocks }
+    }
+}
+
+// ControlFlowGraph ______________________________
+
+/// A statement-level control flow graph of the body of a function.
+///
+/// The first block is the empty entry block and the second block is the empty exit block. Blocks
+/// that are not reachable from the entry block (e.g., statements that follow a `return`
+/// statement) are included in the graph.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct ControlFlowGraph<'tu> {
+    entity: Entity<'tu>,
+    blocks: Vec<Block<'tu>>,
+}
+
+impl<'tu> ControlFlowGraph<'tu> {
+    //- Constructors -----------------------------
+
+    /// Constructs a new `ControlFlowGraph` from the body of the supplied function, if it is a
+    /// function definition.
+    ///
+    /// Only control flow between statements is represented. Control flow within expressions (e.g.,
+    /// the short-circuiting of `&&`) and exceptions thrown by anything other than a `throw`
+    /// expression statement are not represented, except that control may flow from a `try`
+    /// statement to any of its `catch` statements. Conditions are not evaluated except that loops
+    /// with a nonzero integer literal or `true` as their condition are treated as infinite loops.
+    pub fn new(entity: Entity<'tu>) -> Option<ControlFlowGraph<'tu>> {
+        match entity.get_kind() {
+            EntityKind::FunctionDecl |
+            EntityKind::FunctionTemplate |
+            EntityKind::Method |
+            EntityKind::Constructor |
+            EntityKind::Destructor |
+            EntityKind::ConversionFunction => { },
+            _ => return None,
+        }
+
+        let children = entity.get_children();
+        let body = children.iter().rev().find(|c| {
+            c.get_kind() == EntityKind::CompoundStmt || c.get_kind() == EntityKind::TryStmt
+        });
+        body.map(|b| Builder::new().build(entity, *b))
+    }
+
+    //- Accessors --------------------------------
+
+    /// Returns whether control can reach the end of the function without passing through a
+    /// `return` statement or a `throw` expression statement.
+    pub fn can_fall_off_end(&self) -> bool {
+        let reachable = self.get_reachable_blocks();
+        self.get_predecessors(EXIT).into_iter().any(|p| {
+            let block = &self.blocks[p];
+            let last = block.statements.last().map(|s| s.get_kind());
+            reachable.contains(&p) &&
+            last != Some(EntityKind::ReturnStmt) &&
+            block.successors.iter().any(|e| e.target == EXIT && e.kind != EdgeKind::Exception)
+        })
+    }
+
+    /// Returns the blocks in this control flow graph.
+    pub fn get_blocks(&self) -> &[Block<'tu>] {
+        &self.blocks
+    }
+
+    /// Returns the cyclomatic complexity of the reachable part of this control flow graph.
+    pub fn get_cyclomatic_complexity(&self) -> usize {
+        let reachable = self.get_reachable_blocks();
+        let edges = reachable.iter().map(|b| self.blocks[*b].successors.len()).sum::<usize>();
+        (edges + 2).saturating_sub(reachable.len())
+    }
+
+    /// Returns the function this control flow graph was constructed from.
+    pub fn get_entity(&self) -> Entity<'tu> {
+        self.entity
+    }
+
+    /// Returns the index of the entry block of this control flow graph.
+    pub fn get_entry(&self) -> usize {
+        ENTRY
+    }
+
+    /// Returns the index of the exit block of this control flow graph.
+    pub fn get_exit(&self) -> usize {
+        EXIT
+    }
+
+    /// Returns the indices of the blocks with edges to the supplied block.
+    pub fn get_predecessors(&self, block: usize) -> Vec<usize> {
+        let blocks = self.blocks.iter().enumerate();
+        let blocks = blocks.filter(|&(_, b)| b.successors.iter().any(|e| e.target == block));
+        blocks.map(|(i, _)| i).collect()
+    }
+
+    /// Returns the indices of the blocks that are reachable from the entry block, including the
+    /// entry block.
+    pub fn get_reachable_blocks(&self) -> BTreeSet<usize> {
+        let mut reachable = BTreeSet::new();
+        let mut stack = vec![ENTRY];
+        while let Some(block) = stack.pop() {
+            if reachable.insert(block) {
+                stack.extend(self.blocks[block].successors.iter().map(|e| e.target));
+            }
+        }
+        reachable
+    }
+
+    /// Returns the indices of the blocks that contain statements but are not reachable from the
+    /// entry block.
+    pub fn get_unreachable_blocks(&self) -> Vec<usize> {
+        let reachable = self.get_reachable_blocks();
+        let blocks = self.blocks.iter().enumerate().filter(|&(i, b)| {
+            !reachable.contains(&i) && (!b.statements.is_empty() || b.terminator.is_some())
+        });
+        blocks.map(|(i, _)| i).collect()
+    }
+
+    /// Returns this control flow graph in the DOT graph description language.
+    ///
+    /// Blocks are labeled with their labels, the tokens of their statements, and their
+    /// terminators. Edges other than unconditional edges are labeled with their kinds.
+    pub fn to_dot(&self) -> String {
+        let mut dot = String::from("digraph cfg {\n");
+        for (index, block) in self.blocks.iter().enumerate() {
+            let mut lines = match index {
+                ENTRY => vec!["ENTRY".into()],
+                EXIT => vec!["EXIT".into()],
+                _ => vec![format!("B{}", index)],
+            };
+            lines.extend(block.label.map(get_label_text));
+            lines.extend(block.statements.iter().map(|s| get_text(*s)));
+            lines.extend(block.terminator.map(get_terminator_text));
+            lines.push(String::new());
+            let label = utility::quote_dot(&lines.join("\n")).replace('\n', "\\l");
+            let shape = if index == ENTRY || index == EXIT { "ellipse" } else { "box" };
+            dot.push_str(&format!("    {} [label={}, shape={}];\n", index, label, shape));
+        }
+        for (index, block) in self.blocks.iter().enumerate() {
+            for edge in &block.successors {
+                if edge.kind == EdgeKind::Unconditional {
+                    dot.push_str(&format!("    {} -> {};\n", index, edge.target));
+                } else {
+                    let kind = edge.kind.get_name();
+                    dot.push_str(&format!("    {} -> {} [label={}];\n", index, edge.target, kind));
+                }
+            }
+        }
+        dot.push_str("}\n");
+        dot
+    }
+}
+
+// Edge __________________________________________
+
+/// An edge between two blocks in a control flow graph.
+#[derive(Copy, Clone, Debug, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct Edge {
+    /// The index of the block control flows to.
+    pub target: usize,
+    /// When control flows along this edge.
+    pub kind: EdgeKind,
+}
+
+// Target ________________________________________
+
+struct Target {
+    breaks: Vec<usize>,
+    continues: Option<Vec<usize>>,
+}
+
+//================================================
+// Functions
+//================================================
+
+fn get_label(statement: Entity) -> Option<String> {
+    let children = statement.get_children();
+    let reference = children.iter().find(|c| c.get_kind() == EntityKind::LabelRef);
+    reference.and_then(|r| r.get_name())
+}
+
+fn get_label_text(label: Entity) -> String {
+    let children = label.get_children();
+    match label.get_kind() {
+        EntityKind::LabelStmt => format!("{}:", label.get_name().unwrap_or_else(String::new)),
+        EntityKind::CaseStmt => format!("case {}:", children.first().map_or_else(String::new, |c| {
+            get_text(*c)
+        })),
+        EntityKind::DefaultStmt => "default:".into(),
+        _ => match children.first() {
+            Some(c) if c.get_kind() == EntityKind::VarDecl => format!("catch ({})", get_text(*c)),
+            _ => "catch (...)".into(),
+        },
+    }
+}
+
+fn get_offset(location: SourceLocation) -> u32 {
+    location.get_file_location().offset
+}
+
+fn get_semicolons(statement: Entity) -> Option<(u32, u32)> {
+    let tokens = statement.get_range().map_or_else(Vec::new, |r| r.tokenize());
+    let mut depth = 0;
+    let mut semicolons = vec![];
+    for token in &tokens {
+        match &token.get_spelling()[..] {
+            "(" | "[" | "{" => depth += 1,
+            ")" | "]" | "}" => {
+                depth -= 1;
+                if depth == 0 {
+                    break;
+                }
+            },
+            ";" if depth == 1 => semicolons.push(get_offset(token.get_location())),
+            _ => { },
+        }
+    }
+
+    if semicolons.len() == 2 {
+        Some((semicolons[0], semicolons[1]))
+    } else {
+        None
+    }
+}
+
+fn get_terminator_text(terminator: Entity) -> String {
+    let text = match terminator.get_kind() {
+        EntityKind::IfStmt => "if",
+        EntityKind::WhileStmt => "while",
+        EntityKind::DoStmt => "do while",
+        EntityKind::ForStmt | EntityKind::ForRangeStmt => "for",
+        EntityKind::SwitchStmt => "switch",
+        EntityKind::BreakStmt => "break",
+        EntityKind::ContinueStmt => "continue",
+        EntityKind::GotoStmt => {
+            return format!("goto {}", get_label(terminator).unwrap_or_else(String::new));
+        },
+        EntityKind::IndirectGotoStmt => "goto *",
+        _ => "try",
+    };
+    text.into()
+}
+
+fn get_text(entity: Entity) -> String {
+    let range = match entity.get_range() {
+        Some(range) => range,
+        None => return String::new(),
+    };
+    let end = get_offset(range.get_end());
+    let tokens = range.tokenize().into_iter().filter(|t| get_offset(t.get_location()) < end);
+    tokens.map(|t| t.get_spelling()).collect::<Vec<_>>().join(" ")
+}
+
+fn is_constant_true(conditions: &[Entity]) -> bool {
+    if conditions.len() != 1 {
+        return false;
+    }
+
+    let mut condition = conditions[0];
+    while condition.get_kind() == EntityKind::UnexposedExpr ||
+          condition.get_kind() == EntityKind::ParenExpr {
+        let children = condition.get_children();
+        if children.len() != 1 {
+            return false;
+        }
+        condition = children[0];
+    }
+    match expression::get_literal(condition) {
+        Some(Literal::Integer(value)) => value != 0,
+        Some(Literal::Bool(value)) => value,
+        _ => false,
+    }
+}
+
+fn is_throw(statement: Entity) -> bool {
+    match statement.get_kind() {
+        EntityKind::ThrowExpr | EntityKind::ObjCAtThrowStmt => true,
+        EntityKind::UnexposedExpr => {
+            let children = statement.get_children();
+            children.len() == 1 && is_throw(children[0])
+        },
+        _ => false,
+    }
+}
@@ -0,0 +1,158 @@
+// Copyright 2016 Kyle Mayes
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Traversals which visit each declaration only once across multiple translation units.
+
+use std::collections::{HashSet};
+
+use super::{Entity, EntityKind, EntityVisitResult, TranslationUnit, Usr};
+use super::source::{File};
+
+//================================================
+// Enums
+//================================================
+
+// Key ___________________________________________
+
+/// Identifies a declaration across translation units.
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub enum Key {
+    /// The unique identifier of the file a declaration is located in, the character offset of the
+    /// declaration in that file, and the categorization of the declaration.
+    Location((u64, u64, u64), u32, EntityKind),
+    /// The USR of a declaration.
+    Usr(Usr),
+}
+
+// KeyKind _______________________________________
+
+/// Indicates how declarations are identified across translation units.
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash)]
+pub enum KeyKind {
+    /// Declarations are identified by their locations.
+    ///
+    /// Redeclarations are considered to be distinct declarations.
+    Location,
+    /// Declarations are identified by their USRs.
+    ///
+    /// Redeclarations are considered to be the same declaration. Declarations without USRs are
+    /// identified by their locations.
+    Usr,
+}
+
+//================================================
+// Structs
+//================================================
+
+// Deduplicator __________________________________
+
+/// Visits each declaration and file only once across multiple translation units.
+#[derive(Clone, Debug)]
+pub struct Deduplicator {
+    kind: KeyKind,
+    declarations: HashSet<Key>,
+    files: HashSet<(u64, u64, u64)>,
+}
+
+impl Deduplicator {
+    //- Constructors -----------------------------
+
+    /// Constructs a new `Deduplicator` which identifies declarations in the supplied way.
+    pub fn new(kind: KeyKind) -> Deduplicator {
+        Deduplicator { kind: kind, declarations: HashSet::new(), files: HashSet::new() }
+    }
+
+    //- Accessors --------------------------------
+
+    /// Returns the number of distinct declarations that have been seen.
+    pub fn get_declaration_count(&self) -> usize {
+        self.declarations.len()
+    }
+
+    /// Returns the number of distinct files that have been seen.
+    pub fn get_file_count(&self) -> usize {
+        self.files.len()
+    }
+
+    /// Returns the key that identifies the supplied declaration, if it has a location.
+    pub fn get_key(&self, entity: Entity) -> Option<Key> {
+        let usr = if self.kind == KeyKind::Usr { entity.get_usr() } else { None };
+        usr.map(Key::Usr).or_else(|| entity.get_location().map(|l| {
+            let location = l.get_file_location();
+            Key::Location(location.file.get_id(), location.offset, entity.get_kind())
+        }))
+    }
+
+    /// Returns whether the supplied declaration has been seen.
+    pub fn is_seen(&self, entity: Entity) -> bool {
+        self.get_key(entity).map_or(false, |k| self.declarations.contains(&k))
+    }
+
+    /// Returns whether the supplied file has been seen.
+    pub fn is_seen_file(&self, file: File) -> bool {
+        self.files.contains(&file.get_id())
+    }
+
+    //- Mutators ---------------------------------
+
+    /// Forgets all of the declarations and files that have been seen.
+    pub fn clear(&mut self) {
+        self.declarations.clear();
+        self.files.clear();
+    }
+
+    /// Marks the supplied declaration as seen and returns whether it had not been seen before.
+    ///
+    /// Declarations without locations are never considered to have been seen.
+    pub fn insert(&mut self, entity: Entity) -> bool {
+        self.get_key(entity).map_or(true, |k| self.declarations.insert(k))
+    }
+
+    /// Marks the supplied file as seen and returns whether it had not been seen before.
+    pub fn insert_file(&mut self, file: File) -> bool {
+        self.files.insert(file.get_id())
+    }
+
+    /// Visits the declarations in the supplied translation unit which have not been seen before,
+    /// marking them as seen, and returns whether the traversal was terminated early.
+    ///
+    /// `file` is called with each file that has not been seen before, the first time a declaration
+    /// in that file is encountered. `declaration` is called with each unseen declaration and its
+    /// parent and determines how the traversal proceeds. The descendants of a declaration that has
+    /// been seen before are not visited, and neither are the descendants of a declaration for which
+    /// `declaration` returns `EntityVisitResult::Continue`. Entities that are not declarations
+    /// (e.g., statements and expressions) are traversed without being passed to `declaration`.
+    pub fn visit<'tu, F, D>(
+        &mut self, tu: &'tu TranslationUnit<'tu>, mut file: F, mut declaration: D
+    ) -> bool where F: FnMut(File<'tu>), D: FnMut(Entity<'tu>, Entity<'tu>) -> EntityVisitResult {
+        tu.get_entity().visit_children(|e, p| {
+            if !e.is_declaration() {
+                return EntityVisitResult::Recurse;
+            }
+
+            if let Some(location) = e.get_location() {
+                let header = location.get_file_location().file;
+                if self.insert_file(header) {
+                    file(header);
+                }
+            }
+
+            if self.insert(e) {
+                declaration(e, p)
+            } else {
+                EntityVisitResult::Continue
+            }
+        })
+    }
+}
@@ -0,0 +1,230 @@
+// Copyright 2016 Kyle Mayes
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Checking documentation comments for coverage and consistency.
+
+use super::{Accessibility, Entity, EntityKind, Linkage, Type, TypeKind};
+use super::documentation::{CommentChild, ParameterDirection};
+use super::source::{SourceRange};
+
+//================================================
+// Enums
+//================================================
+
+// FindingKind ___________________________________
+
+/// Indicates the categorization of a documentation finding.
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub enum FindingKind {
+    /// A public function has no documentation comment.
+    Undocumented,
+    /// A parameter is not documented by a `\param` command.
+    UndocumentedParameter(String),
+    /// A `\param` command refers to a parameter that does not exist.
+    UnknownParameter(String),
+    /// The explicit direction of a `\param` command does not match the constness of the
+    /// parameter.
+    ///
+    /// Values and pointers or references to `const` types are expected to be `[in]` while
+    /// pointers or references to non-`const` types are expected to be `[out]` or `[in,out]`.
+    DirectionMismatch(String, ParameterDirection),
+    /// A function that does not return `void` has no `\return` command.
+    MissingReturn,
+    /// A template parameter is not documented by a `\tparam` command.
+    UndocumentedTemplateParameter(String),
+    /// A `\tparam` command refers to a template parameter that does not exist.
+    UnknownTemplateParameter(String),
+}
+
+//================================================
+// Structs
+//================================================
+
+// Finding _______________________________________
+
+/// A documentation coverage or consistency problem.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Finding<'tu> {
+    /// The function or template the problem was found in.
+    pub entity: Entity<'tu>,
+    /// The categorization of the problem.
+    pub kind: FindingKind,
+    /// The source range of the problem.
+    ///
+    /// This is the range of the parameter for problems with parameters that exist and the range
+    /// of the documentation comment (or the entity if it has no documentation comment) otherwise.
+    pub range: SourceRange<'tu>,
+}
+
+//================================================
+// Functions
+//================================================
+
+fn check<'tu>(entity: Entity<'tu>, findings: &mut Vec<Finding<'tu>>) {
+    let range = match entity.get_comment_range().or_else(|| entity.get_range()) {
+        Some(range) => range,
+        None => return,
+    };
+
+    macro_rules! finding {
+        ($kind:expr) => (finding!($kind, range));
+        ($kind:expr, $range:expr) => ({
+            findings.push(Finding { entity: entity, kind: $kind, range: $range });
+        });
+    }
+
+    let comment = match entity.get_parsed_comment() {
+        Some(comment) => comment.get_children(),
+        None => return finding!(FindingKind::Undocumented),
+    };
+
+    let mut parameters = vec![];
+    let mut template_parameters = vec![];
+    let mut returns = false;
+    for child in comment {
+        match child {
+            CommentChild::BlockCommand(command) => match &command.command[..] {
+                "return" | "returns" | "result" => returns = true,
+                _ => { },
+            },
+            CommentChild::ParamCommand(command) => parameters.push(command),
+            CommentChild::TParamCommand(command) => template_parameters.push(command),
+            _ => { },
+        }
+    }
+
+    let children = entity.get_children();
+
+    if entity.get_kind() != EntityKind::ClassTemplate {
+        let arguments = children.iter().filter(|c| c.get_kind() == EntityKind::ParmDecl);
+        let arguments = arguments.filter_map(|a| a.get_name().map(|n| (n, *a))).collect::<Vec<_>>();
+
+        for &(ref name, argument) in &arguments {
+            let range = argument.get_range().unwrap_or(range);
+            match parameters.iter().find(|p| &p.parameter == name) {
+                Some(parameter) => if let Some(direction) = parameter.direction {
+                    let input = argument.get_type().map_or(true, is_input);
+                    if input != (direction == ParameterDirection::In) {
+                        finding!(FindingKind::DirectionMismatch(name.clone(), direction), range);
+                    }
+                },
+                None => finding!(FindingKind::UndocumentedParameter(name.clone()), range),
+            }
+        }
+
+        for parameter in &parameters {
+            let variadic = parameter.parameter == "..." && entity.is_variadic();
+            if !variadic && !arguments.iter().any(|&(ref n, _)| n == &parameter.parameter) {
+                finding!(FindingKind::UnknownParameter(parameter.parameter.clone()));
+            }
+        }
+
+        let result = entity.get_type().and_then(|t| t.get_result_type());
+        if !returns && result.map_or(false, |r| r.get_kind() != TypeKind::Void) {
+            finding!(FindingKind::MissingReturn);
+        }
+    }
+
+    let templates = children.iter().filter(|c| {
+        match c.get_kind() {
+            EntityKind::TemplateTypeParameter |
+            EntityKind::NonTypeTemplateParameter |
+            EntityKind::TemplateTemplateParameter => true,
+            _ => false,
+        }
+    });
+    let templates = templates.filter_map(|t| t.get_name().map(|n| (n, *t))).collect::<Vec<_>>();
+
+    for &(ref name, template) in &templates {
+        if !template_parameters.iter().any(|p| &p.parameter == name) {
+            let range = template.get_range().unwrap_or(range);
+            finding!(FindingKind::UndocumentedTemplateParameter(name.clone()), range);
+        }
+    }
+
+    for parameter in &template_parameters {
+        if !templates.iter().any(|&(ref n, _)| n == &parameter.parameter) {
+            finding!(FindingKind::UnknownTemplateParameter(parameter.parameter.clone()));
+        }
+    }
+}
+
+fn is_input(type_: Type) -> bool {
+    let type_ = type_.get_canonical_type();
+    match type_.get_kind() {
+        TypeKind::Pointer | TypeKind::LValueReference | TypeKind::RValueReference =>
+            type_.get_pointee_type().map_or(true, |p| p.is_const_qualified()),
+        _ => true,
+    }
+}
+
+fn is_public(entity: &Entity) -> bool {
+    if entity.get_accessibility().map_or(false, |a| a != Accessibility::Public) {
+        return false;
+    }
+
+    match entity.get_kind() {
+        EntityKind::Method |
+        EntityKind::Constructor |
+        EntityKind::Destructor |
+        EntityKind::ConversionFunction |
+        EntityKind::ClassDecl |
+        EntityKind::StructDecl |
+        EntityKind::ClassTemplate => true,
+        _ => entity.get_linkage() == Some(Linkage::External),
+    }
+}
+
+fn visit<'tu>(entities: Vec<Entity<'tu>>, findings: &mut Vec<Finding<'tu>>) {
+    for entity in entities {
+        if entity.is_in_system_header() || !is_public(&entity) {
+            continue;
+        }
+
+        match entity.get_kind() {
+            EntityKind::FunctionDecl |
+            EntityKind::FunctionTemplate |
+            EntityKind::Method |
+            EntityKind::Constructor |
+            EntityKind::Destructor |
+            EntityKind::ConversionFunction => if entity == entity.get_canonical_entity() {
+                check(entity, findings);
+            },
+            EntityKind::ClassTemplate => {
+                let commented = entity.get_parsed_comment().is_some();
+                if entity == entity.get_canonical_entity() && commented {
+                    check(entity, findings);
+                }
+                visit(entity.get_children(), findings);
+            },
+            EntityKind::Namespace |
+            EntityKind::LinkageSpec |
+            EntityKind::ClassDecl |
+            EntityKind::StructDecl => visit(entity.get_children(), findings),
+            _ => { },
+        }
+    }
+}
+
+/// Returns the documentation problems for the public functions and templates in the supplied
+/// entities and the entities they contain.
+///
+/// Functions are public if they have external linkage or are public members. Declarations in
+/// system headers are not checked and each function is only checked once, regardless of how many
+/// times it is declared.
+pub fn check_documentation<'tu, E: Into<Vec<Entity<'tu>>>>(entities: E) -> Vec<Finding<'tu>> {
+    let mut findings = vec![];
+    visit(entities.into(), &mut findings);
+    findings
+}
@@ -0,0 +1,392 @@
+// Copyright 2016 Kyle Mayes
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Documents which own a translation unit and reparse it when the files it depends on change.
+
+use std::cmp;
+use std::io;
+use std::collections::{BTreeMap};
+use std::path::{Path, PathBuf};
+use std::time::{Duration, Instant};
+
+use libc::{time_t};
+
+use utility;
+use super::{Index, SourceError, TranslationUnit, Unsaved};
+use super::diagnostic::{Diagnostic, Severity};
+
+//================================================
+// Structs
+//================================================
+
+// Buffer ________________________________________
+
+#[derive(Clone, Debug)]
+struct Buffer {
+    contents: String,
+    dirty: bool,
+}
+
+// Document ______________________________________
+
+/// A source file which owns the translation unit parsed from it.
+///
+/// A document keeps the contents of unsaved files in memory and records the identifiers and last
+/// modification times of the files its translation unit depends on so that the translation unit is
+/// only reparsed when an unsaved file has been changed or a file it depends on has been modified on
+/// disk.
+#[derive(Debug)]
+pub struct Document<'i> {
+    index: &'i Index<'i>,
+    path: PathBuf,
+    arguments: Vec<String>,
+    buffers: BTreeMap<PathBuf, Buffer>,
+    removed: Vec<PathBuf>,
+    files: BTreeMap<PathBuf, ((u64, u64, u64), time_t)>,
+    diagnostics: Vec<StoredDiagnostic>,
+    tu: Option<TranslationUnit<'i>>,
+}
+
+impl<'i> Document<'i> {
+    //- Constructors -----------------------------
+
+    /// Constructs a new `Document` for the source file at the supplied path which will be parsed
+    /// with the supplied compiler arguments.
+    ///
+    /// The source file is not parsed until this document is first updated. The paths of the source
+    /// file and the unsaved files supplied to this document should be absolute.
+    pub fn new<P: Into<PathBuf>, S: AsRef<str>>(
+        index: &'i Index<'i>, path: P, arguments: &[S]
+    ) -> Document<'i> {
+        Document {
+            index: index,
+            path: path.into(),
+            arguments: arguments.iter().map(|a| a.as_ref().into()).collect(),
+            buffers: BTreeMap::new(),
+            removed: vec![],
+            files: BTreeMap::new(),
+            diagnostics: vec![],
+            tu: None,
+        }
+    }
+
+    //- Accessors --------------------------------
+
+    /// Returns the contents of the unsaved file at the supplied path, if any.
+    pub fn get_contents<P: AsRef<Path>>(&self, path: P) -> Option<&str> {
+        self.buffers.get(path.as_ref()).map(|b| &b.contents[..])
+    }
+
+    /// Returns the diagnostics reported when the translation unit was last parsed or reparsed.
+    pub fn get_diagnostics(&self) -> &[StoredDiagnostic] {
+        &self.diagnostics
+    }
+
+    /// Returns the paths of the files the translation unit depends on which have been modified or
+    /// removed on disk since the translation unit was last parsed or reparsed.
+    ///
+    /// Files which are overridden by unsaved files are not checked.
+    pub fn get_disk_changes(&self) -> Vec<&Path> {
+        self.files.iter().filter(|&(p, &(_, t))| {
+            !self.buffers.contains_key(p) && utility::get_time(p) != Some(t)
+        }).map(|(p, _)| p.as_path()).collect()
+    }
+
+    /// Returns the path of the source file.
+    pub fn get_path(&self) -> &Path {
+        &self.path
+    }
+
+    /// Returns the translation unit, if the source file has been successfully parsed.
+    pub fn get_translation_unit(&self) -> Option<&TranslationUnit<'i>> {
+        self.tu.as_ref()
+    }
+
+    /// Returns the paths of the unsaved files which have been changed or removed since the
+    /// translation unit was last parsed or reparsed.
+    pub fn get_unsaved_changes(&self) -> Vec<&Path> {
+        let dirty = self.buffers.iter().filter(|&(_, b)| b.dirty).map(|(p, _)| p.as_path());
+        let mut changes = dirty.chain(self.removed.iter().map(|p| p.as_path())).collect::<Vec<_>>();
+        changes.sort();
+        changes.dedup();
+        changes
+    }
+
+    /// Returns whether the translation unit needs to be parsed or reparsed.
+    pub fn is_stale(&self) -> bool {
+        self.tu.is_none() || !self.get_unsaved_changes().is_empty() ||
+            !self.get_disk_changes().is_empty()
+    }
+
+    //- Mutators ---------------------------------
+
+    /// Applies the supplied edits in order to the unsaved file at the supplied path.
+    ///
+    /// If there is no unsaved file at the supplied path, the unsaved file is first created from the
+    /// contents of the file on disk.
+    ///
+    /// # Failures
+    ///
+    /// * an I/O error occurs while reading the file on disk
+    /// * the file on disk is not valid UTF-8
+    pub fn edit<P: AsRef<Path>>(&mut self, path: P, edits: &[TextEdit]) -> io::Result<()> {
+        let path = path.as_ref();
+        if !self.buffers.contains_key(path) {
+            let contents = try!(utility::read_file(path));
+            self.buffers.insert(path.into(), Buffer { contents: contents, dirty: false });
+        }
+
+        let buffer = self.buffers.get_mut(path).unwrap();
+        for edit in edits {
+            edit.apply(&mut buffer.contents);
+        }
+        buffer.dirty |= !edits.is_empty();
+        Ok(())
+    }
+
+    /// Removes the unsaved file at the supplied path so that the file on disk is used instead.
+    pub fn remove_contents<P: AsRef<Path>>(&mut self, path: P) {
+        if self.buffers.remove(path.as_ref()).is_some() {
+            self.removed.push(path.as_ref().into());
+        }
+    }
+
+    /// Sets the contents of the unsaved file at the supplied path.
+    pub fn set_contents<P: Into<PathBuf>, C: Into<String>>(&mut self, path: P, contents: C) {
+        let (path, contents) = (path.into(), contents.into());
+        let dirty = self.buffers.get(&path).map_or(true, |b| b.dirty || b.contents != contents);
+        self.buffers.insert(path, Buffer { contents: contents, dirty: dirty });
+    }
+
+    /// Parses or reparses the translation unit if it is stale and returns the statistics and
+    /// diagnostics delta for the parse or reparse.
+    ///
+    /// If a reparse fails, the translation unit is discarded and the source file will be parsed
+    /// again on the next update.
+    ///
+    /// # Failures
+    ///
+    /// * an error occurs while deserializing an AST file
+    /// * `libclang` crashes
+    /// * an unknown error occurs
+    pub fn update(&mut self) -> Result<Option<Reparse>, SourceError> {
+        if !self.is_stale() {
+            return Ok(None);
+        }
+
+        let unsaved = self.get_unsaved_changes().iter().map(|p| p.to_path_buf()).collect();
+        let mut modified = self.get_disk_changes().iter().map(|p| p.to_path_buf()).collect();
+        let buffers = self.buffers.iter().map(|(p, b)| Unsaved::new(p, &b.contents));
+        let buffers = buffers.collect::<Vec<_>>();
+
+        let start = Instant::now();
+        let (parsed, tu) = match self.tu.take() {
+            Some(tu) => (false, tu.reparse(&buffers)),
+            None => {
+                let mut parser = self.index.parser(&self.path);
+                parser.arguments(&self.arguments).unsaved(&buffers).precompiled_preamble(true);
+                (true, parser.parse())
+            },
+        };
+        let duration = start.elapsed();
+
+        for buffer in self.buffers.values_mut() {
+            buffer.dirty = false;
+        }
+        self.removed.clear();
+
+        let tu = match tu {
+            Ok(tu) => tu,
+            Err(error) => {
+                self.files.clear();
+                self.diagnostics.clear();
+                return Err(error);
+            },
+        };
+
+        let (files, diagnostics) = {
+            let files = get_files(&tu);
+            let diagnostics = tu.get_diagnostics();
+            (files, diagnostics.iter().map(StoredDiagnostic::from_diagnostic).collect::<Vec<_>>())
+        };
+        self.tu = Some(tu);
+
+        if !parsed {
+            modified = merge(modified, self.files.iter().filter(|&(p, f)| {
+                !self.buffers.contains_key(p) && files.get(p).map_or(true, |n| n != f)
+            }).map(|(p, _)| p.clone()).collect());
+        }
+
+        let added = diagnostics.iter().filter(|d| !self.diagnostics.contains(d)).cloned();
+        let added = added.collect();
+        let removed = self.diagnostics.iter().filter(|d| !diagnostics.contains(d)).cloned();
+        let removed = removed.collect();
+
+        self.files = files;
+        self.diagnostics = diagnostics;
+
+        Ok(Some(Reparse {
+            parsed: parsed,
+            duration: duration,
+            unsaved: unsaved,
+            modified: modified,
+            added: added,
+            removed: removed,
+        }))
+    }
+}
+
+// Reparse _______________________________________
+
+/// The statistics and diagnostics delta for a parse or reparse of a document.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Reparse {
+    /// Whether the translation unit was parsed from scratch instead of reparsed.
+    pub parsed: bool,
+    /// The time it took to parse or reparse the translation unit.
+    pub duration: Duration,
+    /// The paths of the unsaved files which had been changed.
+    pub unsaved: Vec<PathBuf>,
+    /// The paths of the files which had been modified or removed on disk.
+    pub modified: Vec<PathBuf>,
+    /// The diagnostics which were not reported by the previous parse or reparse.
+    pub added: Vec<StoredDiagnostic>,
+    /// The diagnostics reported by the previous parse or reparse which are no longer reported.
+    pub removed: Vec<StoredDiagnostic>,
+}
+
+// StoredDiagnostic ______________________________
+
+/// A diagnostic which does not borrow the translation unit it was reported for.
+#[derive(Clone, Debug, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct StoredDiagnostic {
+    /// The severity of the diagnostic.
+    pub severity: Severity,
+    /// The text of the diagnostic.
+    pub text: String,
+    /// The path of the file the diagnostic is located in, if any.
+    pub path: Option<PathBuf>,
+    /// The line the diagnostic is located on.
+    pub line: u32,
+    /// The column the diagnostic is located at.
+    pub column: u32,
+}
+
+impl StoredDiagnostic {
+    //- Constructors -----------------------------
+
+    /// Constructs a new `StoredDiagnostic` from the supplied diagnostic.
+    pub fn from_diagnostic(diagnostic: &Diagnostic) -> StoredDiagnostic {
+        let location = diagnostic.get_location().get_file_location();
+        StoredDiagnostic {
+            severity: diagnostic.get_severity(),
+            text: diagnostic.get_text(),
+            path: if location.file.is_null() { None } else { Some(location.file.get_path()) },
+            line: location.line,
+            column: location.column,
+        }
+    }
+}
+
+// TextEdit ______________________________________
+
+/// A replacement of the text between two positions in a file.
+///
+/// Positions are pairs of lines and columns, both of which start at `1`. Like source locations,
+/// columns are byte offsets into a line plus one.
+#[derive(Clone, Debug, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct TextEdit {
+    /// The line and column of the start of the replaced text.
+    pub start: (u32, u32),
+    /// The line and column of the end of the replaced text (exclusive).
+    pub end: (u32, u32),
+    /// The replacement text.
+    pub text: String,
+}
+
+impl TextEdit {
+    //- Constructors -----------------------------
+
+    /// Constructs a new `TextEdit`.
+    pub fn new<T: Into<String>>(start: (u32, u32), end: (u32, u32), text: T) -> TextEdit {
+        TextEdit { start: start, end: end, text: text.into() }
+    }
+
+    /// Constructs a new `TextEdit` which inserts the supplied text at the supplied position.
+    pub fn insertion<T: Into<String>>(position: (u32, u32), text: T) -> TextEdit {
+        TextEdit::new(position, position, text)
+    }
+
+    //- Accessors --------------------------------
+
+    /// Applies this edit to the supplied text.
+    ///
+    /// Positions past the end of a line are clamped to the end of that line and positions past the
+    /// last line are clamped to the end of the text.
+    pub fn apply(&self, text: &mut String) {
+        let start = get_offset(text, self.start);
+        let end = cmp::max(start, get_offset(text, self.end));
+        let mut result = String::with_capacity(text.len() - (end - start) + self.text.len());
+        result.push_str(&text[..start]);
+        result.push_str(&self.text);
+        result.push_str(&text[end..]);
+        *text = result;
+    }
+}
+
+//================================================
+// Functions
+//================================================
+
+fn get_files(tu: &TranslationUnit) -> BTreeMap<PathBuf, ((u64, u64, u64), time_t)> {
+    let main = tu.get_entity().get_name().and_then(|n| tu.get_file(n));
+    let mut files = main.into_iter().collect::<Vec<_>>();
+    let mut next = 0;
+    while next < files.len() {
+        for include in files[next].get_includes() {
+            if let Some(file) = include.get_file() {
+                if !files.contains(&file) {
+                    files.push(file);
+                }
+            }
+        }
+        next += 1;
+    }
+    files.iter().map(|f| (f.get_path(), (f.get_id(), f.get_time()))).collect()
+}
+
+fn get_offset(text: &str, position: (u32, u32)) -> usize {
+    let (line, column) = position;
+    let mut start = 0;
+    for _ in 1..line {
+        match text[start..].find('\n') {
+            Some(index) => start += index + 1,
+            None => return text.len(),
+        }
+    }
+
+    let length = text[start..].find('\n').unwrap_or(text.len() - start);
+    let mut offset = start + cmp::min(column.saturating_sub(1) as usize, length);
+    while !text.is_char_boundary(offset) {
+        offset -= 1;
+    }
+    offset
+}
+
+fn merge(mut left: Vec<PathBuf>, right: Vec<PathBuf>) -> Vec<PathBuf> {
+    left.extend(right);
+    left.sort();
+    left.dedup();
+    left
+}
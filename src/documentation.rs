@@ -195,6 +195,27 @@ impl<'tu> Comment<'tu> {
     pub fn as_xml(&self) -> String {
         unsafe { utility::to_string(clang_FullComment_getAsXML(self.raw)) }
     }
+
+    /// Returns this comment as a Markdown string, keeping or stripping any HTML tags.
+    ///
+    /// Parameters, template parameters, return values, and see also references are rendered as
+    /// dedicated sections following the brief and the details.
+    pub fn as_markdown(&self, html: bool) -> String {
+        layout(summarize(&self.get_children(), Format::Markdown(html)), Format::Markdown(html))
+    }
+
+    /// Returns this comment as a plain text string with any HTML tags stripped.
+    ///
+    /// Parameters, template parameters, return values, and see also references are rendered as
+    /// dedicated sections following the brief and the details.
+    pub fn as_text(&self) -> String {
+        layout(summarize(&self.get_children(), Format::Text), Format::Text)
+    }
+
+    /// Returns the structured summary of this comment, rendered as plain text.
+    pub fn get_doc_comment(&self) -> DocComment {
+        summarize(&self.get_children(), Format::Text)
+    }
 }
 
 impl<'tu> fmt::Debug for Comment<'tu> {
@@ -203,6 +224,42 @@ impl<'tu> fmt::Debug for Comment<'tu> {
     }
 }
 
+// DocComment ____________________________________
+
+/// A structured summary of a comment.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct DocComment {
+    /// The brief, if any.
+    ///
+    /// If the comment has no `\brief` command, the first paragraph is used as the brief.
+    pub brief: Option<String>,
+    /// The paragraphs and verbatim blocks that follow the brief.
+    pub details: Vec<String>,
+    /// The names and descriptions of the parameters in the order they were documented.
+    pub parameters: Vec<(String, String)>,
+    /// The names and descriptions of the template parameters in the order they were documented.
+    pub template_parameters: Vec<(String, String)>,
+    /// The description of the return value, if any.
+    pub returns: Option<String>,
+    /// The see also references.
+    pub see_also: Vec<String>,
+}
+
+impl DocComment {
+    //- Accessors --------------------------------
+
+    /// Returns the description of the parameter with the supplied name, if it was documented.
+    pub fn get_parameter(&self, name: &str) -> Option<&str> {
+        self.parameters.iter().find(|&&(ref n, _)| n == name).map(|&(_, ref d)| &d[..])
+    }
+
+    /// Returns the description of the template parameter with the supplied name, if it was
+    /// documented.
+    pub fn get_template_parameter(&self, name: &str) -> Option<&str> {
+        self.template_parameters.iter().find(|&&(ref n, _)| n == name).map(|&(_, ref d)| &d[..])
+    }
+}
+
 // HtmlStartTag __________________________________
 
 /// An HTML start tag.
@@ -272,6 +329,8 @@ pub struct ParamCommand {
     pub parameter: String,
     /// The parameter direction, if specified.
     pub direction: Option<ParameterDirection>,
+    /// The children of the paragraph argument.
+    pub children: Vec<CommentChild>,
 }
 
 impl ParamCommand {
@@ -289,7 +348,11 @@ impl ParamCommand {
         } else {
             None
         };
-        ParamCommand { index: index, parameter: parameter, direction: direction }
+        let paragraph = clang_BlockCommandComment_getParagraph(raw);
+        let children = Comment::from_raw(paragraph).get_children();
+        ParamCommand {
+            index: index, parameter: parameter, direction: direction, children: children
+        }
     }
 }
 
@@ -303,6 +366,8 @@ pub struct TParamCommand {
     pub position: Option<(usize, usize)>,
     /// The template parameter.
     pub parameter: String,
+    /// The children of the paragraph argument.
+    pub children: Vec<CommentChild>,
 }
 
 impl TParamCommand {
@@ -317,6 +382,182 @@ impl TParamCommand {
             None
         };
         let parameter = utility::to_string(clang_TParamCommandComment_getParamName(raw));
-        TParamCommand { position: position, parameter: parameter }
+        let paragraph = clang_BlockCommandComment_getParagraph(raw);
+        let children = Comment::from_raw(paragraph).get_children();
+        TParamCommand { position: position, parameter: parameter, children: children }
     }
 }
+
+//================================================
+// Functions
+//================================================
+
+#[derive(Copy, Clone)]
+enum Format {
+    Markdown(bool),
+    Text,
+}
+
+fn capitalize(string: &str) -> String {
+    let mut chars = string.chars();
+    match chars.next() {
+        Some(first) => first.to_uppercase().chain(chars).collect(),
+        None => String::new(),
+    }
+}
+
+fn layout(doc: DocComment, format: Format) -> String {
+    let heading = |h: &str| match format {
+        Format::Markdown(_) => format!("**{}:**", h),
+        Format::Text => format!("{}:", h),
+    };
+
+    let list = |h: &str, items: &[(String, String)]| {
+        let items = items.iter().map(|&(ref n, ref d)| match format {
+            Format::Markdown(_) => format!("- `{}`: {}", n, d),
+            Format::Text => format!("    {}: {}", n, d),
+        }).collect::<Vec<_>>();
+        match format {
+            Format::Markdown(_) => format!("{}\n\n{}", heading(h), items.join("\n")),
+            Format::Text => format!("{}\n{}", heading(h), items.join("\n")),
+        }
+    };
+
+    let mut blocks = vec![];
+    blocks.extend(doc.brief.into_iter());
+    blocks.extend(doc.details.into_iter());
+    if !doc.template_parameters.is_empty() {
+        blocks.push(list("Template parameters", &doc.template_parameters));
+    }
+    if !doc.parameters.is_empty() {
+        blocks.push(list("Parameters", &doc.parameters));
+    }
+    if let Some(returns) = doc.returns {
+        blocks.push(format!("{} {}", heading("Returns"), returns));
+    }
+    if !doc.see_also.is_empty() {
+        blocks.push(format!("{} {}", heading("See also"), doc.see_also.join(", ")));
+    }
+    blocks.join("\n\n")
+}
+
+fn render_inline(child: &CommentChild, format: Format, string: &mut String) {
+    match *child {
+        CommentChild::Text(ref text) => string.push_str(text),
+        CommentChild::InlineCommand(ref command) => {
+            let arguments = command.arguments.join(" ");
+            match (format, command.style) {
+                (Format::Markdown(_), Some(InlineCommandStyle::Bold)) =>
+                    string.push_str(&format!("**{}**", arguments)),
+                (Format::Markdown(_), Some(InlineCommandStyle::Emphasized)) =>
+                    string.push_str(&format!("*{}*", arguments)),
+                (Format::Markdown(_), Some(InlineCommandStyle::Monospace)) =>
+                    string.push_str(&format!("`{}`", arguments)),
+                _ => string.push_str(&arguments),
+            }
+        },
+        CommentChild::HtmlStartTag(ref tag) => if let Format::Markdown(true) = format {
+            string.push('<');
+            string.push_str(&tag.name);
+            for &(ref name, ref value) in &tag.attributes {
+                string.push_str(&format!(" {}=\"{}\"", name, value));
+            }
+            string.push_str(if tag.closing { " />" } else { ">" });
+        },
+        CommentChild::HtmlEndTag(ref name) => if let Format::Markdown(true) = format {
+            string.push_str(&format!("</{}>", name));
+        },
+        CommentChild::Paragraph(ref children) => for child in children {
+            render_inline(child, format, string);
+        },
+        CommentChild::BlockCommand(ref command) => for child in &command.children {
+            render_inline(child, format, string);
+        },
+        CommentChild::ParamCommand(_) | CommentChild::TParamCommand(_) => { },
+        CommentChild::VerbatimCommand(ref lines) => string.push_str(&lines.join(" ")),
+        CommentChild::VerbatimLineCommand(ref line) => string.push_str(line),
+    }
+}
+
+fn render_paragraph(children: &[CommentChild], format: Format) -> String {
+    let mut string = String::new();
+    for child in children {
+        render_inline(child, format, &mut string);
+    }
+    string.split_whitespace().collect::<Vec<_>>().join(" ")
+}
+
+fn render_verbatim(lines: &[String], format: Format) -> String {
+    let lines = lines.iter().map(|l| l.trim_right()).collect::<Vec<_>>();
+    let indent = lines.iter().filter(|l| !l.is_empty()).map(|l| {
+        l.bytes().take_while(|b| *b == b' ' || *b == b'\t').count()
+    }).min().unwrap_or(0);
+    let lines = lines.iter().map(|l| if l.is_empty() { *l } else { &l[indent..] });
+    match format {
+        Format::Markdown(_) => format!("```\n{}\n```", lines.collect::<Vec<_>>().join("\n")),
+        Format::Text => lines.map(|l| format!("    {}", l)).collect::<Vec<_>>().join("\n"),
+    }
+}
+
+fn summarize(children: &[CommentChild], format: Format) -> DocComment {
+    let mut doc = DocComment {
+        brief: None,
+        details: vec![],
+        parameters: vec![],
+        template_parameters: vec![],
+        returns: None,
+        see_also: vec![],
+    };
+
+    let explicit = children.iter().any(|c| match *c {
+        CommentChild::BlockCommand(ref command) =>
+            command.command == "brief" || command.command == "short",
+        _ => false,
+    });
+
+    for child in children {
+        match *child {
+            CommentChild::BlockCommand(ref command) => {
+                let text = render_paragraph(&command.children, format);
+                match &command.command[..] {
+                    "brief" | "short" if doc.brief.is_none() => doc.brief = Some(text),
+                    "return" | "returns" | "result" => doc.returns = Some(text),
+                    "see" | "sa" => doc.see_also.push(text),
+                    other => {
+                        let heading = match format {
+                            Format::Markdown(_) => format!("**{}:**", capitalize(other)),
+                            Format::Text => format!("{}:", capitalize(other)),
+                        };
+                        doc.details.push(format!("{} {}", heading, text).trim().into());
+                    },
+                }
+            },
+            CommentChild::ParamCommand(ref command) => {
+                let text = render_paragraph(&command.children, format);
+                doc.parameters.push((command.parameter.clone(), text));
+            },
+            CommentChild::TParamCommand(ref command) => {
+                let text = render_paragraph(&command.children, format);
+                doc.template_parameters.push((command.parameter.clone(), text));
+            },
+            CommentChild::VerbatimCommand(ref lines) =>
+                doc.details.push(render_verbatim(lines, format)),
+            CommentChild::VerbatimLineCommand(ref line) =>
+                doc.details.push(render_verbatim(&[line.clone()], format)),
+            _ => {
+                let text = render_paragraph(&[child.clone()], format);
+                if text.is_empty() {
+                    continue;
+                }
+
+                if !explicit && doc.brief.is_none() && doc.details.is_empty() {
+                    doc.brief = Some(text);
+                } else {
+                    doc.details.push(text);
+                }
+            },
+        }
+    }
+
+    doc
+}
@@ -0,0 +1,656 @@
+// Copyright 2016 Kyle Mayes
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Operators, literal values, and other details of expressions.
+//!
+//! `libclang` does not expose most of the details of expressions so they are recovered from the
+//! tokens of the expressions instead. Literals which are the results of macro expansions are
+//! evaluated with `Entity::evaluate` when it is available.
+
+use std::char;
+
+use super::{Entity, EntityKind};
+use super::source::{SourceLocation};
+use super::token::{Token, TokenKind};
+
+#[cfg(feature="gte_clang_3_9")]
+use super::{EvaluationResult};
+
+//================================================
+// Enums
+//================================================
+
+// BinaryOperator ________________________________
+
+/// Indicates the operator of a binary operator or compound assignment operator expression.
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash)]
+pub enum BinaryOperator {
+    /// `*`
+    Multiply,
+    /// `/`
+    Divide,
+    /// `%`
+    Remainder,
+    /// `+`
+    Add,
+    /// `-`
+    Subtract,
+    /// `<<`
+    ShiftLeft,
+    /// `>>`
+    ShiftRight,
+    /// `<`
+    Less,
+    /// `>`
+    Greater,
+    /// `<=`
+    LessEqual,
+    /// `>=`
+    GreaterEqual,
+    /// `==`
+    Equal,
+    /// `!=`
+    NotEqual,
+    /// `&`
+    BitwiseAnd,
+    /// `^`
+    BitwiseXor,
+    /// `|`
+    BitwiseOr,
+    /// `&&`
+    LogicalAnd,
+    /// `||`
+    LogicalOr,
+    /// `=`
+    Assign,
+    /// `*=`
+    MultiplyAssign,
+    /// `/=`
+    DivideAssign,
+    /// `%=`
+    RemainderAssign,
+    /// `+=`
+    AddAssign,
+    /// `-=`
+    SubtractAssign,
+    /// `<<=`
+    ShiftLeftAssign,
+    /// `>>=`
+    ShiftRightAssign,
+    /// `&=`
+    BitwiseAndAssign,
+    /// `^=`
+    BitwiseXorAssign,
+    /// `|=`
+    BitwiseOrAssign,
+    /// `,`
+    Comma,
+    /// `.*`
+    PointerToMemberDot,
+    /// `->*`
+    PointerToMemberArrow,
+}
+
+impl BinaryOperator {
+    //- Constructors -----------------------------
+
+    fn from_spelling(spelling: &str) -> Option<BinaryOperator> {
+        let operator = match spelling {
+            "*" => BinaryOperator::Multiply,
+            "/" => BinaryOperator::Divide,
+            "%" => BinaryOperator::Remainder,
+            "+" => BinaryOperator::Add,
+            "-" => BinaryOperator::Subtract,
+            "<<" => BinaryOperator::ShiftLeft,
+            ">>" => BinaryOperator::ShiftRight,
+            "<" => BinaryOperator::Less,
+            ">" => BinaryOperator::Greater,
+            "<=" => BinaryOperator::LessEqual,
+            ">=" => BinaryOperator::GreaterEqual,
+            "==" => BinaryOperator::Equal,
+            "!=" => BinaryOperator::NotEqual,
+            "&" => BinaryOperator::BitwiseAnd,
+            "^" => BinaryOperator::BitwiseXor,
+            "|" => BinaryOperator::BitwiseOr,
+            "&&" => BinaryOperator::LogicalAnd,
+            "||" => BinaryOperator::LogicalOr,
+            "=" => BinaryOperator::Assign,
+            "*=" => BinaryOperator::MultiplyAssign,
+            "/=" => BinaryOperator::DivideAssign,
+            "%=" => BinaryOperator::RemainderAssign,
+            "+=" => BinaryOperator::AddAssign,
+            "-=" => BinaryOperator::SubtractAssign,
+            "<<=" => BinaryOperator::ShiftLeftAssign,
+            ">>=" => BinaryOperator::ShiftRightAssign,
+            "&=" => BinaryOperator::BitwiseAndAssign,
+            "^=" => BinaryOperator::BitwiseXorAssign,
+            "|=" => BinaryOperator::BitwiseOrAssign,
+            "," => BinaryOperator::Comma,
+            ".*" => BinaryOperator::PointerToMemberDot,
+            "->*" => BinaryOperator::PointerToMemberArrow,
+            _ => return None,
+        };
+        Some(operator)
+    }
+
+    //- Accessors --------------------------------
+
+    /// Returns the operator a compound assignment operator applies before assigning (e.g., `+` for
+    /// `+=`), if this is a compound assignment operator.
+    pub fn get_compound_operator(&self) -> Option<BinaryOperator> {
+        let spelling = self.get_spelling();
+        if self.is_assignment() && spelling.len() > 1 {
+            BinaryOperator::from_spelling(&spelling[..spelling.len() - 1])
+        } else {
+            None
+        }
+    }
+
+    /// Returns the spelling of this operator (e.g., `+=`).
+    pub fn get_spelling(&self) -> &'static str {
+        match *self {
+            BinaryOperator::Multiply => "*",
+            BinaryOperator::Divide => "/",
+            BinaryOperator::Remainder => "%",
+            BinaryOperator::Add => "+",
+            BinaryOperator::Subtract => "-",
+            BinaryOperator::ShiftLeft => "<<",
+            BinaryOperator::ShiftRight => ">>",
+            BinaryOperator::Less => "<",
+            BinaryOperator::Greater => ">",
+            BinaryOperator::LessEqual => "<=",
+            BinaryOperator::GreaterEqual => ">=",
+            BinaryOperator::Equal => "==",
+            BinaryOperator::NotEqual => "!=",
+            BinaryOperator::BitwiseAnd => "&",
+            BinaryOperator::BitwiseXor => "^",
+            BinaryOperator::BitwiseOr => "|",
+            BinaryOperator::LogicalAnd => "&&",
+            BinaryOperator::LogicalOr => "||",
+            BinaryOperator::Assign => "=",
+            BinaryOperator::MultiplyAssign => "*=",
+            BinaryOperator::DivideAssign => "/=",
+            BinaryOperator::RemainderAssign => "%=",
+            BinaryOperator::AddAssign => "+=",
+            BinaryOperator::SubtractAssign => "-=",
+            BinaryOperator::ShiftLeftAssign => "<<=",
+            BinaryOperator::ShiftRightAssign => ">>=",
+            BinaryOperator::BitwiseAndAssign => "&=",
+            BinaryOperator::BitwiseXorAssign => "^=",
+            BinaryOperator::BitwiseOrAssign => "|=",
+            BinaryOperator::Comma => ",",
+            BinaryOperator::PointerToMemberDot => ".*",
+            BinaryOperator::PointerToMemberArrow => "->*",
+        }
+    }
+
+    /// Returns whether this operator is a simple or compound assignment operator.
+    pub fn is_assignment(&self) -> bool {
+        match *self {
+            BinaryOperator::Assign |
+            BinaryOperator::MultiplyAssign |
+            BinaryOperator::DivideAssign |
+            BinaryOperator::RemainderAssign |
+            BinaryOperator::AddAssign |
+            BinaryOperator::SubtractAssign |
+            BinaryOperator::ShiftLeftAssign |
+            BinaryOperator::ShiftRightAssign |
+            BinaryOperator::BitwiseAndAssign |
+            BinaryOperator::BitwiseXorAssign |
+            BinaryOperator::BitwiseOrAssign => true,
+            _ => false,
+        }
+    }
+
+    /// Returns whether this operator is a relational or equality operator.
+    pub fn is_comparison(&self) -> bool {
+        match *self {
+            BinaryOperator::Less |
+            BinaryOperator::Greater |
+            BinaryOperator::LessEqual |
+            BinaryOperator::GreaterEqual |
+            BinaryOperator::Equal |
+            BinaryOperator::NotEqual => true,
+            _ => false,
+        }
+    }
+
+    /// Returns whether this operator is a logical operator (i.e., `&&` or `||`).
+    pub fn is_logical(&self) -> bool {
+        *self == BinaryOperator::LogicalAnd || *self == BinaryOperator::LogicalOr
+    }
+}
+
+// CastKind ______________________________________
+
+/// Indicates the syntax of an explicit cast expression.
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash)]
+pub enum CastKind {
+    /// A C-style cast (e.g., `(int)x`).
+    CStyle,
+    /// A functional cast (e.g., `int(x)`).
+    Functional,
+    /// A `static_cast`.
+    Static,
+    /// A `dynamic_cast`.
+    Dynamic,
+    /// A `reinterpret_cast`.
+    Reinterpret,
+    /// A `const_cast`.
+    Const,
+    /// An Objective-C bridged cast (e.g., `(__bridge id)x`).
+    ObjCBridged,
+}
+
+// Literal _______________________________________
+
+/// The value of a literal expression.
+#[derive(Clone, Debug, PartialEq)]
+pub enum Literal {
+    /// The value of an integer literal.
+    Integer(u64),
+    /// The value of a floating point literal.
+    Float(f64),
+    /// The code point of a character literal.
+    Character(u32),
+    /// The value of a string literal or a sequence of concatenated string literals.
+    String(String),
+    /// The value of a boolean literal.
+    Bool(bool),
+    /// A null pointer literal (i.e., `nullptr`).
+    Nullptr,
+}
+
+// MemberAccess __________________________________
+
+/// Indicates how a member reference expression accesses a member.
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash)]
+pub enum MemberAccess {
+    /// The member is accessed through a pointer (e.g., `p->m`).
+    Arrow,
+    /// The member is accessed through an object (e.g., `o.m`).
+    Dot,
+}
+
+// UnaryOperator _________________________________
+
+/// Indicates the operator of a unary operator expression.
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash)]
+pub enum UnaryOperator {
+    /// Postfix `++`.
+    PostIncrement,
+    /// Postfix `--`.
+    PostDecrement,
+    /// Prefix `++`.
+    PreIncrement,
+    /// Prefix `--`.
+    PreDecrement,
+    /// `&`
+    AddressOf,
+    /// `*`
+    Dereference,
+    /// `+`
+    Plus,
+    /// `-`
+    Minus,
+    /// `~`
+    BitwiseNot,
+    /// `!`
+    LogicalNot,
+    /// `__real`
+    Real,
+    /// `__imag`
+    Imaginary,
+    /// `__extension__`
+    Extension,
+}
+
+impl UnaryOperator {
+    //- Constructors -----------------------------
+
+    fn from_spelling(spelling: &str, postfix: bool) -> Option<UnaryOperator> {
+        let operator = match (spelling, postfix) {
+            ("++", true) => UnaryOperator::PostIncrement,
+            ("--", true) => UnaryOperator::PostDecrement,
+            ("++", false) => UnaryOperator::PreIncrement,
+            ("--", false) => UnaryOperator::PreDecrement,
+            ("&", false) => UnaryOperator::AddressOf,
+            ("*", false) => UnaryOperator::Dereference,
+            ("+", false) => UnaryOperator::Plus,
+            ("-", false) => UnaryOperator::Minus,
+            ("~", false) => UnaryOperator::BitwiseNot,
+            ("!", false) => UnaryOperator::LogicalNot,
+            ("__real", false) | ("__real__", false) => UnaryOperator::Real,
+            ("__imag", false) | ("__imag__", false) => UnaryOperator::Imaginary,
+            ("__extension__", false) => UnaryOperator::Extension,
+            _ => return None,
+        };
+        Some(operator)
+    }
+
+    //- Accessors --------------------------------
+
+    /// Returns the spelling of this operator (e.g., `++`).
+    pub fn get_spelling(&self) -> &'static str {
+        match *self {
+            UnaryOperator::PostIncrement | UnaryOperator::PreIncrement => "++",
+            UnaryOperator::PostDecrement | UnaryOperator::PreDecrement => "--",
+            UnaryOperator::AddressOf => "&",
+            UnaryOperator::Dereference => "*",
+            UnaryOperator::Plus => "+",
+            UnaryOperator::Minus => "-",
+            UnaryOperator::BitwiseNot => "~",
+            UnaryOperator::LogicalNot => "!",
+            UnaryOperator::Real => "__real",
+            UnaryOperator::Imaginary => "__imag",
+            UnaryOperator::Extension => "__extension__",
+        }
+    }
+
+    /// Returns whether this operator is an increment or decrement operator.
+    pub fn is_increment_or_decrement(&self) -> bool {
+        match *self {
+            UnaryOperator::PostIncrement |
+            UnaryOperator::PostDecrement |
+            UnaryOperator::PreIncrement |
+            UnaryOperator::PreDecrement => true,
+            _ => false,
+        }
+    }
+
+    /// Returns whether this operator is a postfix operator.
+    pub fn is_postfix(&self) -> bool {
+        *self == UnaryOperator::PostIncrement || *self == UnaryOperator::PostDecrement
+    }
+}
+
+//================================================
+// Functions
+//================================================
+
+#[cfg(feature="gte_clang_3_9")]
+fn evaluate(entity: Entity) -> Option<Literal> {
+    match entity.evaluate() {
+        Some(EvaluationResult::Integer(integer)) => match entity.get_kind() {
+            EntityKind::CharacterLiteral => Some(Literal::Character(integer as u32)),
+            _ => Some(Literal::Integer(integer as u64)),
+        },
+        Some(EvaluationResult::Float(float)) => Some(Literal::Float(float)),
+        Some(EvaluationResult::String(string)) => string.into_string().ok().map(Literal::String),
+        _ => None,
+    }
+}
+
+#[cfg(not(feature="gte_clang_3_9"))]
+fn evaluate(_: Entity) -> Option<Literal> {
+    None
+}
+
+/// Returns the operator of the supplied binary operator or compound assignment operator
+/// expression, if possible.
+pub fn get_binary_operator(entity: Entity) -> Option<BinaryOperator> {
+    match entity.get_kind() {
+        EntityKind::BinaryOperator | EntityKind::CompoundAssignOperator => { },
+        _ => return None,
+    }
+
+    let lhs = match entity.get_children().first().and_then(|c| c.get_range()) {
+        Some(lhs) => get_offset(lhs.get_end()),
+        None => return None,
+    };
+    let tokens = get_tokens(entity);
+    let mut tokens = tokens.iter().filter(|t| t.get_kind() == TokenKind::Punctuation);
+    let operator = tokens.find(|t| get_offset(t.get_location()) >= lhs);
+    operator.and_then(|t| BinaryOperator::from_spelling(&t.get_spelling()))
+}
+
+/// Returns the syntax of the supplied explicit cast expression, if it is one.
+pub fn get_cast_kind(entity: Entity) -> Option<CastKind> {
+    match entity.get_kind() {
+        EntityKind::CStyleCastExpr => Some(CastKind::CStyle),
+        EntityKind::FunctionalCastExpr => Some(CastKind::Functional),
+        EntityKind::StaticCastExpr => Some(CastKind::Static),
+        EntityKind::DynamicCastExpr => Some(CastKind::Dynamic),
+        EntityKind::ReinterpretCastExpr => Some(CastKind::Reinterpret),
+        EntityKind::ConstCastExpr => Some(CastKind::Const),
+        EntityKind::ObjCBridgedCastExpr => Some(CastKind::ObjCBridged),
+        _ => None,
+    }
+}
+
+/// Returns the value of the supplied literal expression, if possible.
+///
+/// The values of integer, floating point, character, and string literals are determined from their
+/// tokens. If the tokens of such a literal are not literal tokens (e.g., because the literal is the
+/// result of a macro expansion), the literal is evaluated instead if `Entity::evaluate` is
+/// available.
+pub fn get_literal(entity: Entity) -> Option<Literal> {
+    let kind = entity.get_kind();
+    match kind {
+        EntityKind::BoolLiteralExpr | EntityKind::ObjCBoolLiteralExpr => {
+            let tokens = get_tokens(entity);
+            return match tokens.first().map(|t| t.get_spelling()) {
+                Some(ref spelling) if spelling == "true" || spelling == "YES" => {
+                    Some(Literal::Bool(true))
+                },
+                Some(ref spelling) if spelling == "false" || spelling == "NO" => {
+                    Some(Literal::Bool(false))
+                },
+                _ => None,
+            };
+        },
+        EntityKind::NullPtrLiteralExpr => return Some(Literal::Nullptr),
+        EntityKind::IntegerLiteral |
+        EntityKind::FloatingLiteral |
+        EntityKind::CharacterLiteral |
+        EntityKind::StringLiteral => { },
+        _ => return None,
+    }
+
+    let tokens = get_tokens(entity);
+    let literals = tokens.iter().take_while(|t| t.get_kind() == TokenKind::Literal);
+    let spellings = literals.map(|t| t.get_spelling()).collect::<Vec<_>>();
+    let literal = match (kind, spellings.first()) {
+        (EntityKind::IntegerLiteral, Some(spelling)) => {
+            parse_integer(spelling).map(Literal::Integer)
+        },
+        (EntityKind::FloatingLiteral, Some(spelling)) => parse_float(spelling).map(Literal::Float),
+        (EntityKind::CharacterLiteral, Some(spelling)) => {
+            parse_character(spelling).map(Literal::Character)
+        },
+        (EntityKind::StringLiteral, Some(_)) => {
+            let strings = spellings.iter().map(|s| parse_string(s)).collect::<Option<Vec<_>>>();
+            strings.map(|s| Literal::String(s.concat()))
+        },
+        _ => None,
+    };
+    literal.or_else(|| evaluate(entity))
+}
+
+/// Returns how the supplied member reference expression accesses its member, if it is a member
+/// reference expression with an explicit base expression.
+pub fn get_member_access(entity: Entity) -> Option<MemberAccess> {
+    if entity.get_kind() != EntityKind::MemberRefExpr {
+        return None;
+    }
+
+    let name = match entity.get_location() {
+        Some(name) => get_offset(name),
+        None => return None,
+    };
+    let tokens = get_tokens(entity);
+    let tokens = tokens.iter().filter(|t| get_offset(t.get_location()) < name);
+    let tokens = tokens.filter(|t| t.get_spelling() != "template");
+    match tokens.last().map(|t| t.get_spelling()) {
+        Some(ref spelling) if spelling == "->" => Some(MemberAccess::Arrow),
+        Some(ref spelling) if spelling == "." => Some(MemberAccess::Dot),
+        _ => None,
+    }
+}
+
+fn get_offset(location: SourceLocation) -> u32 {
+    location.get_file_location().offset
+}
+
+fn get_tokens<'tu>(entity: Entity<'tu>) -> Vec<Token<'tu>> {
+    entity.get_range().map_or_else(Vec::new, |r| r.tokenize())
+}
+
+/// Returns the operator of the supplied unary operator expression, if possible.
+pub fn get_unary_operator(entity: Entity) -> Option<UnaryOperator> {
+    if entity.get_kind() != EntityKind::UnaryOperator {
+        return None;
+    }
+
+    let operand = match entity.get_children().first().and_then(|c| c.get_range()) {
+        Some(operand) => operand,
+        None => return None,
+    };
+    let tokens = get_tokens(entity);
+    let prefix = tokens.first().map_or(false, |t| {
+        get_offset(t.get_location()) < get_offset(operand.get_start())
+    });
+    if prefix {
+        UnaryOperator::from_spelling(&tokens[0].get_spelling(), false)
+    } else {
+        let end = get_offset(operand.get_end());
+        let operator = tokens.iter().find(|t| get_offset(t.get_location()) >= end);
+        operator.and_then(|t| UnaryOperator::from_spelling(&t.get_spelling(), true))
+    }
+}
+
+fn parse_character(spelling: &str) -> Option<u32> {
+    let start = match spelling.find('\'') {
+        Some(start) => start + 1,
+        None => return None,
+    };
+    if spelling.len() < start + 1 || !spelling.ends_with('\'') {
+        return None;
+    }
+
+    let characters = unescape(&spelling[start..spelling.len() - 1]);
+    match characters.as_ref().map(|c| c.chars().collect::<Vec<_>>()) {
+        Some(ref characters) if characters.len() == 1 => Some(characters[0] as u32),
+        _ => None,
+    }
+}
+
+fn parse_float(spelling: &str) -> Option<f64> {
+    let spelling = spelling.replace('\'', "");
+    if spelling.starts_with("0x") || spelling.starts_with("0X") {
+        return None;
+    }
+    spelling.trim_right_matches(|c| "fFlL".contains(c)).parse().ok()
+}
+
+fn parse_integer(spelling: &str) -> Option<u64> {
+    let spelling = spelling.replace('\'', "");
+    let digits = spelling.trim_right_matches(|c| "uUlLzZ".contains(c));
+    let (digits, radix) = if digits.starts_with("0x") || digits.starts_with("0X") {
+        (&digits[2..], 16)
+    } else if digits.starts_with("0b") || digits.starts_with("0B") {
+        (&digits[2..], 2)
+    } else if digits.len() > 1 && digits.starts_with('0') {
+        (&digits[1..], 8)
+    } else {
+        (digits, 10)
+    };
+    u64::from_str_radix(digits, radix).ok()
+}
+
+fn parse_string(spelling: &str) -> Option<String> {
+    let start = match spelling.find('"') {
+        Some(start) => start,
+        None => return None,
+    };
+    if spelling.len() < start + 2 || !spelling.ends_with('"') {
+        return None;
+    }
+
+    if spelling[..start].ends_with('R') {
+        let contents = &spelling[start + 1..spelling.len() - 1];
+        let open = match contents.find('(') {
+            Some(open) => open,
+            None => return None,
+        };
+        let close = contents.len() - open - 1;
+        if close < open + 1 {
+            return None;
+        }
+        Some(contents[open + 1..close].into())
+    } else {
+        unescape(&spelling[start + 1..spelling.len() - 1])
+    }
+}
+
+fn unescape(string: &str) -> Option<String> {
+    let mut unescaped = String::new();
+    let mut characters = string.chars().peekable();
+    while let Some(character) = characters.next() {
+        if character != '\\' {
+            unescaped.push(character);
+            continue;
+        }
+
+        let escaped = match characters.next() {
+            Some(escaped) => escaped,
+            None => return None,
+        };
+        let code = match escaped {
+            'a' => 0x07,
+            'b' => 0x08,
+            'f' => 0x0C,
+            'n' => 0x0A,
+            'r' => 0x0D,
+            't' => 0x09,
+            'v' => 0x0B,
+            'e' | 'E' => 0x1B,
+            '0'...'7' => {
+                let mut code = escaped.to_digit(8).unwrap();
+                for _ in 0..2 {
+                    match characters.peek().and_then(|c| c.to_digit(8)) {
+                        Some(digit) => code = code * 8 + digit,
+                        None => break,
+                    }
+                    characters.next();
+                }
+                code
+            },
+            'x' | 'u' | 'U' => {
+                let limit = match escaped { 'u' => 4, 'U' => 8, _ => usize::max_value() };
+                let mut code = 0u32;
+                let mut count = 0;
+                while count < limit {
+                    match characters.peek().and_then(|c| c.to_digit(16)) {
+                        Some(digit) => code = code.wrapping_mul(16).wrapping_add(digit),
+                        None => break,
+                    }
+                    characters.next();
+                    count += 1;
+                }
+                if count == 0 {
+                    return None;
+                }
+                code
+            },
+            other => other as u32,
+        };
+
+        match char::from_u32(code) {
+            Some(character) => unescaped.push(character),
+            None => return None,
+        }
+    }
+    Some(unescaped)
+}
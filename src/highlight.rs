@@ -0,0 +1,454 @@
+// Copyright 2016 Kyle Mayes
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Semantic syntax highlighting.
+
+use std::collections::{HashMap};
+
+use utility;
+use super::{Availability, Entity, EntityKind, TranslationUnit};
+#[cfg(not(feature="gte_clang_3_6"))]
+use super::{Linkage};
+#[cfg(feature="gte_clang_3_6")]
+use super::{StorageClass};
+use super::token::{Token, TokenKind, TokenStream, TriviaKind};
+
+//================================================
+// Enums
+//================================================
+
+// SemanticKind __________________________________
+
+/// Indicates the semantic categorization of a token.
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash)]
+pub enum SemanticKind {
+    /// A comment.
+    Comment,
+    /// A field or an Objective-C instance variable.
+    Field,
+    /// An enum constant.
+    EnumConstant,
+    /// A function that is not a member of a class.
+    Function,
+    /// A keyword.
+    Keyword,
+    /// A variable with automatic storage.
+    LocalVariable,
+    /// A macro.
+    Macro,
+    /// A method, constructor, destructor, or conversion function.
+    Method,
+    /// A namespace or namespace alias.
+    Namespace,
+    /// A numeric literal.
+    Number,
+    /// A function parameter.
+    Parameter,
+    /// A string or character literal.
+    String,
+    /// A template parameter.
+    TemplateParameter,
+    /// A type (e.g., a class, an enum, or a typedef).
+    Type,
+    /// A variable that does not have automatic storage.
+    Variable,
+}
+
+impl SemanticKind {
+    //- Accessors --------------------------------
+
+    /// Returns the CSS class for this semantic token categorization.
+    pub fn get_css_class(&self) -> &'static str {
+        match *self {
+            SemanticKind::Comment => "comment",
+            SemanticKind::Field => "field",
+            SemanticKind::EnumConstant => "enum-constant",
+            SemanticKind::Function => "function",
+            SemanticKind::Keyword => "keyword",
+            SemanticKind::LocalVariable => "local-variable",
+            SemanticKind::Macro => "macro",
+            SemanticKind::Method => "method",
+            SemanticKind::Namespace => "namespace",
+            SemanticKind::Number => "number",
+            SemanticKind::Parameter => "parameter",
+            SemanticKind::String => "string",
+            SemanticKind::TemplateParameter => "template-parameter",
+            SemanticKind::Type => "type",
+            SemanticKind::Variable => "variable",
+        }
+    }
+
+    /// Returns the Language Server Protocol semantic token type for this semantic token
+    /// categorization.
+    pub fn get_lsp_type(&self) -> &'static str {
+        match *self {
+            SemanticKind::Comment => "comment",
+            SemanticKind::Field => "property",
+            SemanticKind::EnumConstant => "enumMember",
+            SemanticKind::Function => "function",
+            SemanticKind::Keyword => "keyword",
+            SemanticKind::LocalVariable | SemanticKind::Variable => "variable",
+            SemanticKind::Macro => "macro",
+            SemanticKind::Method => "method",
+            SemanticKind::Namespace => "namespace",
+            SemanticKind::Number => "number",
+            SemanticKind::Parameter => "parameter",
+            SemanticKind::String => "string",
+            SemanticKind::TemplateParameter => "typeParameter",
+            SemanticKind::Type => "type",
+        }
+    }
+}
+
+//================================================
+// Structs
+//================================================
+
+// SemanticModifiers _____________________________
+
+/// The modifiers that apply to a semantic token.
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq, Hash)]
+pub struct SemanticModifiers {
+    /// Indicates the token is the name of a declaration.
+    pub declaration: bool,
+    /// Indicates the token refers to a static variable, function, or method.
+    pub static_: bool,
+    /// Indicates the token refers to a `const` variable, parameter, or field or a `const` method.
+    pub const_: bool,
+    /// Indicates the token refers to a deprecated entity.
+    pub deprecated: bool,
+    /// Indicates the token refers to an entity declared in a system header.
+    pub system: bool,
+}
+
+impl SemanticModifiers {
+    //- Accessors --------------------------------
+
+    /// Returns these modifiers as a Language Server Protocol semantic token modifier bit set.
+    ///
+    /// The bits correspond to the modifiers returned by `get_lsp_modifiers`.
+    pub fn get_lsp_bits(&self) -> u32 {
+        let flags = [self.declaration, self.static_, self.const_, self.deprecated, self.system];
+        flags.iter().enumerate().fold(0, |b, (i, f)| if *f { b | (1 << i) } else { b })
+    }
+
+    fn get_css_classes(&self) -> Vec<&'static str> {
+        let flags = [self.declaration, self.static_, self.const_, self.deprecated, self.system];
+        let classes = ["declaration", "static", "const", "deprecated", "system"];
+        flags.iter().zip(classes.iter()).filter(|&(f, _)| *f).map(|(_, c)| *c).collect()
+    }
+}
+
+// SemanticToken _________________________________
+
+/// A token and its semantic categorization.
+#[derive(Copy, Clone, Debug)]
+pub struct SemanticToken<'tu> {
+    /// The token.
+    pub token: Token<'tu>,
+    /// The semantic categorization of the token.
+    pub kind: SemanticKind,
+    /// The modifiers that apply to the token.
+    pub modifiers: SemanticModifiers,
+    /// The AST entity the token refers to, if any.
+    pub entity: Option<Entity<'tu>>,
+}
+
+impl<'tu> SemanticToken<'tu> {
+    //- Accessors --------------------------------
+
+    /// Returns the space-separated CSS classes for this semantic token (e.g., `function static`).
+    pub fn get_css_classes(&self) -> String {
+        let mut classes = vec![self.kind.get_css_class()];
+        classes.extend(self.modifiers.get_css_classes());
+        classes.join(" ")
+    }
+}
+
+//================================================
+// Functions
+//================================================
+
+fn classify_entity(entity: &Entity) -> Option<SemanticKind> {
+    let parent = entity.get_semantic_parent().map(|p| p.get_kind());
+    match entity.get_kind() {
+        EntityKind::Namespace | EntityKind::NamespaceAlias => Some(SemanticKind::Namespace),
+        EntityKind::StructDecl |
+        EntityKind::UnionDecl |
+        EntityKind::ClassDecl |
+        EntityKind::EnumDecl |
+        EntityKind::TypedefDecl |
+        EntityKind::TypeAliasDecl |
+        EntityKind::ClassTemplate |
+        EntityKind::ClassTemplatePartialSpecialization |
+        EntityKind::ObjCInterfaceDecl |
+        EntityKind::ObjCProtocolDecl |
+        EntityKind::ObjCCategoryDecl => Some(SemanticKind::Type),
+        EntityKind::TemplateTypeParameter |
+        EntityKind::NonTypeTemplateParameter |
+        EntityKind::TemplateTemplateParameter => Some(SemanticKind::TemplateParameter),
+        EntityKind::FunctionDecl => Some(SemanticKind::Function),
+        EntityKind::FunctionTemplate => match parent {
+            Some(EntityKind::ClassDecl) |
+            Some(EntityKind::StructDecl) |
+            Some(EntityKind::UnionDecl) |
+            Some(EntityKind::ClassTemplate) |
+            Some(EntityKind::ClassTemplatePartialSpecialization) => Some(SemanticKind::Method),
+            _ => Some(SemanticKind::Function),
+        },
+        EntityKind::Method |
+        EntityKind::Constructor |
+        EntityKind::Destructor |
+        EntityKind::ConversionFunction |
+        EntityKind::ObjCInstanceMethodDecl |
+        EntityKind::ObjCClassMethodDecl => Some(SemanticKind::Method),
+        EntityKind::ParmDecl => Some(SemanticKind::Parameter),
+        EntityKind::VarDecl => if has_static_storage(entity) {
+            Some(SemanticKind::Variable)
+        } else {
+            match parent {
+                Some(EntityKind::FunctionDecl) |
+                Some(EntityKind::FunctionTemplate) |
+                Some(EntityKind::Method) |
+                Some(EntityKind::Constructor) |
+                Some(EntityKind::Destructor) |
+                Some(EntityKind::ConversionFunction) |
+                Some(EntityKind::ObjCInstanceMethodDecl) |
+                Some(EntityKind::ObjCClassMethodDecl) => Some(SemanticKind::LocalVariable),
+                _ => Some(SemanticKind::Variable),
+            }
+        },
+        EntityKind::FieldDecl | EntityKind::ObjCIvarDecl | EntityKind::ObjCPropertyDecl =>
+            Some(SemanticKind::Field),
+        EntityKind::EnumConstantDecl => Some(SemanticKind::EnumConstant),
+        EntityKind::MacroDefinition => Some(SemanticKind::Macro),
+        _ => None,
+    }
+}
+
+fn classify_token<'tu>(
+    token: Token<'tu>, entity: Option<Entity<'tu>>
+) -> Option<SemanticToken<'tu>> {
+    let kind = match token.get_kind() {
+        TokenKind::Comment => Some(SemanticKind::Comment),
+        TokenKind::Keyword => Some(SemanticKind::Keyword),
+        TokenKind::Literal => {
+            let spelling = token.get_spelling();
+            let prefix = ["u8", "u", "U", "L"].iter().find(|p| spelling.starts_with(*p));
+            let quote = &spelling[prefix.map_or(0, |p| p.len())..];
+            let quote = if quote.starts_with('R') { &quote[1..] } else { quote };
+            if quote.starts_with('"') || quote.starts_with('\'') {
+                Some(SemanticKind::String)
+            } else {
+                Some(SemanticKind::Number)
+            }
+        },
+        TokenKind::Identifier | TokenKind::Punctuation => None,
+    };
+
+    if let Some(kind) = kind {
+        let modifiers = SemanticModifiers::default();
+        return Some(SemanticToken { token: token, kind: kind, modifiers: modifiers, entity: None });
+    }
+
+    if token.get_kind() != TokenKind::Identifier {
+        return None;
+    }
+
+    let annotated = match entity {
+        Some(entity) => entity,
+        None => return None,
+    };
+    let declaration = match annotated.get_kind() {
+        EntityKind::MacroExpansion => annotated.get_reference().unwrap_or(annotated),
+        _ if annotated.is_declaration() || annotated.get_kind() == EntityKind::MacroDefinition =>
+            annotated,
+        _ => match annotated.get_reference() {
+            Some(declaration) => declaration,
+            None => return None,
+        },
+    };
+
+    classify_entity(&declaration).map(|kind| {
+        let location = annotated.get_location();
+        let const_ = declaration.get_type().map_or(false, |t| t.is_const_qualified());
+        let modifiers = SemanticModifiers {
+            declaration: declaration == annotated && location == Some(token.get_location()),
+            static_: is_static(&declaration),
+            const_: const_ || declaration.is_const_method(),
+            deprecated: declaration.get_availability() == Availability::Deprecated,
+            system: declaration.is_in_system_header(),
+        };
+        SemanticToken { token: token, kind: kind, modifiers: modifiers, entity: Some(declaration) }
+    })
+}
+
+#[cfg(feature="gte_clang_3_6")]
+fn has_static_storage(entity: &Entity) -> bool {
+    match entity.get_storage_class() {
+        Some(StorageClass::Static) | Some(StorageClass::Extern) => true,
+        _ => false,
+    }
+}
+
+#[cfg(not(feature="gte_clang_3_6"))]
+fn has_static_storage(entity: &Entity) -> bool {
+    match entity.get_linkage() {
+        Some(Linkage::Internal) | Some(Linkage::External) | Some(Linkage::UniqueExternal) => true,
+        _ => false,
+    }
+}
+
+#[cfg(feature="gte_clang_3_6")]
+fn is_static(entity: &Entity) -> bool {
+    entity.get_storage_class() == Some(StorageClass::Static) || entity.is_static_method()
+}
+
+#[cfg(not(feature="gte_clang_3_6"))]
+fn is_static(entity: &Entity) -> bool {
+    entity.get_linkage() == Some(Linkage::Internal) || entity.is_static_method()
+}
+
+/// Returns the semantic tokens for the supplied tokens.
+///
+/// Punctuation and identifiers which do not refer to an AST entity that can be categorized are
+/// omitted. The translation unit should be constructed with a detailed preprocessing record for
+/// macros to be categorized.
+pub fn classify<'tu>(
+    tu: &'tu TranslationUnit<'tu>, tokens: &[Token<'tu>]
+) -> Vec<SemanticToken<'tu>> {
+    let entities = tu.annotate(tokens);
+    tokens.iter().zip(entities.into_iter()).filter_map(|(t, e)| classify_token(*t, e)).collect()
+}
+
+/// Returns the supplied semantic tokens in the Language Server Protocol semantic token delta
+/// encoding.
+///
+/// The token types and modifiers are encoded as indices into the vectors returned by
+/// `get_lsp_types` and `get_lsp_modifiers`. Semantic tokens which span multiple lines are split
+/// into one semantic token per line.
+pub fn encode(tokens: &[SemanticToken]) -> Vec<u32> {
+    let types = get_lsp_types();
+
+    let mut segments = vec![];
+    for token in tokens {
+        let range = token.token.get_range();
+        let start = range.get_start().get_file_location();
+        let end = range.get_end().get_file_location();
+        if start.line == 0 || start.column == 0 {
+            continue;
+        }
+
+        let type_ = types.iter().position(|t| *t == token.kind.get_lsp_type()).unwrap() as u32;
+        let bits = token.modifiers.get_lsp_bits();
+        if start.line == end.line {
+            let length = end.column.saturating_sub(start.column);
+            segments.push((start.line - 1, start.column - 1, length, type_, bits));
+        } else {
+            let spelling = token.token.get_spelling();
+            for (i, text) in spelling.lines().enumerate() {
+                let line = start.line - 1 + i as u32;
+                let column = if i == 0 { start.column - 1 } else { 0 };
+                if !text.is_empty() {
+                    segments.push((line, column, text.len() as u32, type_, bits));
+                }
+            }
+        }
+    }
+    segments.sort();
+
+    let mut data = Vec::with_capacity(segments.len() * 5);
+    let (mut line, mut column) = (0, 0);
+    for (l, c, length, type_, bits) in segments {
+        let delta = if l == line { c - column } else { c };
+        data.extend_from_slice(&[l - line, delta, length, type_, bits]);
+        line = l;
+        column = c;
+    }
+    data
+}
+
+/// Returns the Language Server Protocol semantic token modifiers in the order used by
+/// `SemanticModifiers::get_lsp_bits`.
+pub fn get_lsp_modifiers() -> Vec<&'static str> {
+    vec!["declaration", "static", "readonly", "deprecated", "defaultLibrary"]
+}
+
+/// Returns the Language Server Protocol semantic token types in the order used by `encode`.
+pub fn get_lsp_types() -> Vec<&'static str> {
+    vec![
+        "namespace",
+        "type",
+        "typeParameter",
+        "parameter",
+        "variable",
+        "property",
+        "enumMember",
+        "function",
+        "method",
+        "macro",
+        "keyword",
+        "comment",
+        "string",
+        "number",
+    ]
+}
+
+/// Returns the supplied tokens and trivia as HTML with the supplied semantic tokens rendered as
+/// `span` elements with CSS classes.
+///
+/// Each `span` element has the CSS class for the semantic token categorization (e.g., `function`)
+/// and a CSS class for each modifier (`declaration`, `static`, `const`, `deprecated`, and
+/// `system`). Comments are rendered as `span` elements with the `comment` CSS class. The result
+/// is wrapped in a `pre` element with the `source` CSS class.
+pub fn render_html(stream: &TokenStream, tokens: &[SemanticToken]) -> String {
+    let tokens = tokens.iter().map(|t| {
+        (t.token.get_location().get_file_location().offset as usize, t)
+    }).collect::<HashMap<_, _>>();
+
+    let mut html = String::from("<pre class=\"source\">");
+    {
+        let mut render = |offset: usize, text: &str, comment: bool| {
+            let classes = match tokens.get(&offset) {
+                Some(token) => Some(token.get_css_classes()),
+                None if comment => Some("comment".into()),
+                None => None,
+            };
+
+            match classes {
+                Some(classes) => {
+                    html.push_str(&format!("<span class=\"{}\">", classes));
+                    utility::escape_html(text, &mut html);
+                    html.push_str("</span>");
+                },
+                None => utility::escape_html(text, &mut html),
+            }
+        };
+
+        for token in &stream.tokens {
+            for trivia in &token.leading {
+                render(trivia.offset, &trivia.text, trivia.kind == TriviaKind::Comment);
+            }
+            render(token.offset, &token.text, false);
+            for trivia in &token.trailing {
+                render(trivia.offset, &trivia.text, trivia.kind == TriviaKind::Comment);
+            }
+        }
+        for trivia in &stream.trailing {
+            render(trivia.offset, &trivia.text, trivia.kind == TriviaKind::Comment);
+        }
+    }
+    html.push_str("</pre>");
+    html
+}
@@ -0,0 +1,333 @@
+// Copyright 2016 Kyle Mayes
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Include graphs constructed from inclusion directives.
+
+use std::cmp;
+use std::collections::{HashMap, VecDeque};
+
+use rustc_serialize::json::{Json, ToJson};
+
+use utility;
+use super::{Entity, EntityKind, TranslationUnit};
+use super::source::{File, SourceRange};
+
+//================================================
+// Structs
+//================================================
+
+// Include _______________________________________
+
+/// An inclusion directive and the files it connects.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub struct Include<'tu> {
+    /// The file that contains the inclusion directive.
+    pub includer: File<'tu>,
+    /// The file that is included by the inclusion directive.
+    pub included: File<'tu>,
+    /// The inclusion directive.
+    pub directive: Entity<'tu>,
+    /// The source range of the inclusion directive.
+    pub range: SourceRange<'tu>,
+}
+
+// IncludeGraph __________________________________
+
+/// A graph of the inclusion directives in the files of a translation unit.
+#[derive(Clone, Debug)]
+pub struct IncludeGraph<'tu> {
+    tu: &'tu TranslationUnit<'tu>,
+    main: File<'tu>,
+    files: Vec<File<'tu>>,
+    includes: Vec<Include<'tu>>,
+}
+
+impl<'tu> IncludeGraph<'tu> {
+    //- Constructors -----------------------------
+
+    /// Constructs a new `IncludeGraph` from the inclusion directives in the files reachable from
+    /// the main file of the supplied translation unit.
+    ///
+    /// Returns `None` if the main file of the translation unit could not be found (e.g., a
+    /// translation unit loaded from an AST file whose main file no longer exists).
+    pub fn new(tu: &'tu TranslationUnit<'tu>) -> Option<IncludeGraph<'tu>> {
+        let main = match tu.get_entity().get_name().and_then(|n| tu.get_file(n)) {
+            Some(main) => main,
+            None => return None,
+        };
+
+        let mut files = vec![main];
+        let mut includes = vec![];
+        let mut index = 0;
+        while index < files.len() {
+            let includer = files[index];
+            includer.visit_includes(|e, r| {
+                if let Some(included) = e.get_file() {
+                    includes.push(Include {
+                        includer: includer, included: included, directive: e, range: r
+                    });
+                    if !files.contains(&included) {
+                        files.push(included);
+                    }
+                }
+                true
+            });
+            index += 1;
+        }
+
+        Some(IncludeGraph { tu: tu, main: main, files: files, includes: includes })
+    }
+
+    //- Accessors --------------------------------
+
+    /// Returns the inclusion directives in this include graph.
+    pub fn get_all_includes(&self) -> &[Include<'tu>] {
+        &self.includes
+    }
+
+    /// Returns the cycles in this include graph.
+    ///
+    /// Each cycle is a set of files (sorted by path) where each file directly or indirectly
+    /// includes every other file in the set. A file that includes itself is a cycle on its own.
+    pub fn get_cycles(&self) -> Vec<Vec<File<'tu>>> {
+        let mut tarjan = Tarjan {
+            graph: self, index: 0, indices: HashMap::new(), stack: vec![], cycles: vec![]
+        };
+        for file in &self.files {
+            if !tarjan.indices.contains_key(file) {
+                tarjan.visit(*file);
+            }
+        }
+
+        let mut cycles = tarjan.cycles;
+        for cycle in &mut cycles {
+            cycle.sort_by(|a, b| a.get_path().cmp(&b.get_path()));
+        }
+        cycles.sort_by(|a, b| a[0].get_path().cmp(&b[0].get_path()));
+        cycles
+    }
+
+    /// Returns the length of the shortest include chain from the main file to the supplied file,
+    /// if the supplied file is in this include graph.
+    ///
+    /// The depth of the main file is `0` and the depth of a file included by the main file is `1`.
+    pub fn get_depth(&self, file: File<'tu>) -> Option<usize> {
+        self.get_include_chain(file).map(|c| c.len())
+    }
+
+    /// Returns the files in this include graph in the order they were discovered, starting with the
+    /// main file.
+    pub fn get_files(&self) -> &[File<'tu>] {
+        &self.files
+    }
+
+    /// Returns the shortest chain of inclusion directives from the main file to the supplied file,
+    /// if the supplied file is in this include graph.
+    pub fn get_include_chain(&self, file: File<'tu>) -> Option<Vec<&Include<'tu>>> {
+        let mut parents: HashMap<File, Option<&Include>> = HashMap::new();
+        parents.insert(self.main, None);
+
+        let mut queue = VecDeque::new();
+        queue.push_back(self.main);
+        while let Some(includer) = queue.pop_front() {
+            if includer == file {
+                let mut chain = vec![];
+                let mut current = file;
+                while let Some(include) = parents[&current] {
+                    chain.push(include);
+                    current = include.includer;
+                }
+                chain.reverse();
+                return Some(chain);
+            }
+
+            for include in self.get_includes(includer) {
+                if !parents.contains_key(&include.included) {
+                    parents.insert(include.included, Some(include));
+                    queue.push_back(include.included);
+                }
+            }
+        }
+        None
+    }
+
+    /// Returns the inclusion directives that include the supplied file.
+    pub fn get_includers(&self, included: File<'tu>) -> Vec<&Include<'tu>> {
+        self.includes.iter().filter(|i| i.included == included).collect()
+    }
+
+    /// Returns the inclusion directives in the supplied file.
+    pub fn get_includes(&self, includer: File<'tu>) -> Vec<&Include<'tu>> {
+        self.includes.iter().filter(|i| i.includer == includer).collect()
+    }
+
+    /// Returns the main file of the translation unit this include graph was constructed from.
+    pub fn get_main_file(&self) -> File<'tu> {
+        self.main
+    }
+
+    /// Returns the inclusion directives in the supplied file that include files which declare
+    /// nothing referenced in the supplied file.
+    ///
+    /// The declarations in the files transitively included by an included file are considered to
+    /// be declared by that file so that a header which only includes other headers is not
+    /// reported if any of those headers is used. Macros are only considered if the translation unit
+    /// was constructed with a detailed preprocessing record.
+    pub fn get_unused_includes(&self, includer: File<'tu>) -> Vec<&Include<'tu>> {
+        let mut declarations = HashMap::new();
+        collect_declarations(self.tu.get_entity().get_children(), &mut declarations);
+
+        self.get_includes(includer).into_iter().filter(|include| {
+            let mut files = vec![include.included];
+            let mut index = 0;
+            while index < files.len() {
+                for nested in self.get_includes(files[index]) {
+                    if nested.included != includer && !files.contains(&nested.included) {
+                        files.push(nested.included);
+                    }
+                }
+                index += 1;
+            }
+
+            let declarations = files.iter().filter_map(|f| declarations.get(f));
+            !declarations.flat_map(|d| d.iter()).any(|d| {
+                includer.visit_references(*d, |_, _| false)
+            })
+        }).collect()
+    }
+
+    /// Returns this include graph in the DOT graph description language.
+    ///
+    /// Edges are labeled with the line of the inclusion directive.
+    pub fn to_dot(&self) -> String {
+        let mut dot = String::from("digraph includes {\n");
+        for file in &self.files {
+            let path = utility::quote_dot(&file.get_path().to_string_lossy());
+            dot.push_str(&format!("    {};\n", path));
+        }
+        for include in &self.includes {
+            let includer = utility::quote_dot(&include.includer.get_path().to_string_lossy());
+            let included = utility::quote_dot(&include.included.get_path().to_string_lossy());
+            let line = include.range.get_start().get_file_location().line;
+            dot.push_str(&format!("    {} -> {} [label=\"{}\"];\n", includer, included, line));
+        }
+        dot.push_str("}\n");
+        dot
+    }
+}
+
+impl<'tu> ToJson for IncludeGraph<'tu> {
+    /// Returns this include graph as a JSON object.
+    ///
+    /// The `main` member is the path of the main file, the `files` member is an array of objects
+    /// with `path` and `depth` members, and the `includes` member is an array of objects with
+    /// `includer`, `included`, `line`, and `column` members.
+    fn to_json(&self) -> Json {
+        let path = |file: &File| file.get_path().to_string_lossy().into_owned();
+
+        let files = self.files.iter().map(|f| {
+            object!("path" => path(f), "depth" => self.get_depth(*f).map(|d| d as u64))
+        }).collect::<Vec<_>>();
+
+        let includes = self.includes.iter().map(|i| {
+            let location = i.range.get_start().get_file_location();
+            object!(
+                "includer" => path(&i.includer),
+                "included" => path(&i.included),
+                "line" => location.line,
+                "column" => location.column,
+            )
+        }).collect::<Vec<_>>();
+
+        object!("main" => path(&self.main), "files" => files, "includes" => includes)
+    }
+}
+
+// Tarjan ________________________________________
+
+struct Tarjan<'g, 'tu: 'g> {
+    graph: &'g IncludeGraph<'tu>,
+    index: usize,
+    indices: HashMap<File<'tu>, (usize, usize, bool)>,
+    stack: Vec<File<'tu>>,
+    cycles: Vec<Vec<File<'tu>>>,
+}
+
+impl<'g, 'tu> Tarjan<'g, 'tu> {
+    fn visit(&mut self, file: File<'tu>) {
+        self.indices.insert(file, (self.index, self.index, true));
+        self.index += 1;
+        self.stack.push(file);
+
+        let mut recursive = false;
+        for include in self.graph.get_includes(file) {
+            let included = include.included;
+            recursive |= included == file;
+            let low = match self.indices.get(&included) {
+                None => {
+                    self.visit(included);
+                    Some(self.indices[&included].1)
+                },
+                Some(&(index, _, true)) => Some(index),
+                Some(_) => None,
+            };
+            if let Some(low) = low {
+                let entry = self.indices.get_mut(&file).unwrap();
+                entry.1 = cmp::min(entry.1, low);
+            }
+        }
+
+        let (index, low, _) = self.indices[&file];
+        if index == low {
+            let mut component = vec![];
+            loop {
+                let top = self.stack.pop().unwrap();
+                self.indices.get_mut(&top).unwrap().2 = false;
+                component.push(top);
+                if top == file {
+                    break;
+                }
+            }
+            if component.len() > 1 || recursive {
+                self.cycles.push(component);
+            }
+        }
+    }
+}
+
+//================================================
+// Functions
+//================================================
+
+fn collect_declarations<'tu>(
+    entities: Vec<Entity<'tu>>, declarations: &mut HashMap<File<'tu>, Vec<Entity<'tu>>>
+) {
+    for entity in entities {
+        let kind = entity.get_kind();
+        if !entity.is_declaration() && kind != EntityKind::MacroDefinition {
+            continue;
+        }
+
+        if let Some(location) = entity.get_location() {
+            let file = location.get_file_location().file;
+            declarations.entry(file).or_insert_with(Vec::new).push(entity);
+        }
+
+        match kind {
+            EntityKind::Namespace | EntityKind::LinkageSpec | EntityKind::EnumDecl =>
+                collect_declarations(entity.get_children(), declarations),
+            _ => { },
+        }
+    }
+}
@@ -0,0 +1,256 @@
+// Copyright 2016 Kyle Mayes
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Include-what-you-use style analysis of inclusion directives.
+
+use std::collections::{HashMap, HashSet};
+
+use super::{Entity, EntityKind, EntityVisitResult, TranslationUnit, TypeKind};
+use super::diagnostic::{FixIt};
+use super::source::{File, SourceLocation, SourceRange};
+
+//================================================
+// Structs
+//================================================
+
+// Analysis ______________________________________
+
+/// The results of analyzing the inclusion directives in a source file.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Analysis<'tu> {
+    /// The headers which declare symbols used in the source file but are not directly included.
+    pub missing: Vec<MissingInclude<'tu>>,
+    /// The directly included headers which declare no symbols used in the source file.
+    pub unused: Vec<UnusedInclude<'tu>>,
+    /// The classes, structs, and unions only used through pointers and references in the source
+    /// file whose definitions are not provided by any of the directly included headers.
+    pub forward_declarations: Vec<ForwardDeclaration<'tu>>,
+}
+
+// ForwardDeclaration ____________________________
+
+/// A class, struct, or union which could be forward declared instead of included.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct ForwardDeclaration<'tu> {
+    /// The class, struct, or union.
+    pub entity: Entity<'tu>,
+    /// The forward declaration (e.g., `namespace n { struct S; }`).
+    pub declaration: String,
+    /// The edit that inserts the forward declaration.
+    pub fix_it: FixIt<'tu>,
+}
+
+// MissingInclude ________________________________
+
+/// A header which declares symbols used in a source file but is not directly included.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct MissingInclude<'tu> {
+    /// The header.
+    pub header: File<'tu>,
+    /// The symbols declared in the header which are used in the source file.
+    pub symbols: Vec<Entity<'tu>>,
+    /// The edit that inserts an inclusion directive for the header.
+    pub fix_it: FixIt<'tu>,
+}
+
+// UnusedInclude _________________________________
+
+/// A directly included header which declares no symbols used in a source file.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct UnusedInclude<'tu> {
+    /// The header.
+    pub header: File<'tu>,
+    /// The inclusion directive.
+    pub directive: Entity<'tu>,
+    /// The edit that removes the line containing the inclusion directive.
+    pub fix_it: FixIt<'tu>,
+}
+
+//================================================
+// Functions
+//================================================
+
+fn get_file<'tu>(entity: Entity<'tu>) -> Option<File<'tu>> {
+    entity.get_location().map(|l| l.get_file_location().file)
+}
+
+fn get_forward_declaration(entity: Entity) -> Option<String> {
+    let keyword = match entity.get_kind() {
+        EntityKind::StructDecl => "struct",
+        EntityKind::ClassDecl => "class",
+        EntityKind::UnionDecl => "union",
+        _ => return None,
+    };
+
+    let mut declaration = format!("{} {};", keyword, entity.get_name().unwrap_or_else(String::new));
+    let mut parent = entity.get_semantic_parent();
+    while let Some(namespace) = parent {
+        match (namespace.get_kind(), namespace.get_name()) {
+            (EntityKind::TranslationUnit, _) => break,
+            (EntityKind::Namespace, Some(name)) =>
+                declaration = format!("namespace {} {{ {} }}", name, declaration),
+            _ => return None,
+        }
+        parent = namespace.get_semantic_parent();
+    }
+    Some(declaration)
+}
+
+fn get_include_path(file: File, header: File) -> String {
+    let path = header.get_path();
+    let directory = file.get_path().parent().map(|p| p.to_path_buf());
+    let relative = directory.as_ref().and_then(|d| path.strip_prefix(d).ok()).unwrap_or(&path);
+    let components = relative.components().map(|c| c.as_os_str().to_string_lossy().into_owned());
+    components.collect::<Vec<_>>().join("/")
+}
+
+fn get_next_line<'tu>(file: File<'tu>, line: u32) -> (SourceLocation<'tu>, bool) {
+    // `libclang` returns the location of the last character for lines past the end of a file.
+    let location = file.get_location(line + 1, 1);
+    let next = location.get_file_location();
+    if next.line == line + 1 {
+        (location, false)
+    } else {
+        (file.get_offset_location(next.offset + 1), true)
+    }
+}
+
+fn is_forward_declarable(parent: Entity) -> bool {
+    let type_ = match parent.get_kind() {
+        EntityKind::ParmDecl |
+        EntityKind::VarDecl |
+        EntityKind::FieldDecl |
+        EntityKind::TypedefDecl |
+        EntityKind::TypeAliasDecl => parent.get_type(),
+        EntityKind::FunctionDecl => parent.get_type().and_then(|t| t.get_result_type()),
+        _ => None,
+    };
+
+    match type_.map(|t| t.get_canonical_type().get_kind()) {
+        Some(TypeKind::Pointer) |
+        Some(TypeKind::LValueReference) |
+        Some(TypeKind::RValueReference) => true,
+        _ => false,
+    }
+}
+
+fn is_record(entity: Entity) -> bool {
+    match entity.get_kind() {
+        EntityKind::StructDecl | EntityKind::ClassDecl | EntityKind::UnionDecl => true,
+        _ => false,
+    }
+}
+
+fn is_reference(entity: Entity) -> bool {
+    match entity.get_kind() {
+        EntityKind::DeclRefExpr | EntityKind::MemberRefExpr | EntityKind::MacroExpansion => true,
+        _ => entity.is_reference(),
+    }
+}
+
+/// Analyzes the inclusion directives in the supplied source file.
+///
+/// A symbol used in the source file is considered to be provided by the header that contains its
+/// first declaration or, for classes, structs, and unions, its definition. Classes, structs, and
+/// unions which are only used through pointers and references in variable, parameter, field,
+/// typedef, and function return types do not need to be provided by a header and can instead be
+/// forward declared.
+///
+/// Symbols declared in system headers and inclusion directives for system headers are ignored.
+/// Macros are only considered if the translation unit was constructed with a detailed
+/// preprocessing record.
+pub fn analyze<'tu>(tu: &'tu TranslationUnit<'tu>, file: File<'tu>) -> Analysis<'tu> {
+    // Collect the uses of the symbols declared outside of the source file.
+    let mut full = HashSet::new();
+    let mut partial = HashSet::new();
+    let mut declared = HashSet::new();
+    tu.get_entity().visit_children(|e, p| {
+        if get_file(e) != Some(file) {
+            return EntityVisitResult::Continue;
+        }
+
+        if is_record(e) {
+            declared.insert(e.get_canonical_entity());
+        }
+
+        let reference = if is_reference(e) { e.get_reference() } else { None };
+        if let Some(reference) = reference {
+            let reference = if is_record(reference) {
+                reference.get_definition().unwrap_or(reference)
+            } else {
+                reference.get_canonical_entity()
+            };
+
+            if !reference.is_in_system_header() && get_file(reference) != Some(file) {
+                let forward = e.get_kind() == EntityKind::TypeRef && is_forward_declarable(p);
+                if forward && is_record(reference) {
+                    partial.insert(reference);
+                } else {
+                    full.insert(reference);
+                }
+            }
+        }
+
+        EntityVisitResult::Recurse
+    });
+
+    // Determine which headers are used and which are directly included.
+    let mut used: HashMap<File, Vec<Entity>> = HashMap::new();
+    for symbol in &full {
+        if let Some(header) = get_file(*symbol) {
+            used.entry(header).or_insert_with(Vec::new).push(*symbol);
+        }
+    }
+
+    let directives = file.get_includes().into_iter().filter_map(|d| {
+        d.get_file().map(|f| (d, f))
+    }).collect::<Vec<_>>();
+    let included = directives.iter().map(|&(_, f)| f).collect::<HashSet<_>>();
+
+    let (location, end) = match directives.last().and_then(|&(d, _)| d.get_range()) {
+        Some(range) => get_next_line(file, range.get_end().get_file_location().line),
+        None => (file.get_location(1, 1), false),
+    };
+    let format_line = |line: String| {
+        if end { format!("\n{}", line) } else { format!("{}\n", line) }
+    };
+
+    let mut missing = used.iter().filter(|&(h, _)| !included.contains(h)).map(|(h, s)| {
+        let mut symbols = s.clone();
+        symbols.sort_by_key(|s| s.get_location().map(|l| l.get_file_location().offset));
+        let directive = format!("#include \"{}\"", get_include_path(file, *h));
+        let fix_it = FixIt::Insertion(location, format_line(directive));
+        MissingInclude { header: *h, symbols: symbols, fix_it: fix_it }
+    }).collect::<Vec<_>>();
+    missing.sort_by(|a, b| a.header.get_path().cmp(&b.header.get_path()));
+
+    let unused = directives.iter().filter(|&&(_, h)| {
+        !used.contains_key(&h) && !h.get_location(1, 1).is_in_system_header()
+    }).filter_map(|&(d, h)| d.get_range().map(|r| {
+        let line = r.get_start().get_file_location().line;
+        let range = SourceRange::new(file.get_location(line, 1), get_next_line(file, line).0);
+        UnusedInclude { header: h, directive: d, fix_it: FixIt::Deletion(range) }
+    })).collect::<Vec<_>>();
+
+    let mut forward_declarations = partial.iter().filter(|s| {
+        let declared = declared.contains(&s.get_canonical_entity());
+        !declared && !full.contains(*s) && get_file(**s).map_or(true, |h| !used.contains_key(&h))
+    }).filter_map(|s| get_forward_declaration(*s).map(|d| {
+        let fix_it = FixIt::Insertion(location, format_line(d.clone()));
+        ForwardDeclaration { entity: *s, declaration: d, fix_it: fix_it }
+    })).collect::<Vec<_>>();
+    forward_declarations.sort_by(|a, b| a.declaration.cmp(&b.declaration));
+
+    Analysis { missing: missing, unused: unused, forward_declarations: forward_declarations }
+}
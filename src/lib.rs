@@ -33,6 +33,8 @@ extern crate lazy_static;
 
 extern crate clang_sys;
 extern crate libc;
+extern crate regex;
+extern crate rustc_serialize;
 
 #[macro_use]
 mod utility;
@@ -43,11 +45,31 @@ pub mod documentation;
 pub mod source;
 pub mod token;
 
+pub mod batch;
+pub mod browser;
+pub mod call_graph;
+pub mod control_flow;
+pub mod dedup;
+pub mod doccheck;
+pub mod document;
+pub mod expression;
+pub mod highlight;
+pub mod include_graph;
+pub mod iwyu;
+pub mod lint;
+pub mod lsp;
+pub mod metrics;
+pub mod naming;
+pub mod pch;
+pub mod refactor;
+pub mod rename;
 pub mod sonar;
+pub mod symbol_index;
 
 use std::cmp;
 use std::fmt;
 use std::hash;
+use std::io;
 use std::mem;
 use std::ptr;
 use std::slice;
@@ -65,12 +87,14 @@ use completion::{Completer, CompletionString};
 use diagnostic::{Diagnostic};
 use documentation::{Comment};
 use source::{File, Module, SourceLocation, SourceRange};
-use token::{Token};
+use token::{Token, TokenStream};
 use utility::{FromError, Nullable};
 
 mod error;
 pub use self::error::*;
 
+const VIRTUAL_DIRECTORY: &'static str = "/__clang_virtual__";
+
 //================================================
 // Enums
 //================================================
@@ -887,7 +911,16 @@ pub enum Visibility {
 
 lazy_static! { static ref AVAILABLE: AtomicBool = AtomicBool::new(true); }
 
-/// An empty type which prevents the use of this library from multiple threads simultaneously.
+/// An empty type which indicates that `libclang` is available.
+///
+/// Only one instance of `Clang` may exist at a time. The instance may be shared between threads
+/// (e.g., with `Arc`) and each thread may use `libclang` through its own indexes at the same time
+/// as the others since indexes, translation units, and the values borrowed from them cannot be
+/// sent to or shared with other threads.
+///
+/// When the `runtime` feature is enabled, the `libclang` shared library is loaded on the thread
+/// that constructs the instance of `Clang` and may not be available to other threads so `libclang`
+/// should only be used from that thread.
 #[derive(Debug)]
 pub struct Clang;
 
@@ -1670,6 +1703,61 @@ impl<'c> Index<'c> {
         Parser::new(self, f)
     }
 
+    /// Parses a translation unit from the supplied source code without touching the filesystem.
+    ///
+    /// The source code is used as the contents of a virtual main file with the supplied name in
+    /// the `/__clang_virtual__` directory, which does not need to exist.
+    ///
+    /// # Failures
+    ///
+    /// * an error occurs while deserializing an AST file
+    /// * `libclang` crashes
+    /// * an unknown error occurs
+    pub fn parse_string<N: AsRef<str>, C: AsRef<str>, S: AsRef<str>>(
+        &'c self, name: N, contents: C, language: Language, arguments: &[S]
+    ) -> Result<TranslationUnit<'c>, SourceError> {
+        self.parse_string_with_headers(name, contents, &[], language, arguments)
+    }
+
+    /// Parses a translation unit from the supplied source code and headers without touching the
+    /// filesystem.
+    ///
+    /// The source code is used as the contents of a virtual main file with the supplied name in
+    /// the `/__clang_virtual__` directory and the headers are used as the contents of virtual
+    /// files with the supplied names in the `/__clang_virtual__/include` directory, which is added
+    /// to the include search path. Neither directory needs to exist.
+    ///
+    /// # Failures
+    ///
+    /// * an error occurs while deserializing an AST file
+    /// * `libclang` crashes
+    /// * an unknown error occurs
+    pub fn parse_string_with_headers<N: AsRef<str>, C: AsRef<str>, S: AsRef<str>>(
+        &'c self,
+        name: N,
+        contents: C,
+        headers: &[(&str, &str)],
+        language: Language,
+        arguments: &[S],
+    ) -> Result<TranslationUnit<'c>, SourceError> {
+        let directory = Path::new(VIRTUAL_DIRECTORY);
+        let include = directory.join("include");
+
+        let mut unsaved = vec![Unsaved::new(directory.join(name.as_ref()), contents)];
+        unsaved.extend(headers.iter().map(|&(n, c)| Unsaved::new(include.join(n), c)));
+
+        let language = match language {
+            Language::C => "c",
+            Language::Cpp => "c++",
+            Language::ObjectiveC => "objective-c",
+        };
+        let mut all = vec!["-x".into(), language.into(), format!("-I{}", include.display())];
+        all.extend(arguments.iter().map(|a| a.as_ref().into()));
+
+        let mut parser = self.parser(directory.join(name.as_ref()));
+        parser.arguments(&all).unsaved(&unsaved).parse()
+    }
+
     /// Returns the thread options for this index.
     pub fn get_thread_options(&self) -> ThreadOptions {
         unsafe { ThreadOptions::from(clang_CXIndex_getGlobalOptions(self.ptr)) }
@@ -1752,6 +1840,35 @@ options! {
     }
 }
 
+// ParseOptions __________________________________
+
+options! {
+    /// A set of options that determines how a translation unit is parsed.
+    #[derive(Default)]
+    options ParseOptions: CXTranslationUnit_Flags {
+        /// Indicates whether a detailed preprocessing record will be constructed.
+        pub detailed_preprocessing_record: CXTranslationUnit_DetailedPreprocessingRecord,
+        /// Indicates whether the translation unit will be considered incomplete.
+        pub incomplete: CXTranslationUnit_Incomplete,
+        /// Indicates whether the preamble of the translation unit will be precompiled.
+        pub precompiled_preamble: CXTranslationUnit_PrecompiledPreamble,
+        /// Indicates whether certain code completion results will be cached.
+        pub cache_completion_results: CXTranslationUnit_CacheCompletionResults,
+        /// Indicates whether the translation unit will be serialized with `save`.
+        pub for_serialization: CXTranslationUnit_ForSerialization,
+        /// Indicates whether chained precompiled headers will be supported.
+        pub cxx_chained_pch: CXTranslationUnit_CXXChainedPCH,
+        /// Indicates whether function and method bodies will be skipped.
+        pub skip_function_bodies: CXTranslationUnit_SkipFunctionBodies,
+        /// Indicates whether documentation comment briefs will be included in code completion
+        /// results.
+        pub briefs_in_completion_results: CXTranslationUnit_IncludeBriefCommentsInCodeCompletion,
+    }, parseoptions: #[feature="gte_clang_3_8"] {
+        /// Indicates whether the precompiled preamble will be created on the first parse.
+        pub create_preamble_on_first_parse: CXTranslationUnit_CreatePreambleOnFirstParse,
+    }
+}
+
 // Parser ________________________________________
 
 builder! {
@@ -1780,6 +1897,30 @@ builder! {
         pub incomplete: CXTranslationUnit_Incomplete,
         /// Sets whether function and method bodies will be skipped.
         pub skip_function_bodies: CXTranslationUnit_SkipFunctionBodies,
+        /// Sets whether the translation unit will be prepared for serialization with `save`.
+        ///
+        /// This option is typically used together with the `incomplete` option when building a
+        /// precompiled header.
+        pub for_serialization: CXTranslationUnit_ForSerialization,
+        /// Sets whether chained precompiled headers will be supported.
+        ///
+        /// This option is only relevant to C++ translation units which include a precompiled
+        /// header that was itself built with a precompiled header.
+        pub cxx_chained_pch: CXTranslationUnit_CXXChainedPCH,
+        /// Sets whether the preamble of the translation unit will be precompiled and reused when
+        /// the translation unit is reparsed.
+        ///
+        /// The preamble is the set of inclusion directives and other preprocessor directives at the
+        /// beginning of the source file. This option increases the time it takes to reparse the
+        /// translation unit the first time but greatly reduces the time it takes to reparse the
+        /// translation unit afterwards if the preamble has not changed.
+        pub precompiled_preamble: CXTranslationUnit_PrecompiledPreamble,
+        /// Sets whether the precompiled preamble will be created when the translation unit is first
+        /// parsed instead of when it is first reparsed.
+        ///
+        /// This option has no effect unless the precompiled preamble option is also set.
+        #[cfg(feature="gte_clang_3_8")]
+        pub create_preamble_on_first_parse: CXTranslationUnit_CreatePreambleOnFirstParse,
         /// Sets whether processing will continue after a fatal error is encountered.
         #[cfg(feature="gte_clang_3_9")]
         pub keep_going: CXTranslationUnit_KeepGoing,
@@ -1810,6 +1951,16 @@ impl<'tu> Parser<'tu> {
         self
     }
 
+    /// Sets the options which are covered by `ParseOptions` (e.g., to the options returned by
+    /// `TranslationUnit::get_default_parse_options`).
+    ///
+    /// Options which are not covered by `ParseOptions` are not changed.
+    pub fn options(&mut self, options: ParseOptions) -> &mut Parser<'tu> {
+        let all = ParseOptions::from(CXTranslationUnit_Flags::all());
+        self.flags = (self.flags - all.into()) | options.into();
+        self
+    }
+
     /// Sets the unsaved files to use.
     pub fn unsaved(&mut self, unsaved: &[Unsaved]) -> &mut Parser<'tu> {
         self.unsaved = unsaved.into();
@@ -1924,6 +2075,12 @@ impl<'i> TranslationUnit<'i> {
 
     //- Accessors --------------------------------
 
+    /// Returns the options that are suitable for parsing a translation unit that will be edited
+    /// (e.g., a source file open in an editor).
+    pub fn get_default_parse_options() -> ParseOptions {
+        unsafe { clang_defaultEditingTranslationUnitOptions().into() }
+    }
+
     /// Returns the diagnostics for this translation unit.
     pub fn get_diagnostics(&'i self) -> Vec<Diagnostic<'i>> {
         iter!(clang_getNumDiagnostics(self.ptr), clang_getDiagnostic(self.ptr),).map(|d| {
@@ -1981,6 +2138,21 @@ impl<'i> TranslationUnit<'i> {
         SaveError::from_error(code)
     }
 
+    /// Tokenizes the contents of the main file of this translation unit on disk and returns the
+    /// resulting tokens and trivia.
+    ///
+    /// # Failures
+    ///
+    /// * an I/O error occurs while reading the main file
+    /// * the main file is not valid UTF-8
+    pub fn tokenize(&'i self) -> io::Result<TokenStream<'i>> {
+        let path = unsafe { utility::to_string(clang_getTranslationUnitSpelling(self.ptr)) };
+        match self.get_file(&path) {
+            Some(file) => file.tokenize(),
+            None => Err(io::Error::new(io::ErrorKind::NotFound, "main file not found")),
+        }
+    }
+
     //- Consumers --------------------------------
 
     /// Consumes this translation unit and reparses the source file it was created from with the
@@ -0,0 +1,798 @@
+// Copyright 2016 Kyle Mayes
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Checking translation units for problems with configurable rules.
+//!
+//! Findings can be suppressed with `NOLINT` comments. A `// NOLINT` comment suppresses all findings
+//! on the line it is on and a `// NOLINT(rule, ...)` comment suppresses the findings of the listed
+//! rules on the line it is on. `NOLINTNEXTLINE` comments suppress findings on the following line
+//! instead.
+
+use std::fmt;
+use std::fs;
+use std::io;
+use std::collections::{BTreeMap, BTreeSet, HashMap};
+use std::error::{Error};
+use std::io::{Write};
+use std::path::{Path, PathBuf};
+
+use rustc_serialize::json::{Json, ToJson};
+
+use utility;
+use super::{Entity, EntityKind, EntityVisitResult, TranslationUnit, TypeKind};
+use super::diagnostic::{FixIt, Severity};
+use super::source::{File, SourceRange};
+use super::token::{Token, TokenKind, Trivia, TriviaKind};
+
+#[cfg(feature="gte_clang_3_6")]
+use super::{StorageClass};
+
+type Suppressions = HashMap<u32, Option<BTreeSet<String>>>;
+
+//================================================
+// Traits
+//================================================
+
+// Rule __________________________________________
+
+/// A check performed on the entities and tokens of translation units.
+///
+/// Entities and tokens in system headers are not visited.
+pub trait Rule {
+    /// Returns the name of this rule (e.g., `banned-functions`).
+    ///
+    /// This name is used to configure this rule, to suppress the findings of this rule, and to
+    /// identify the findings of this rule in baselines.
+    fn get_name(&self) -> &str;
+
+    /// Returns the kinds of the entities `visit_entity` should be called with.
+    fn get_entity_kinds(&self) -> Vec<EntityKind> {
+        vec![]
+    }
+
+    /// Returns the severity of the findings of this rule unless it is configured otherwise.
+    fn get_severity(&self) -> Severity {
+        Severity::Warning
+    }
+
+    /// Configures this rule with the supplied JSON object, returning a description of the problem
+    /// if the configuration is invalid.
+    ///
+    /// The object also contains the `enabled` and `severity` members handled by the linter.
+    fn configure(&mut self, _: &Json) -> Result<(), String> {
+        Ok(())
+    }
+
+    /// Checks the supplied entity, which has one of the kinds returned by `get_entity_kinds`.
+    fn visit_entity<'tu>(&mut self, _: Entity<'tu>, _: &mut Reporter<'tu>) { }
+
+    /// Checks the supplied token, which is in the main file of the translation unit.
+    ///
+    /// Comments are included.
+    fn visit_token<'tu>(&mut self, _: Token<'tu>, _: &mut Reporter<'tu>) { }
+
+    /// Checks the supplied translation unit before its entities and tokens are visited.
+    fn visit_translation_unit<'tu>(&mut self, _: &'tu TranslationUnit<'tu>, _: &mut Reporter<'tu>) {
+    }
+}
+
+//================================================
+// Structs
+//================================================
+
+// BannedFunctions _______________________________
+
+/// A rule which finds calls to banned functions (`banned-functions`).
+///
+/// By default `gets`, `sprintf`, `strcat`, `strcpy`, and `vsprintf` are banned. The `functions`
+/// member of the configuration of this rule replaces the banned functions and is either an array
+/// of function names or an object which maps function names to the names of replacement functions
+/// (or `null`). Calls to banned functions with replacements have fix-its which replace the names.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct BannedFunctions {
+    functions: BTreeMap<String, Option<String>>,
+}
+
+impl BannedFunctions {
+    //- Constructors -----------------------------
+
+    /// Constructs a new `BannedFunctions` with the default banned functions.
+    pub fn new() -> BannedFunctions {
+        let functions = ["gets", "sprintf", "strcat", "strcpy", "vsprintf"];
+        BannedFunctions { functions: functions.iter().map(|f| (f.to_string(), None)).collect() }
+    }
+
+    //- Mutators ---------------------------------
+
+    /// Bans the function with the supplied name and suggests the supplied replacement, if any.
+    pub fn ban<S: Into<String>>(
+        &mut self, function: S, replacement: Option<S>
+    ) -> &mut BannedFunctions {
+        self.functions.insert(function.into(), replacement.map(|r| r.into()));
+        self
+    }
+}
+
+impl Default for BannedFunctions {
+    fn default() -> BannedFunctions {
+        BannedFunctions::new()
+    }
+}
+
+impl Rule for BannedFunctions {
+    fn get_name(&self) -> &str {
+        "banned-functions"
+    }
+
+    fn get_entity_kinds(&self) -> Vec<EntityKind> {
+        vec![EntityKind::CallExpr]
+    }
+
+    fn configure(&mut self, configuration: &Json) -> Result<(), String> {
+        let functions = match configuration.find("functions") {
+            Some(&Json::Array(ref functions)) => functions.iter().map(|f| match *f {
+                Json::String(ref function) => Ok((function.clone(), None)),
+                _ => Err("expected a function name".into()),
+            }).collect::<Result<BTreeMap<_, _>, String>>(),
+            Some(&Json::Object(ref functions)) => functions.iter().map(|(f, r)| match *r {
+                Json::String(ref replacement) => Ok((f.clone(), Some(replacement.clone()))),
+                Json::Null => Ok((f.clone(), None)),
+                _ => Err(format!("expected a replacement function name for `{}`", f)),
+            }).collect(),
+            Some(_) => Err("expected an array or object of function names".into()),
+            None => return Ok(()),
+        };
+        self.functions = try!(functions);
+        Ok(())
+    }
+
+    fn visit_entity<'tu>(&mut self, entity: Entity<'tu>, reporter: &mut Reporter<'tu>) {
+        let function = match entity.get_reference() {
+            Some(function) if function.get_kind() == EntityKind::FunctionDecl => function,
+            _ => return,
+        };
+        let name = match function.get_name() {
+            Some(name) => name,
+            None => return,
+        };
+        let replacement = match self.functions.get(&name) {
+            Some(replacement) => replacement,
+            None => return,
+        };
+
+        let range = match entity.get_range() {
+            Some(range) => range,
+            None => return,
+        };
+        let message = format!("call to banned function `{}`", name);
+        let mut reference = None;
+        entity.visit_children(|e, _| {
+            if e.get_kind() == EntityKind::DeclRefExpr && e.get_reference() == Some(function) {
+                reference = e.get_range();
+                EntityVisitResult::Break
+            } else {
+                EntityVisitResult::Recurse
+            }
+        });
+        match (replacement, reference) {
+            (&Some(ref replacement), Some(reference)) => {
+                let fix_it = FixIt::Replacement(reference, replacement.clone());
+                reporter.report_with_fix_its(range, message, vec![fix_it]);
+            },
+            _ => reporter.report(range, message),
+        }
+    }
+}
+
+// Baseline ______________________________________
+
+/// A multiset of known findings which are not reported.
+///
+/// Findings are identified by their rules, the paths of the files they are in, and their
+/// messages so that known findings are still recognized after the lines they are on move. Each
+/// finding in a baseline only prevents one matching finding from being reported so new findings
+/// with the same rule, path, and message as a known finding are still reported.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct Baseline {
+    findings: BTreeMap<(String, PathBuf, String), usize>,
+}
+
+impl Baseline {
+    //- Constructors -----------------------------
+
+    /// Constructs a new empty `Baseline`.
+    pub fn new() -> Baseline {
+        Baseline::default()
+    }
+
+    /// Constructs a new `Baseline` which contains the supplied findings.
+    pub fn from_findings(findings: &[Finding]) -> Baseline {
+        let mut baseline = Baseline::new();
+        for finding in findings {
+            baseline.insert(finding);
+        }
+        baseline
+    }
+
+    /// Loads a baseline previously saved to the supplied file.
+    ///
+    /// # Failures
+    ///
+    /// * an I/O error occurs while reading the file
+    /// * the file does not contain a valid baseline
+    pub fn load<P: AsRef<Path>>(path: P) -> io::Result<Baseline> {
+        let contents = try!(utility::read_file(path.as_ref()));
+
+        let invalid = || io::Error::new(io::ErrorKind::InvalidData, "invalid baseline");
+        let json = try!(Json::from_str(&contents).map_err(|_| invalid()));
+        let mut baseline = Baseline::new();
+        for finding in try!(json.as_array().ok_or_else(&invalid)) {
+            let string = |k| finding.find(k).and_then(|s| s.as_string()).ok_or_else(&invalid);
+            let rule = try!(string("rule")).into();
+            let path = try!(string("path")).into();
+            let message = try!(string("message")).into();
+            let count = match finding.find("count") {
+                Some(count) => try!(count.as_u64().ok_or_else(&invalid)) as usize,
+                None => 1,
+            };
+            *baseline.findings.entry((rule, path, message)).or_insert(0) += count;
+        }
+        Ok(baseline)
+    }
+
+    //- Accessors --------------------------------
+
+    /// Returns whether this baseline contains the supplied finding.
+    pub fn contains(&self, finding: &Finding) -> bool {
+        self.findings.contains_key(&get_key(finding))
+    }
+
+    /// Returns the number of findings in this baseline.
+    pub fn get_count(&self) -> usize {
+        self.findings.values().sum()
+    }
+
+    /// Saves this baseline to the supplied file.
+    ///
+    /// # Failures
+    ///
+    /// * an I/O error occurs while writing the file
+    pub fn save<P: AsRef<Path>>(&self, path: P) -> io::Result<()> {
+        let json = format!("{}\n", self.to_json().pretty());
+        fs::File::create(path).and_then(|mut f| f.write_all(json.as_bytes()))
+    }
+
+    //- Mutators ---------------------------------
+
+    /// Adds the supplied finding to this baseline and returns whether no matching finding was
+    /// already present.
+    pub fn insert(&mut self, finding: &Finding) -> bool {
+        let count = self.findings.entry(get_key(finding)).or_insert(0);
+        *count += 1;
+        *count == 1
+    }
+}
+
+impl ToJson for Baseline {
+    /// Returns this baseline as a JSON array of objects with `rule`, `path`, `message`, and
+    /// `count` members.
+    fn to_json(&self) -> Json {
+        Json::Array(self.findings.iter().map(|(&(ref rule, ref path, ref message), count)| {
+            object!(
+                "rule" => rule,
+                "path" => path.to_string_lossy().into_owned(),
+                "message" => message,
+                "count" => *count as u64,
+            )
+        }).collect())
+    }
+}
+
+// CStyleCasts ___________________________________
+
+/// A rule which finds C-style casts in C++ source files (`c-style-casts`).
+///
+/// Source files are considered to be C++ source files if the main file of the translation unit
+/// has a C++ file extension (e.g., `.cpp`). C-style casts between arithmetic types have fix-its
+/// which replace them with `static_cast`s.
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq)]
+pub struct CStyleCasts {
+    cplusplus: bool,
+}
+
+impl CStyleCasts {
+    //- Constructors -----------------------------
+
+    /// Constructs a new `CStyleCasts`.
+    pub fn new() -> CStyleCasts {
+        CStyleCasts::default()
+    }
+}
+
+impl Rule for CStyleCasts {
+    fn get_name(&self) -> &str {
+        "c-style-casts"
+    }
+
+    fn get_entity_kinds(&self) -> Vec<EntityKind> {
+        vec![EntityKind::CStyleCastExpr]
+    }
+
+    fn visit_entity<'tu>(&mut self, entity: Entity<'tu>, reporter: &mut Reporter<'tu>) {
+        if !self.cplusplus {
+            return;
+        }
+
+        let range = match entity.get_range() {
+            Some(range) => range,
+            None => return,
+        };
+        let message = "C-style cast".to_string();
+
+        let target = entity.get_type().map(|t| t.get_canonical_type().get_kind());
+        let children = entity.get_children();
+        let source = children.last().and_then(|c| c.get_type());
+        let source = source.map(|t| t.get_canonical_type().get_kind());
+        let arithmetic = target.map_or(false, is_arithmetic) && source.map_or(false, is_arithmetic);
+
+        let tokens = range.tokenize();
+        let mut depth = 0;
+        let close = tokens.iter().position(|t| {
+            match &t.get_spelling()[..] {
+                "(" => depth += 1,
+                ")" => depth -= 1,
+                _ => { },
+            }
+            depth == 0
+        });
+        match close {
+            Some(close) if arithmetic && close > 1 => {
+                let spellings = tokens[1..close].iter().map(|t| t.get_spelling());
+                let kind = spellings.collect::<Vec<_>>().join(" ");
+                let start = tokens[0].get_range().get_start();
+                let parentheses = SourceRange::new(start, tokens[close].get_range().get_end());
+                let fix_its = vec![
+                    FixIt::Replacement(parentheses, format!("static_cast<{}>(", kind)),
+                    FixIt::Insertion(range.get_end(), ")".into()),
+                ];
+                reporter.report_with_fix_its(range, message, fix_its);
+            },
+            _ => reporter.report(range, message),
+        }
+    }
+
+    fn visit_translation_unit<'tu>(
+        &mut self, tu: &'tu TranslationUnit<'tu>, _: &mut Reporter<'tu>
+    ) {
+        let cplusplus = tu.get_entity().get_name().map(|n| utility::is_cplusplus(Path::new(&n)));
+        self.cplusplus = cplusplus.unwrap_or(false);
+    }
+}
+
+// ConfigError ___________________________________
+
+/// An error in the configuration of a linter.
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub struct ConfigError {
+    /// The name of the rule whose configuration is invalid, if any.
+    pub rule: Option<String>,
+    /// A description of the error.
+    pub message: String,
+}
+
+impl Error for ConfigError {
+    fn description(&self) -> &str {
+        &self.message
+    }
+}
+
+impl fmt::Display for ConfigError {
+    fn fmt(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+        match self.rule {
+            Some(ref rule) => write!(formatter, "{}: {}", rule, self.message),
+            None => write!(formatter, "{}", self.message),
+        }
+    }
+}
+
+// Finding _______________________________________
+
+/// A problem found by a rule.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Finding<'tu> {
+    /// The name of the rule that found the problem.
+    pub rule: String,
+    /// The severity of the problem.
+    pub severity: Severity,
+    /// The source range the problem is in.
+    pub range: SourceRange<'tu>,
+    /// A description of the problem.
+    pub message: String,
+    /// The suggested fixes for the problem.
+    pub fix_its: Vec<FixIt<'tu>>,
+}
+
+// Linter ________________________________________
+
+/// Checks translation units with a set of rules.
+#[allow(missing_debug_implementations)]
+#[derive(Default)]
+pub struct Linter {
+    rules: Vec<State>,
+    baseline: Baseline,
+}
+
+impl Linter {
+    //- Constructors -----------------------------
+
+    /// Constructs a new `Linter` without any rules.
+    pub fn new() -> Linter {
+        Linter::default()
+    }
+
+    //- Accessors --------------------------------
+
+    /// Returns the names of the enabled rules of this linter.
+    pub fn get_enabled_rules(&self) -> Vec<&str> {
+        self.rules.iter().filter(|s| s.enabled).map(|s| s.rule.get_name()).collect()
+    }
+
+    //- Mutators ---------------------------------
+
+    /// Adds the supplied rule to this linter.
+    pub fn add_rule(&mut self, rule: Box<Rule>) -> &mut Linter {
+        let severity = rule.get_severity();
+        self.rules.push(State { rule: rule, enabled: true, severity: severity });
+        self
+    }
+
+    /// Sets the baseline whose findings are not reported by this linter.
+    pub fn baseline(&mut self, baseline: Baseline) -> &mut Linter {
+        self.baseline = baseline;
+        self
+    }
+
+    /// Configures the rules of this linter with the supplied JSON object.
+    ///
+    /// The object maps the names of rules to objects which configure those rules. The `enabled`
+    /// member of such an object is a boolean which enables or disables the rule and the `severity`
+    /// member is one of `note`, `warning`, `error`, or `fatal` and overrides the severity of the
+    /// findings of the rule. The remaining members are specific to the rule.
+    ///
+    /// # Failures
+    ///
+    /// * the configuration is not an object
+    /// * the configuration refers to a rule this linter does not have
+    /// * the configuration of a rule is invalid
+    pub fn configure(&mut self, configuration: &Json) -> Result<(), ConfigError> {
+        let error = |r: Option<&str>, m: &str| ConfigError {
+            rule: r.map(|r| r.into()), message: m.into()
+        };
+
+        let rules = configuration.as_object().ok_or_else(|| error(None, "expected an object"));
+        let rules = try!(rules);
+        for (name, configuration) in rules {
+            let state = self.rules.iter_mut().find(|s| s.rule.get_name() == name);
+            let state = try!(state.ok_or_else(|| error(None, &format!("unknown rule `{}`", name))));
+            let error = |m: &str| error(Some(name), m);
+
+            if !configuration.is_object() {
+                return Err(error("expected an object"));
+            }
+
+            match configuration.find("enabled") {
+                Some(&Json::Boolean(enabled)) => state.enabled = enabled,
+                Some(_) => return Err(error("expected `enabled` to be a boolean")),
+                None => { },
+            }
+
+            match configuration.find("severity").map(|s| s.as_string().and_then(parse_severity)) {
+                Some(Some(severity)) => state.severity = severity,
+                Some(None) => return Err(error("expected `severity` to be a severity")),
+                None => { },
+            }
+
+            try!(state.rule.configure(configuration).map_err(|m| error(&m)));
+        }
+        Ok(())
+    }
+
+    /// Checks the supplied translation unit with the enabled rules of this linter and returns the
+    /// findings that are not suppressed or in the baseline, sorted by position.
+    pub fn run<'tu>(&mut self, tu: &'tu TranslationUnit<'tu>) -> Vec<Finding<'tu>> {
+        let mut reporters = self.rules.iter().map(|_| Reporter::new()).collect::<Vec<_>>();
+        let enabled = (0..self.rules.len()).filter(|i| self.rules[*i].enabled).collect::<Vec<_>>();
+
+        for &index in &enabled {
+            self.rules[index].rule.visit_translation_unit(tu, &mut reporters[index]);
+        }
+
+        let kinds = enabled.iter().map(|i| (*i, self.rules[*i].rule.get_entity_kinds()));
+        let kinds = kinds.filter(|&(_, ref k)| !k.is_empty()).collect::<Vec<_>>();
+        if !kinds.is_empty() {
+            let rules = &mut self.rules;
+            tu.get_entity().visit_children(|e, _| {
+                if e.is_in_system_header() {
+                    return EntityVisitResult::Continue;
+                }
+
+                for &(index, ref kinds) in &kinds {
+                    if kinds.contains(&e.get_kind()) {
+                        rules[index].rule.visit_entity(e, &mut reporters[index]);
+                    }
+                }
+                EntityVisitResult::Recurse
+            });
+        }
+
+        let main = tu.get_entity().get_name().and_then(|n| tu.get_file(n));
+        for token in main.map_or_else(Vec::new, get_tokens) {
+            for &index in &enabled {
+                self.rules[index].rule.visit_token(token, &mut reporters[index]);
+            }
+        }
+
+        let mut findings = vec![];
+        for (state, reporter) in self.rules.iter().zip(reporters.into_iter()) {
+            findings.extend(reporter.findings.into_iter().map(|(range, message, fix_its)| Finding {
+                rule: state.rule.get_name().into(),
+                severity: state.severity,
+                range: range,
+                message: message,
+                fix_its: fix_its,
+            }));
+        }
+
+        findings.sort_by_key(|f| {
+            let location = f.range.get_start().get_file_location();
+            (location.file.get_path(), location.offset, f.rule.clone())
+        });
+
+        let mut suppressions = HashMap::new();
+        let mut baseline = self.baseline.findings.clone();
+        findings.retain(|f| {
+            let location = f.range.get_start().get_file_location();
+            let id = location.file.get_id();
+            let suppressions = suppressions.entry(id).or_insert_with(|| {
+                get_suppressions(location.file)
+            });
+            if is_suppressed(suppressions, location.line, &f.rule) {
+                return false;
+            }
+
+            match baseline.get_mut(&get_key(f)) {
+                Some(count) if *count != 0 => {
+                    *count -= 1;
+                    false
+                },
+                _ => true,
+            }
+        });
+        findings
+    }
+}
+
+// NonConstGlobals _______________________________
+
+/// A rule which finds definitions of non-`const` variables with static storage duration
+/// (`non-const-globals`).
+///
+/// This includes variables declared at namespace scope, `static` local variables, and `static`
+/// data members.
+#[cfg(feature="gte_clang_3_6")]
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq)]
+pub struct NonConstGlobals;
+
+#[cfg(feature="gte_clang_3_6")]
+impl NonConstGlobals {
+    //- Constructors -----------------------------
+
+    /// Constructs a new `NonConstGlobals`.
+    pub fn new() -> NonConstGlobals {
+        NonConstGlobals
+    }
+}
+
+#[cfg(feature="gte_clang_3_6")]
+impl Rule for NonConstGlobals {
+    fn get_name(&self) -> &str {
+        "non-const-globals"
+    }
+
+    fn get_entity_kinds(&self) -> Vec<EntityKind> {
+        vec![EntityKind::VarDecl]
+    }
+
+    fn visit_entity<'tu>(&mut self, entity: Entity<'tu>, reporter: &mut Reporter<'tu>) {
+        let storage = entity.get_storage_class();
+        if storage == Some(StorageClass::Extern) && !entity.is_definition() {
+            return;
+        }
+
+        let parent = entity.get_semantic_parent().map(|p| p.get_kind());
+        let global = match parent {
+            Some(EntityKind::TranslationUnit) | Some(EntityKind::Namespace) => true,
+            Some(EntityKind::ClassDecl) |
+            Some(EntityKind::ClassTemplate) |
+            Some(EntityKind::ClassTemplatePartialSpecialization) |
+            Some(EntityKind::StructDecl) |
+            Some(EntityKind::UnionDecl) => entity.is_definition(),
+            _ => storage == Some(StorageClass::Static),
+        };
+        if !global {
+            return;
+        }
+
+        let mut type_ = entity.get_type();
+        while let Some(element) = type_.and_then(|t| t.get_element_type()) {
+            type_ = Some(element);
+        }
+        if type_.map_or(true, |t| t.is_const_qualified()) {
+            return;
+        }
+
+        if let (Some(name), Some(range)) = (entity.get_name(), entity.get_range()) {
+            reporter.report(range, format!("non-const global variable `{}`", name));
+        }
+    }
+}
+
+// Reporter ______________________________________
+
+/// Collects the findings of a rule.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Reporter<'tu> {
+    findings: Vec<(SourceRange<'tu>, String, Vec<FixIt<'tu>>)>,
+}
+
+impl<'tu> Reporter<'tu> {
+    //- Constructors -----------------------------
+
+    fn new() -> Reporter<'tu> {
+        Reporter { findings: vec![] }
+    }
+
+    //- Mutators ---------------------------------
+
+    /// Reports a problem in the supplied source range.
+    pub fn report<S: Into<String>>(&mut self, range: SourceRange<'tu>, message: S) {
+        self.findings.push((range, message.into(), vec![]));
+    }
+
+    /// Reports a problem in the supplied source range with suggested fixes.
+    pub fn report_with_fix_its<S: Into<String>>(
+        &mut self, range: SourceRange<'tu>, message: S, fix_its: Vec<FixIt<'tu>>
+    ) {
+        self.findings.push((range, message.into(), fix_its));
+    }
+}
+
+// State _________________________________________
+
+struct State {
+    rule: Box<Rule>,
+    enabled: bool,
+    severity: Severity,
+}
+
+//================================================
+// Functions
+//================================================
+
+fn get_comments<'tu>(file: File<'tu>, trivia: &[Trivia]) -> Vec<Token<'tu>> {
+    trivia.iter().filter(|t| t.kind == TriviaKind::Comment).flat_map(|t| {
+        let start = file.get_offset_location(t.offset as u32);
+        let end = file.get_offset_location((t.offset + t.text.len()) as u32);
+        SourceRange::new(start, end).tokenize()
+    }).collect()
+}
+
+fn get_key(finding: &Finding) -> (String, PathBuf, String) {
+    let path = finding.range.get_start().get_file_location().file.get_path();
+    (finding.rule.clone(), path, finding.message.clone())
+}
+
+fn get_suppressions(file: File) -> Suppressions {
+    let mut suppressions = Suppressions::new();
+    for token in get_tokens(file) {
+        if token.get_kind() != TokenKind::Comment {
+            continue;
+        }
+
+        let spelling = token.get_spelling();
+        let (rest, line) = if let Some(index) = spelling.find("NOLINTNEXTLINE") {
+            let line = token.get_range().get_end().get_file_location().line + 1;
+            (&spelling[index + "NOLINTNEXTLINE".len()..], line)
+        } else if let Some(index) = spelling.find("NOLINT") {
+            (&spelling[index + "NOLINT".len()..], token.get_location().get_file_location().line)
+        } else {
+            continue;
+        };
+
+        let rules = if rest.starts_with('(') {
+            let rules = rest[1..].find(')').map(|e| rest[1..e + 1].split(','));
+            rules.map(|r| r.map(|r| r.trim().to_string()).collect::<Vec<_>>())
+        } else {
+            None
+        };
+
+        let existing = suppressions.entry(line).or_insert_with(|| Some(BTreeSet::new()));
+        match (existing.as_mut(), rules) {
+            (Some(existing), Some(rules)) => existing.extend(rules),
+            _ => *existing = None,
+        }
+    }
+    suppressions
+}
+
+fn get_tokens(file: File) -> Vec<Token> {
+    let stream = match file.tokenize() {
+        Ok(stream) => stream,
+        Err(_) => return vec![],
+    };
+
+    let mut tokens = vec![];
+    for token in &stream.tokens {
+        tokens.extend(get_comments(file, &token.leading));
+        tokens.push(token.token);
+        tokens.extend(get_comments(file, &token.trailing));
+    }
+    tokens.extend(get_comments(file, &stream.trailing));
+    tokens
+}
+
+fn is_arithmetic(kind: TypeKind) -> bool {
+    match kind {
+        TypeKind::Bool |
+        TypeKind::CharS |
+        TypeKind::CharU |
+        TypeKind::SChar |
+        TypeKind::UChar |
+        TypeKind::WChar |
+        TypeKind::Char16 |
+        TypeKind::Char32 |
+        TypeKind::Short |
+        TypeKind::UShort |
+        TypeKind::Int |
+        TypeKind::UInt |
+        TypeKind::Long |
+        TypeKind::ULong |
+        TypeKind::LongLong |
+        TypeKind::ULongLong |
+        TypeKind::Int128 |
+        TypeKind::UInt128 |
+        TypeKind::Float |
+        TypeKind::Double |
+        TypeKind::LongDouble => true,
+        _ => false,
+    }
+}
+
+fn is_suppressed(suppressions: &Suppressions, line: u32, rule: &str) -> bool {
+    match suppressions.get(&line) {
+        Some(&Some(ref rules)) => rules.contains(rule),
+        Some(&None) => true,
+        None => false,
+    }
+}
+
+fn parse_severity(severity: &str) -> Option<Severity> {
+    match severity {
+        "note" => Some(Severity::Note),
+        "warning" => Some(Severity::Warning),
+        "error" => Some(Severity::Error),
+        "fatal" => Some(Severity::Fatal),
+        _ => None,
+    }
+}
@@ -0,0 +1,674 @@
+// Copyright 2016 Kyle Mayes
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! A minimal C and C++ language server.
+//!
+//! The server speaks the Language Server Protocol (LSP) and supports diagnostics, code completion,
+//! signature help (`clang` 3.7 and later), hover, go-to-definition, document symbols, and semantic
+//! tokens. Every open document is backed by a translation unit which is reparsed with the unsaved
+//! contents of the document whenever it changes.
+//!
+//! Positions are converted between LSP and `libclang` by treating LSP characters as bytes, which
+//! is only exact for ASCII source files.
+
+use std::io;
+use std::collections::{HashMap};
+use std::io::{BufRead, Write};
+use std::path::{Path, PathBuf};
+
+use rustc_serialize::json::{Json, ToJson};
+
+use super::{Entity, EntityKind, Index, TranslationUnit, Unsaved};
+use super::highlight;
+use super::completion::CompletionItem;
+#[cfg(feature="gte_clang_3_7")]
+use super::completion::Signature;
+use super::diagnostic::{Diagnostic, Severity};
+use super::source::{Location, SourceRange};
+
+//================================================
+// Enums
+//================================================
+
+// SymbolKind ____________________________________
+
+/// Indicates the categorization of a symbol as defined by the Language Server Protocol.
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash)]
+pub enum SymbolKind {
+    /// A namespace.
+    Namespace = 3,
+    /// A C++ class or an Objective-C interface.
+    Class = 5,
+    /// A method.
+    Method = 6,
+    /// A property.
+    Property = 7,
+    /// A field.
+    Field = 8,
+    /// A constructor or destructor.
+    Constructor = 9,
+    /// An enum.
+    Enum = 10,
+    /// An Objective-C protocol.
+    Interface = 11,
+    /// A function.
+    Function = 12,
+    /// A variable.
+    Variable = 13,
+    /// A macro definition or another constant.
+    Constant = 14,
+    /// An enum constant.
+    EnumMember = 22,
+    /// A struct, union, or type alias.
+    Struct = 23,
+}
+
+impl SymbolKind {
+    //- Constructors -----------------------------
+
+    /// Constructs a new `SymbolKind` from the categorization of an AST entity, if applicable.
+    pub fn from_entity_kind(kind: EntityKind) -> Option<SymbolKind> {
+        match kind {
+            EntityKind::Namespace => Some(SymbolKind::Namespace),
+            EntityKind::ClassDecl | EntityKind::ClassTemplate | EntityKind::ObjCInterfaceDecl =>
+                Some(SymbolKind::Class),
+            EntityKind::Method |
+            EntityKind::ObjCInstanceMethodDecl |
+            EntityKind::ObjCClassMethodDecl => Some(SymbolKind::Method),
+            EntityKind::ObjCPropertyDecl => Some(SymbolKind::Property),
+            EntityKind::FieldDecl | EntityKind::ObjCIvarDecl => Some(SymbolKind::Field),
+            EntityKind::Constructor | EntityKind::Destructor => Some(SymbolKind::Constructor),
+            EntityKind::EnumDecl => Some(SymbolKind::Enum),
+            EntityKind::ObjCProtocolDecl => Some(SymbolKind::Interface),
+            EntityKind::FunctionDecl | EntityKind::FunctionTemplate =>
+                Some(SymbolKind::Function),
+            EntityKind::VarDecl => Some(SymbolKind::Variable),
+            EntityKind::MacroDefinition => Some(SymbolKind::Constant),
+            EntityKind::EnumConstantDecl => Some(SymbolKind::EnumMember),
+            EntityKind::StructDecl |
+            EntityKind::UnionDecl |
+            EntityKind::TypedefDecl |
+            EntityKind::TypeAliasDecl => Some(SymbolKind::Struct),
+            _ => None,
+        }
+    }
+}
+
+//================================================
+// Structs
+//================================================
+
+// Document ______________________________________
+
+struct Document<'i> {
+    text: String,
+    tu: Option<TranslationUnit<'i>>,
+}
+
+// Server ________________________________________
+
+/// A language server which keeps a translation unit for each open document.
+#[allow(missing_debug_implementations)]
+pub struct Server<'i> {
+    index: &'i Index<'i>,
+    arguments: Vec<String>,
+    documents: HashMap<PathBuf, Document<'i>>,
+    shutdown: bool,
+    exit: bool,
+}
+
+impl<'i> Server<'i> {
+    //- Constructors -----------------------------
+
+    /// Constructs a new `Server` which parses documents with the supplied compiler arguments.
+    pub fn new<S: AsRef<str>>(index: &'i Index<'i>, arguments: &[S]) -> Server<'i> {
+        let arguments = arguments.iter().map(|a| a.as_ref().into()).collect();
+        Server {
+            index: index,
+            arguments: arguments,
+            documents: HashMap::new(),
+            shutdown: false,
+            exit: false,
+        }
+    }
+
+    //- Accessors --------------------------------
+
+    /// Returns whether this server has received a `shutdown` request.
+    pub fn is_shutdown(&self) -> bool {
+        self.shutdown
+    }
+
+    /// Returns whether this server has received an `exit` notification.
+    pub fn is_exited(&self) -> bool {
+        self.exit
+    }
+
+    //- Mutators ---------------------------------
+
+    /// Handles the supplied message and returns the response and notifications it produced.
+    pub fn handle(&mut self, message: &Json) -> Vec<Json> {
+        let method = message.find("method").and_then(|m| m.as_string()).unwrap_or("");
+        let params = message.find("params").cloned().unwrap_or(Json::Null);
+        let id = message.find("id").cloned();
+
+        let mut messages = vec![];
+        let result = match method {
+            "initialize" => {
+                if let Some(arguments) = params.find_path(&["initializationOptions", "arguments"]) {
+                    let arguments = arguments.as_array().map_or(&[][..], |a| &a[..]);
+                    let arguments = arguments.iter().filter_map(|a| a.as_string());
+                    self.arguments.extend(arguments.map(|a| a.into()));
+                }
+                Some(get_capabilities())
+            },
+            "shutdown" => {
+                self.shutdown = true;
+                Some(Json::Null)
+            },
+            "exit" => {
+                self.exit = true;
+                None
+            },
+            "textDocument/didOpen" => {
+                if let Some(path) = get_path(&params) {
+                    let text = params.find_path(&["textDocument", "text"]);
+                    let text = text.and_then(|t| t.as_string()).unwrap_or("");
+                    self.open(path.clone(), text.into());
+                    messages.push(self.publish_diagnostics(&path));
+                }
+                None
+            },
+            "textDocument/didChange" => {
+                if let Some(path) = get_path(&params) {
+                    let changes = params.find("contentChanges").and_then(|c| c.as_array());
+                    let text = changes.and_then(|c| c.last()).and_then(|c| c.find("text"));
+                    if let Some(text) = text.and_then(|t| t.as_string()) {
+                        self.change(&path, text.into());
+                        messages.push(self.publish_diagnostics(&path));
+                    }
+                }
+                None
+            },
+            "textDocument/didClose" => {
+                if let Some(path) = get_path(&params) {
+                    self.documents.remove(&path);
+                    let uri = path_to_uri(&path);
+                    let params = object!("uri" => uri, "diagnostics" => Json::Array(vec![]));
+                    messages.push(notification("textDocument/publishDiagnostics", params));
+                }
+                None
+            },
+            "textDocument/completion" => Some(self.complete(&params)),
+            "textDocument/hover" => Some(self.hover(&params)),
+            #[cfg(feature="gte_clang_3_7")]
+            "textDocument/signatureHelp" => Some(self.help(&params)),
+            "textDocument/definition" => Some(self.define(&params)),
+            "textDocument/documentSymbol" => Some(self.list_symbols(&params)),
+            "textDocument/semanticTokens/full" => Some(self.highlight(&params)),
+            _ => if id.is_some() {
+                let error = object!("code" => -32601i64, "message" => "method not found");
+                messages.push(object!("jsonrpc" => "2.0", "id" => id.clone(), "error" => error));
+                None
+            } else {
+                None
+            },
+        };
+
+        if let (Some(id), Some(result)) = (id, result) {
+            messages.insert(0, object!("jsonrpc" => "2.0", "id" => id, "result" => result));
+        }
+
+        messages
+    }
+
+    /// Reads and handles messages from the supplied reader until an `exit` notification is received
+    /// or the reader is exhausted and returns whether a `shutdown` request was received.
+    ///
+    /// # Failures
+    ///
+    /// * an I/O error occurs
+    /// * a message is malformed
+    pub fn run<R: BufRead, W: Write>(
+        &mut self, reader: &mut R, writer: &mut W
+    ) -> io::Result<bool> {
+        while let Some(message) = try!(read_message(reader)) {
+            for message in self.handle(&message) {
+                try!(write_message(writer, &message));
+            }
+
+            if self.exit {
+                break;
+            }
+        }
+
+        Ok(self.shutdown)
+    }
+
+    fn open(&mut self, path: PathBuf, text: String) {
+        let tu = {
+            let unsaved = Unsaved::new(&path, &text);
+            let mut parser = self.index.parser(&path);
+            parser.arguments(&self.arguments).unsaved(&[unsaved]);
+            parser.detailed_preprocessing_record(true).briefs_in_completion_results(true);
+            parser.parse().ok()
+        };
+        self.documents.insert(path, Document { text: text, tu: tu });
+    }
+
+    fn change(&mut self, path: &Path, text: String) {
+        let tu = self.documents.get_mut(path).and_then(|d| d.tu.take());
+        match tu {
+            Some(tu) => {
+                let tu = tu.reparse(&[Unsaved::new(path, &text)]).ok();
+                self.documents.insert(path.into(), Document { text: text, tu: tu });
+            },
+            None => self.open(path.into(), text),
+        }
+    }
+
+    fn complete(&self, params: &Json) -> Json {
+        let mut items = vec![];
+        if let Some((path, line, column, document)) = self.get_position(params) {
+            if let Some(ref tu) = document.tu {
+                let mut completer = tu.completer(&path, line, column);
+                completer.briefs(true).unsaved(&[Unsaved::new(&path, &document.text)]);
+                let results = completer.complete();
+                let mut results = results.get_results();
+                results.sort();
+                for (i, result) in results.iter().enumerate() {
+                    items.push(item_to_json(&result.as_item(), i));
+                }
+            }
+        }
+        object!("isIncomplete" => false, "items" => Json::Array(items))
+    }
+
+    fn define(&self, params: &Json) -> Json {
+        if let Some((path, line, column, document)) = self.get_position(params) {
+            if let Some(ref tu) = document.tu {
+                let definition = get_entity(tu, &path, line, column).and_then(|e| {
+                    let reference = e.get_reference().unwrap_or(e);
+                    reference.get_definition().or(Some(reference))
+                });
+                if let Some(location) = definition.and_then(|d| entity_to_location(&d)) {
+                    return location;
+                }
+            }
+        }
+        Json::Null
+    }
+
+    #[cfg(feature="gte_clang_3_7")]
+    fn help(&self, params: &Json) -> Json {
+        if let Some((path, line, column, document)) = self.get_position(params) {
+            if let Some(ref tu) = document.tu {
+                let mut completer = tu.completer(&path, line, column);
+                completer.briefs(true).unsaved(&[Unsaved::new(&path, &document.text)]);
+                let help = completer.signature_help();
+                if !help.signatures.is_empty() {
+                    let signatures = help.signatures.iter().map(signature_to_json);
+                    return object!(
+                        "signatures" => signatures.collect::<Vec<_>>(),
+                        "activeSignature" => help.active_signature.unwrap_or(0) as u64,
+                        "activeParameter" => help.get_active_parameter().unwrap_or(0) as u64,
+                    );
+                }
+            }
+        }
+        Json::Null
+    }
+
+    fn highlight(&self, params: &Json) -> Json {
+        let mut data = vec![];
+        if let Some(path) = get_path(params) {
+            if let Some(document) = self.documents.get(&path) {
+                if let Some(ref tu) = document.tu {
+                    if let Some(file) = tu.get_file(&path) {
+                        let start = file.get_offset_location(0);
+                        let end = file.get_offset_location(document.text.len() as u32);
+                        let tokens = SourceRange::new(start, end).tokenize();
+                        data = highlight::encode(&highlight::classify(tu, &tokens));
+                    }
+                }
+            }
+        }
+        object!("data" => data)
+    }
+
+    fn hover(&self, params: &Json) -> Json {
+        if let Some((path, line, column, document)) = self.get_position(params) {
+            if let Some(ref tu) = document.tu {
+                if let Some(entity) = get_entity(tu, &path, line, column) {
+                    let entity = entity.get_reference().unwrap_or(entity);
+                    let mut contents = String::new();
+                    if let Some(name) = entity.get_display_name() {
+                        let type_ = entity.get_type().map(|t| t.get_display_name());
+                        let type_ = type_.map_or(String::new(), |t| format!("{} ", t));
+                        contents.push_str(&format!("```cpp\n{}{}\n```", type_, name));
+                    }
+                    if let Some(comment) = entity.get_parsed_comment() {
+                        contents.push_str("\n\n");
+                        contents.push_str(&comment.as_markdown(false));
+                    }
+                    if !contents.is_empty() {
+                        let contents = object!("kind" => "markdown", "value" => contents);
+                        return object!("contents" => contents);
+                    }
+                }
+            }
+        }
+        Json::Null
+    }
+
+    fn list_symbols(&self, params: &Json) -> Json {
+        let mut symbols = vec![];
+        if let Some(document) = get_path(params).and_then(|p| self.documents.get(&p)) {
+            if let Some(ref tu) = document.tu {
+                for entity in tu.get_entity().get_children() {
+                    add_symbols(entity, None, &mut symbols);
+                }
+            }
+        }
+        Json::Array(symbols)
+    }
+
+    fn get_position(&self, params: &Json) -> Option<(PathBuf, u32, u32, &Document<'i>)> {
+        let path = match get_path(params) {
+            Some(path) => path,
+            None => return None,
+        };
+        let line = params.find_path(&["position", "line"]).and_then(|l| l.as_u64());
+        let column = params.find_path(&["position", "character"]).and_then(|c| c.as_u64());
+        match (line, column, self.documents.get(&path)) {
+            (Some(l), Some(c), Some(d)) => Some((path.clone(), l as u32 + 1, c as u32 + 1, d)),
+            _ => None,
+        }
+    }
+
+    fn publish_diagnostics(&self, path: &Path) -> Json {
+        let mut diagnostics = vec![];
+        if let Some(tu) = self.documents.get(path).and_then(|d| d.tu.as_ref()) {
+            for diagnostic in tu.get_diagnostics() {
+                if let Some(diagnostic) = diagnostic_to_json(&diagnostic) {
+                    diagnostics.push(diagnostic);
+                }
+            }
+        }
+        let params = object!("uri" => path_to_uri(path), "diagnostics" => Json::Array(diagnostics));
+        notification("textDocument/publishDiagnostics", params)
+    }
+}
+
+//================================================
+// Functions
+//================================================
+
+fn add_symbols<'tu>(entity: Entity<'tu>, container: Option<&str>, symbols: &mut Vec<Json>) {
+    if !entity.is_in_main_file() {
+        return;
+    }
+
+    let kind = SymbolKind::from_entity_kind(entity.get_kind());
+    if let (Some(kind), Some(name)) = (kind, entity.get_name()) {
+        if let Some(location) = entity_to_location(&entity) {
+            let mut symbol = object!(
+                "name" => &name[..],
+                "kind" => kind as u64,
+                "location" => location,
+            );
+            if let Some(container) = container {
+                symbol.as_object_mut().unwrap().insert("containerName".into(), container.to_json());
+            }
+            symbols.push(symbol);
+        }
+
+        match entity.get_kind() {
+            EntityKind::Namespace |
+            EntityKind::ClassDecl |
+            EntityKind::ClassTemplate |
+            EntityKind::StructDecl |
+            EntityKind::UnionDecl |
+            EntityKind::EnumDecl => for child in entity.get_children() {
+                add_symbols(child, Some(&name), symbols);
+            },
+            _ => { },
+        }
+    }
+}
+
+fn diagnostic_to_json(diagnostic: &Diagnostic) -> Option<Json> {
+    let location = diagnostic.get_location();
+    if !location.is_in_main_file() {
+        return None;
+    }
+
+    let severity = match diagnostic.get_severity() {
+        Severity::Ignored => return None,
+        Severity::Note => 3u64,
+        Severity::Warning => 2,
+        Severity::Error | Severity::Fatal => 1,
+    };
+    let range = match diagnostic.get_ranges().first() {
+        Some(range) => range_to_json(range),
+        None => {
+            let position = location_to_json(&location.get_file_location());
+            object!("start" => position.clone(), "end" => position)
+        },
+    };
+    Some(object!(
+        "range" => range,
+        "severity" => severity,
+        "source" => "clang",
+        "message" => diagnostic.get_text(),
+    ))
+}
+
+fn entity_to_location(entity: &Entity) -> Option<Json> {
+    let range = entity.get_name_ranges().first().cloned().or_else(|| entity.get_range());
+    range.map(|r| {
+        let path = r.get_start().get_file_location().file.get_path();
+        object!("uri" => path_to_uri(&path), "range" => range_to_json(&r))
+    })
+}
+
+fn get_capabilities() -> Json {
+    let triggers = vec![".".to_json(), ">".to_json(), ":".to_json()];
+    let completion = object!("triggerCharacters" => triggers);
+    let types = highlight::get_lsp_types().iter().map(|t| t.to_json()).collect::<Vec<_>>();
+    let modifiers = highlight::get_lsp_modifiers().iter().map(|m| m.to_json()).collect::<Vec<_>>();
+    let legend = object!("tokenTypes" => types, "tokenModifiers" => modifiers);
+    let mut capabilities = object!(
+        "textDocumentSync" => 1u64,
+        "completionProvider" => completion,
+        "hoverProvider" => true,
+        "definitionProvider" => true,
+        "documentSymbolProvider" => true,
+        "semanticTokensProvider" => object!("legend" => legend, "full" => true),
+    );
+    if cfg!(feature="gte_clang_3_7") {
+        let triggers = vec!["(".to_json(), ",".to_json()];
+        let help = object!("triggerCharacters" => triggers);
+        capabilities.as_object_mut().unwrap().insert("signatureHelpProvider".into(), help);
+    }
+    object!("capabilities" => capabilities)
+}
+
+fn get_entity<'tu>(
+    tu: &'tu TranslationUnit<'tu>, path: &Path, line: u32, column: u32
+) -> Option<Entity<'tu>> {
+    tu.get_file(path).and_then(|f| f.get_location(line, column).get_entity())
+}
+
+fn get_path(params: &Json) -> Option<PathBuf> {
+    let uri = params.find_path(&["textDocument", "uri"]).and_then(|u| u.as_string());
+    uri.and_then(uri_to_path)
+}
+
+fn item_to_json(item: &CompletionItem, index: usize) -> Json {
+    let mut json = object!(
+        "label" => &item.label[..],
+        "kind" => item.kind as u64,
+        "sortText" => format!("{:05}", index),
+        "insertText" => &item.snippets[0][..],
+        "insertTextFormat" => 2u64,
+    );
+    {
+        let object = json.as_object_mut().unwrap();
+        if let Some(ref detail) = item.detail {
+            object.insert("detail".into(), detail.to_json());
+        }
+        if let Some(ref documentation) = item.documentation {
+            object.insert("documentation".into(), documentation.to_json());
+        }
+        if let Some(ref filter) = item.filter_text {
+            object.insert("filterText".into(), filter.to_json());
+        }
+    }
+    json
+}
+
+fn location_to_json(location: &Location) -> Json {
+    let line = location.line.saturating_sub(1) as u64;
+    let character = location.column.saturating_sub(1) as u64;
+    object!("line" => line, "character" => character)
+}
+
+fn notification(method: &str, params: Json) -> Json {
+    object!("jsonrpc" => "2.0", "method" => method, "params" => params)
+}
+
+fn range_to_json(range: &SourceRange) -> Json {
+    let start = location_to_json(&range.get_start().get_file_location());
+    let end = location_to_json(&range.get_end().get_file_location());
+    object!("start" => start, "end" => end)
+}
+
+#[cfg(feature="gte_clang_3_7")]
+fn signature_to_json(signature: &Signature) -> Json {
+    let parameters = signature.parameters.iter().map(|p| object!("label" => &p[..]));
+    let mut json = object!(
+        "label" => &signature.label[..],
+        "parameters" => parameters.collect::<Vec<_>>(),
+    );
+    if let Some(ref documentation) = signature.documentation {
+        let object = json.as_object_mut().unwrap();
+        object.insert("documentation".into(), documentation.to_json());
+    }
+    json
+}
+
+/// Returns the `file` URI for the supplied path.
+pub fn path_to_uri<P: AsRef<Path>>(path: P) -> String {
+    let path = path.as_ref().to_string_lossy().replace('\\', "/");
+    let mut uri = String::from("file://");
+    if !path.starts_with('/') {
+        uri.push('/');
+    }
+    for c in path.chars() {
+        match c {
+            ' ' => uri.push_str("%20"),
+            '#' => uri.push_str("%23"),
+            '%' => uri.push_str("%25"),
+            '?' => uri.push_str("%3F"),
+            c => uri.push(c),
+        }
+    }
+    uri
+}
+
+/// Returns the path for the supplied `file` URI, if it is a `file` URI.
+pub fn uri_to_path(uri: &str) -> Option<PathBuf> {
+    if !uri.starts_with("file://") {
+        return None;
+    }
+
+    let mut path = vec![];
+    let mut bytes = uri[7..].bytes();
+    while let Some(byte) = bytes.next() {
+        if byte == b'%' {
+            let hex = bytes.by_ref().take(2).map(|b| b as char).collect::<String>();
+            match u8::from_str_radix(&hex, 16) {
+                Ok(byte) => path.push(byte),
+                Err(_) => return None,
+            }
+        } else {
+            path.push(byte);
+        }
+    }
+
+    let path = String::from_utf8_lossy(&path).into_owned();
+    if cfg!(windows) && path.starts_with('/') {
+        Some(PathBuf::from(&path[1..]))
+    } else {
+        Some(PathBuf::from(path))
+    }
+}
+
+/// Reads a message with a `Content-Length` header from the supplied reader.
+///
+/// Returns `None` if the reader was exhausted before a message was started.
+///
+/// # Failures
+///
+/// * an I/O error occurs
+/// * the message headers are missing a `Content-Length` header
+/// * the message content is not valid JSON
+pub fn read_message<R: BufRead>(reader: &mut R) -> io::Result<Option<Json>> {
+    let mut length = None;
+    loop {
+        let mut header = String::new();
+        if try!(reader.read_line(&mut header)) == 0 {
+            return Ok(None);
+        }
+
+        let header = header.trim();
+        if header.is_empty() {
+            if length.is_some() {
+                break;
+            } else {
+                continue;
+            }
+        }
+
+        let mut parts = header.splitn(2, ':');
+        if parts.next().map(|n| n.trim().to_lowercase()) == Some("content-length".into()) {
+            length = parts.next().and_then(|v| v.trim().parse::<usize>().ok());
+        }
+    }
+
+    let length = match length {
+        Some(length) => length,
+        None => return Err(io::Error::new(io::ErrorKind::InvalidData, "missing Content-Length")),
+    };
+
+    let mut content = vec![0; length];
+    try!(reader.read_exact(&mut content));
+    let content = String::from_utf8_lossy(&content);
+    match Json::from_str(&content) {
+        Ok(json) => Ok(Some(json)),
+        Err(error) => Err(io::Error::new(io::ErrorKind::InvalidData, error.to_string())),
+    }
+}
+
+/// Writes the supplied message with a `Content-Length` header to the supplied writer.
+///
+/// # Failures
+///
+/// * an I/O error occurs
+pub fn write_message<W: Write>(writer: &mut W, message: &Json) -> io::Result<()> {
+    let content = message.to_string();
+    try!(write!(writer, "Content-Length: {}\r\n\r\n{}", content.len(), content));
+    writer.flush()
+}
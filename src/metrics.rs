@@ -0,0 +1,437 @@
+// Copyright 2016 Kyle Mayes
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Size, complexity, and nesting metrics of function definitions.
+
+use std::cmp;
+use std::io;
+use std::collections::{BTreeMap, BTreeSet};
+use std::path::{PathBuf};
+
+use rustc_serialize::json::{Json, ToJson};
+
+use utility;
+use super::{Entity, EntityKind, EntityVisitResult, TranslationUnit};
+use super::control_flow::{ControlFlowGraph};
+use super::expression;
+use super::source::{File};
+use super::token::{Token, TokenKind, TokenStream, TriviaKind};
+
+//================================================
+// Structs
+//================================================
+
+// FileMetrics ___________________________________
+
+/// The metrics of a source file and the function definitions in it.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct FileMetrics {
+    /// The path of the source file.
+    pub path: PathBuf,
+    /// The number of lines in the source file.
+    pub lines: u32,
+    /// The number of lines in the source file that contain tokens other than comments.
+    pub code_lines: u32,
+    /// The number of lines in the source file that contain comments.
+    pub comment_lines: u32,
+    /// The metrics of the function definitions in the source file, in the order they appear.
+    pub functions: Vec<FunctionMetrics>,
+}
+
+impl FileMetrics {
+    //- Constructors -----------------------------
+
+    fn new(file: File, functions: Vec<FunctionMetrics>) -> io::Result<FileMetrics> {
+        let stream = try!(file.tokenize());
+        let (code, comment) = count_stream_lines(file, &stream);
+
+        Ok(FileMetrics {
+            path: file.get_path(),
+            lines: stream.get_text().lines().count() as u32,
+            code_lines: code,
+            comment_lines: comment,
+            functions: functions,
+        })
+    }
+
+    //- Accessors --------------------------------
+
+    /// Returns the aggregate metrics of this source file.
+    pub fn get_summary(&self) -> Summary {
+        let mut summary = Summary::default();
+        summary.add(self);
+        summary
+    }
+}
+
+impl ToJson for FileMetrics {
+    /// Returns the metrics of this source file as a JSON object.
+    ///
+    /// The `functions` member is an array of function metrics objects and the `summary` member is
+    /// the aggregate metrics of this source file.
+    fn to_json(&self) -> Json {
+        object!(
+            "path" => self.path.to_string_lossy().into_owned(),
+            "lines" => self.lines,
+            "code_lines" => self.code_lines,
+            "comment_lines" => self.comment_lines,
+            "functions" => self.functions,
+            "summary" => self.get_summary(),
+        )
+    }
+}
+
+// FunctionMetrics _______________________________
+
+/// The metrics of a function definition.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct FunctionMetrics {
+    /// The display name of the function (e.g., `add(int, int)`).
+    pub name: String,
+    /// The path of the source file the function is defined in.
+    pub path: PathBuf,
+    /// The line the function definition starts on.
+    pub line: u32,
+    /// The number of lines the function definition spans.
+    pub lines: u32,
+    /// The number of lines in the function definition that contain tokens other than comments.
+    pub code_lines: u32,
+    /// The number of lines in the function definition that contain comments.
+    pub comment_lines: u32,
+    /// The number of statements in the body of the function.
+    ///
+    /// Compound statements, null statements, and labels are not counted.
+    pub statements: usize,
+    /// The number of parameters of the function.
+    pub parameters: usize,
+    /// The cyclomatic complexity of the function.
+    ///
+    /// This is the cyclomatic complexity of the control flow graph of the function plus the
+    /// number of `&&`, `||`, and `?:` operators in the function.
+    pub complexity: usize,
+    /// The maximum depth of nested `if`, `switch`, loop, and `try` statements in the function.
+    ///
+    /// An `if` statement in the `else` branch of an `if` statement does not increase the depth.
+    pub nesting: usize,
+}
+
+impl FunctionMetrics {
+    //- Constructors -----------------------------
+
+    /// Constructs a new `FunctionMetrics` from the supplied function, if it is a function
+    /// definition.
+    pub fn new(entity: Entity) -> Option<FunctionMetrics> {
+        let cfg = match ControlFlowGraph::new(entity) {
+            Some(cfg) => cfg,
+            None => return None,
+        };
+
+        let range = match entity.get_range() {
+            Some(range) => range,
+            None => return None,
+        };
+        let start = range.get_start().get_file_location();
+        let end = range.get_end().get_file_location();
+        let (code, comment) = count_lines(&range.tokenize(), end.offset);
+
+        let mut decisions = 0;
+        entity.visit_children(|e, _| {
+            if e.get_kind() == EntityKind::ConditionalOperator {
+                decisions += 1;
+            } else if expression::get_binary_operator(e).map_or(false, |o| o.is_logical()) {
+                decisions += 1;
+            }
+            EntityVisitResult::Recurse
+        });
+
+        let children = entity.get_children();
+        let body = children.iter().rev().find(|c| {
+            c.get_kind() == EntityKind::CompoundStmt || c.get_kind() == EntityKind::TryStmt
+        });
+
+        Some(FunctionMetrics {
+            name: entity.get_display_name().unwrap_or_else(String::new),
+            path: start.file.get_path(),
+            line: start.line,
+            lines: end.line - start.line + 1,
+            code_lines: code,
+            comment_lines: comment,
+            statements: body.map_or(0, |b| count_statements(*b)),
+            parameters: entity.get_arguments().map_or(0, |a| a.len()),
+            complexity: cfg.get_cyclomatic_complexity() + decisions,
+            nesting: body.map_or(0, |b| get_nesting(*b, 0)),
+        })
+    }
+}
+
+impl ToJson for FunctionMetrics {
+    fn to_json(&self) -> Json {
+        object!(
+            "name" => self.name,
+            "path" => self.path.to_string_lossy().into_owned(),
+            "line" => self.line,
+            "lines" => self.lines,
+            "code_lines" => self.code_lines,
+            "comment_lines" => self.comment_lines,
+            "statements" => self.statements,
+            "parameters" => self.parameters,
+            "complexity" => self.complexity,
+            "nesting" => self.nesting,
+        )
+    }
+}
+
+// Report ________________________________________
+
+/// The metrics of the function definitions in a translation unit.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Report {
+    /// The metrics of the source files in the translation unit that contain function definitions,
+    /// sorted by path.
+    pub files: Vec<FileMetrics>,
+}
+
+impl Report {
+    //- Constructors -----------------------------
+
+    /// Constructs a new `Report` from the function definitions in the supplied translation unit.
+    ///
+    /// Function definitions in system headers are not included.
+    ///
+    /// # Failures
+    ///
+    /// * an I/O error occurs while reading a source file that contains function definitions
+    pub fn new<'tu>(tu: &'tu TranslationUnit<'tu>) -> io::Result<Report> {
+        let mut functions = BTreeMap::new();
+        tu.get_entity().visit_children(|e, _| {
+            if e.is_in_system_header() {
+                return EntityVisitResult::Continue;
+            }
+
+            if !e.is_definition() {
+                return EntityVisitResult::Recurse;
+            }
+
+            match FunctionMetrics::new(e) {
+                Some(metrics) => {
+                    let file = e.get_range().unwrap().get_start().get_file_location().file;
+                    let path = metrics.path.clone();
+                    functions.entry(path).or_insert_with(|| (file, vec![])).1.push(metrics);
+                    EntityVisitResult::Continue
+                },
+                None => EntityVisitResult::Recurse,
+            }
+        });
+
+        let mut files = vec![];
+        for (_, (file, functions)) in functions {
+            files.push(try!(FileMetrics::new(file, functions)));
+        }
+        Ok(Report { files: files })
+    }
+
+    //- Accessors --------------------------------
+
+    /// Returns the metrics of the function definitions in the translation unit.
+    pub fn get_functions(&self) -> Vec<&FunctionMetrics> {
+        self.files.iter().flat_map(|f| f.functions.iter()).collect()
+    }
+
+    /// Returns the aggregate metrics of the translation unit.
+    pub fn get_summary(&self) -> Summary {
+        let mut summary = Summary::default();
+        for file in &self.files {
+            summary.add(file);
+        }
+        summary
+    }
+}
+
+impl ToJson for Report {
+    /// Returns this report as a JSON object.
+    ///
+    /// The `files` member is an array of source file metrics objects and the `summary` member is
+    /// the aggregate metrics of the translation unit.
+    fn to_json(&self) -> Json {
+        object!("files" => self.files, "summary" => self.get_summary())
+    }
+}
+
+// Summary _______________________________________
+
+/// The aggregate metrics of one or more source files.
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq, Hash)]
+pub struct Summary {
+    /// The number of source files.
+    pub files: usize,
+    /// The number of function definitions.
+    pub functions: usize,
+    /// The total number of lines in the source files.
+    pub lines: u32,
+    /// The total number of lines in the source files that contain tokens other than comments.
+    pub code_lines: u32,
+    /// The total number of lines in the source files that contain comments.
+    pub comment_lines: u32,
+    /// The total number of statements in the function definitions.
+    pub statements: usize,
+    /// The total cyclomatic complexity of the function definitions.
+    pub complexity: usize,
+    /// The maximum cyclomatic complexity of the function definitions.
+    pub max_complexity: usize,
+    /// The maximum nesting depth of the function definitions.
+    pub max_nesting: usize,
+    /// The maximum number of parameters of the function definitions.
+    pub max_parameters: usize,
+}
+
+impl Summary {
+    //- Accessors --------------------------------
+
+    /// Returns the average cyclomatic complexity of the function definitions.
+    pub fn get_average_complexity(&self) -> f64 {
+        if self.functions == 0 {
+            0.0
+        } else {
+            self.complexity as f64 / self.functions as f64
+        }
+    }
+
+    /// Returns the ratio of lines that contain comments to lines in the source files.
+    pub fn get_comment_density(&self) -> f64 {
+        if self.lines == 0 {
+            0.0
+        } else {
+            self.comment_lines as f64 / self.lines as f64
+        }
+    }
+
+    //- Mutators ---------------------------------
+
+    fn add(&mut self, file: &FileMetrics) {
+        self.files += 1;
+        self.functions += file.functions.len();
+        self.lines += file.lines;
+        self.code_lines += file.code_lines;
+        self.comment_lines += file.comment_lines;
+        for function in &file.functions {
+            self.statements += function.statements;
+            self.complexity += function.complexity;
+            self.max_complexity = cmp::max(self.max_complexity, function.complexity);
+            self.max_nesting = cmp::max(self.max_nesting, function.nesting);
+            self.max_parameters = cmp::max(self.max_parameters, function.parameters);
+        }
+    }
+}
+
+impl ToJson for Summary {
+    fn to_json(&self) -> Json {
+        object!(
+            "files" => self.files,
+            "functions" => self.functions,
+            "lines" => self.lines,
+            "code_lines" => self.code_lines,
+            "comment_lines" => self.comment_lines,
+            "comment_density" => self.get_comment_density(),
+            "statements" => self.statements,
+            "complexity" => self.complexity,
+            "average_complexity" => self.get_average_complexity(),
+            "max_complexity" => self.max_complexity,
+            "max_nesting" => self.max_nesting,
+            "max_parameters" => self.max_parameters,
+        )
+    }
+}
+
+//================================================
+// Functions
+//================================================
+
+fn count_lines(tokens: &[Token], end: u32) -> (u32, u32) {
+    let mut code = BTreeSet::new();
+    let mut comment = BTreeSet::new();
+    for token in tokens {
+        let range = token.get_range();
+        let start = range.get_start().get_file_location();
+        if start.offset >= end {
+            continue;
+        }
+
+        let lines = if token.get_kind() == TokenKind::Comment { &mut comment } else { &mut code };
+        lines.extend(start.line..range.get_end().get_file_location().line + 1);
+    }
+    (code.len() as u32, comment.len() as u32)
+}
+
+fn count_statements(statement: Entity) -> usize {
+    let children = statement.get_children();
+    let last = |c: &[Entity]| c.last().map_or(0, |l| count_statements(*l));
+    match statement.get_kind() {
+        EntityKind::CompoundStmt => children.iter().map(|c| count_statements(*c)).sum(),
+        EntityKind::NullStmt => 0,
+        EntityKind::CaseStmt | EntityKind::DefaultStmt | EntityKind::LabelStmt => last(&children),
+        EntityKind::CatchStmt => last(&children),
+        EntityKind::IfStmt => {
+            let count = utility::get_condition_count(&children);
+            1 + children[count..].iter().map(|c| count_statements(*c)).sum::<usize>()
+        },
+        EntityKind::WhileStmt |
+        EntityKind::ForStmt |
+        EntityKind::ForRangeStmt |
+        EntityKind::SwitchStmt => 1 + last(&children),
+        EntityKind::DoStmt => 1 + children.first().map_or(0, |f| count_statements(*f)),
+        EntityKind::TryStmt => 1 + children.iter().map(|c| count_statements(*c)).sum::<usize>(),
+        _ => 1,
+    }
+}
+
+fn count_stream_lines(file: File, stream: &TokenStream) -> (u32, u32) {
+    let line = |offset: usize| file.get_offset_location(offset as u32).get_file_location().line;
+    let lines = |offset: usize, text: &str| line(offset)..line(offset + text.len()) + 1;
+
+    let mut code = BTreeSet::new();
+    let mut comment = BTreeSet::new();
+    for token in &stream.tokens {
+        code.extend(lines(token.offset, &token.text));
+    }
+    let trivia = stream.tokens.iter().flat_map(|t| t.leading.iter().chain(t.trailing.iter()));
+    for trivia in trivia.chain(stream.trailing.iter()) {
+        if trivia.kind == TriviaKind::Comment {
+            comment.extend(lines(trivia.offset, &trivia.text));
+        }
+    }
+    (code.len() as u32, comment.len() as u32)
+}
+
+fn get_nesting(entity: Entity, depth: usize) -> usize {
+    let kind = entity.get_kind();
+    let nested = match kind {
+        EntityKind::IfStmt |
+        EntityKind::WhileStmt |
+        EntityKind::DoStmt |
+        EntityKind::ForStmt |
+        EntityKind::ForRangeStmt |
+        EntityKind::SwitchStmt |
+        EntityKind::TryStmt => depth + 1,
+        _ => depth,
+    };
+
+    let children = entity.get_children();
+    let count = utility::get_condition_count(&children);
+    let branch = if kind == EntityKind::IfStmt { count + 1 } else { 0 };
+    children.iter().enumerate().fold(nested, |m, (i, c)| {
+        // An `if` statement in the `else` branch of an `if` statement is not nested.
+        let elseif = branch != 0 && i == branch && c.get_kind() == EntityKind::IfStmt;
+        cmp::max(m, get_nesting(*c, if elseif { depth } else { nested }))
+    })
+}
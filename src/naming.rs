@@ -0,0 +1,617 @@
+// Copyright 2016 Kyle Mayes
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Checking the names of declarations against configurable naming conventions.
+//!
+//! Each kind of name (e.g., `functions`) can have its own convention. Kinds without a convention
+//! fall back to the convention of a more general kind (e.g., `const-methods` fall back to `methods`
+//! which fall back to `functions`) and names of kinds with no applicable convention are not
+//! checked.
+
+use std::collections::{BTreeMap, HashSet};
+use std::path::{PathBuf};
+
+use regex::{Regex};
+
+use rustc_serialize::json::{Json};
+
+use super::{Accessibility, Entity, EntityKind, EntityVisitResult, TranslationUnit};
+use super::diagnostic::{FixIt};
+use super::lint::{Reporter, Rule};
+use super::rename::{Rename};
+use super::source::{SourceLocation, SourceRange};
+
+#[cfg(feature="gte_clang_3_6")]
+use super::{StorageClass};
+
+//================================================
+// Enums
+//================================================
+
+// CaseStyle _____________________________________
+
+/// Indicates how the words in a name are capitalized and separated.
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash)]
+pub enum CaseStyle {
+    /// Capitalized words without separators (e.g., `CamelCase`).
+    CamelCase,
+    /// Capitalized words without separators except for a lowercase first word (e.g., `camelBack`).
+    LowerCamelCase,
+    /// Lowercase words separated by underscores (e.g., `snake_case`).
+    SnakeCase,
+    /// Uppercase words separated by underscores (e.g., `UPPER_CASE`).
+    UpperCase,
+}
+
+impl CaseStyle {
+    //- Constructors -----------------------------
+
+    /// Returns the case style with the supplied name (e.g., `snake_case`), if any.
+    pub fn from_name(name: &str) -> Option<CaseStyle> {
+        match name {
+            "CamelCase" => Some(CaseStyle::CamelCase),
+            "camelBack" => Some(CaseStyle::LowerCamelCase),
+            "snake_case" => Some(CaseStyle::SnakeCase),
+            "UPPER_CASE" => Some(CaseStyle::UpperCase),
+            _ => None,
+        }
+    }
+
+    //- Accessors --------------------------------
+
+    /// Returns the name of this case style (e.g., `snake_case`).
+    pub fn get_name(&self) -> &'static str {
+        match *self {
+            CaseStyle::CamelCase => "CamelCase",
+            CaseStyle::LowerCamelCase => "camelBack",
+            CaseStyle::SnakeCase => "snake_case",
+            CaseStyle::UpperCase => "UPPER_CASE",
+        }
+    }
+
+    /// Returns the supplied name converted to this case style.
+    ///
+    /// Words are separated by underscores, by lowercase letters or digits followed by uppercase
+    /// letters, and by the last of a run of uppercase letters followed by a lowercase letter (e.g.,
+    /// `HTTPServer` is split into `HTTP` and `Server`).
+    pub fn convert(&self, name: &str) -> String {
+        let words = split_words(name);
+        let words = words.iter().enumerate().map(|(i, w)| match *self {
+            CaseStyle::CamelCase => capitalize(w),
+            CaseStyle::LowerCamelCase if i == 0 => w.to_lowercase(),
+            CaseStyle::LowerCamelCase => capitalize(w),
+            CaseStyle::SnakeCase => w.to_lowercase(),
+            CaseStyle::UpperCase => w.to_uppercase(),
+        }).collect::<Vec<_>>();
+        match *self {
+            CaseStyle::CamelCase | CaseStyle::LowerCamelCase => words.concat(),
+            CaseStyle::SnakeCase | CaseStyle::UpperCase => words.join("_"),
+        }
+    }
+
+    /// Returns whether the supplied name is in this case style.
+    pub fn is_match(&self, name: &str) -> bool {
+        !name.is_empty() && self.convert(name) == name
+    }
+}
+
+// NameKind ______________________________________
+
+/// Indicates the kind of a checked name.
+#[derive(Copy, Clone, Debug, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum NameKind {
+    /// The name of a `const` variable with static storage duration.
+    Constant,
+    /// The name of a `const` method.
+    ConstMethod,
+    /// The name of an enum constant.
+    EnumConstant,
+    /// The name of a function which is not a method.
+    Function,
+    /// The name of a non-`const` variable with static storage duration.
+    Global,
+    /// The name of a macro.
+    Macro,
+    /// The name of a field.
+    Member,
+    /// The name of a method which is not a constructor, destructor, conversion function, or
+    /// operator.
+    Method,
+    /// The name of a private or protected field.
+    PrivateMember,
+    /// The name of a class, enum, struct, type alias, typedef, or union.
+    Type,
+}
+
+impl NameKind {
+    //- Constructors -----------------------------
+
+    /// Returns the kind of name the supplied declaration has, if it is checked.
+    pub fn from_entity(entity: &Entity) -> Option<NameKind> {
+        match entity.get_kind() {
+            EntityKind::ClassDecl | EntityKind::ClassTemplate | EntityKind::EnumDecl |
+            EntityKind::StructDecl | EntityKind::TypeAliasDecl | EntityKind::TypedefDecl |
+            EntityKind::UnionDecl => Some(NameKind::Type),
+            EntityKind::EnumConstantDecl => Some(NameKind::EnumConstant),
+            EntityKind::FieldDecl => match entity.get_accessibility() {
+                Some(Accessibility::Private) | Some(Accessibility::Protected) => {
+                    Some(NameKind::PrivateMember)
+                },
+                _ => Some(NameKind::Member),
+            },
+            EntityKind::FunctionDecl => Some(NameKind::Function),
+            EntityKind::FunctionTemplate if is_class(entity.get_semantic_parent()) => {
+                Some(NameKind::Method)
+            },
+            EntityKind::FunctionTemplate => Some(NameKind::Function),
+            EntityKind::MacroDefinition => Some(NameKind::Macro),
+            EntityKind::Method if entity.is_const_method() => Some(NameKind::ConstMethod),
+            EntityKind::Method => Some(NameKind::Method),
+            EntityKind::VarDecl if is_static(entity) => {
+                let mut type_ = entity.get_type();
+                while let Some(element) = type_.and_then(|t| t.get_element_type()) {
+                    type_ = Some(element);
+                }
+                if type_.map_or(false, |t| t.is_const_qualified()) {
+                    Some(NameKind::Constant)
+                } else {
+                    Some(NameKind::Global)
+                }
+            },
+            _ => None,
+        }
+    }
+
+    /// Returns the kind of name with the supplied name (e.g., `functions`), if any.
+    pub fn from_name(name: &str) -> Option<NameKind> {
+        match name {
+            "constants" => Some(NameKind::Constant),
+            "const-methods" => Some(NameKind::ConstMethod),
+            "enum-constants" => Some(NameKind::EnumConstant),
+            "functions" => Some(NameKind::Function),
+            "globals" => Some(NameKind::Global),
+            "macros" => Some(NameKind::Macro),
+            "members" => Some(NameKind::Member),
+            "methods" => Some(NameKind::Method),
+            "private-members" => Some(NameKind::PrivateMember),
+            "types" => Some(NameKind::Type),
+            _ => None,
+        }
+    }
+
+    //- Accessors --------------------------------
+
+    /// Returns the more general kind of name whose convention applies to names of this kind when
+    /// this kind has no convention, if any.
+    pub fn get_fallback(&self) -> Option<NameKind> {
+        match *self {
+            NameKind::ConstMethod => Some(NameKind::Method),
+            NameKind::Method => Some(NameKind::Function),
+            NameKind::PrivateMember => Some(NameKind::Member),
+            _ => None,
+        }
+    }
+
+    /// Returns the name of this kind of name (e.g., `functions`).
+    pub fn get_name(&self) -> &'static str {
+        match *self {
+            NameKind::Constant => "constants",
+            NameKind::ConstMethod => "const-methods",
+            NameKind::EnumConstant => "enum-constants",
+            NameKind::Function => "functions",
+            NameKind::Global => "globals",
+            NameKind::Macro => "macros",
+            NameKind::Member => "members",
+            NameKind::Method => "methods",
+            NameKind::PrivateMember => "private-members",
+            NameKind::Type => "types",
+        }
+    }
+}
+
+//================================================
+// Structs
+//================================================
+
+// Convention ____________________________________
+
+/// A naming convention for a kind of name.
+#[derive(Clone, Debug, Default)]
+pub struct Convention {
+    /// The case style names must be in after the prefix and suffix are removed, if any.
+    pub case: Option<CaseStyle>,
+    /// The prefix names must start with (e.g., `m_`).
+    pub prefix: String,
+    /// The suffix names must end with (e.g., `_t`).
+    pub suffix: String,
+    /// The regular expression names must match, if any.
+    pub pattern: Option<Regex>,
+}
+
+impl Convention {
+    //- Constructors -----------------------------
+
+    /// Constructs a new `Convention` which requires names to be in the supplied case style.
+    pub fn new(case: CaseStyle) -> Convention {
+        Convention { case: Some(case), ..Convention::default() }
+    }
+
+    /// Constructs a new `Convention` from the supplied JSON value, returning a description of the
+    /// problem if the value is invalid.
+    ///
+    /// The value is either the name of a case style or an object with optional `case`, `prefix`,
+    /// `suffix`, and `pattern` members.
+    pub fn from_json(json: &Json) -> Result<Convention, String> {
+        let object = match *json {
+            Json::String(ref case) => return parse_case(case).map(Convention::new),
+            Json::Object(ref object) => object,
+            _ => return Err("expected a case style or an object".into()),
+        };
+
+        let mut convention = Convention::default();
+        for (key, value) in object {
+            match (&key[..], value) {
+                ("case", &Json::String(ref case)) => convention.case = Some(try!(parse_case(case))),
+                ("prefix", &Json::String(ref prefix)) => convention.prefix = prefix.clone(),
+                ("suffix", &Json::String(ref suffix)) => convention.suffix = suffix.clone(),
+                ("pattern", &Json::String(ref pattern)) => {
+                    let pattern = try!(Regex::new(pattern).map_err(|e| e.to_string()));
+                    convention.pattern = Some(pattern);
+                },
+                ("case", _) | ("prefix", _) | ("suffix", _) | ("pattern", _) => {
+                    return Err(format!("expected a string for `{}`", key));
+                },
+                _ => return Err(format!("unknown member `{}`", key)),
+            }
+        }
+        Ok(convention)
+    }
+
+    //- Accessors --------------------------------
+
+    /// Returns whether the supplied name follows this convention.
+    pub fn is_match(&self, name: &str) -> bool {
+        if !name.starts_with(&self.prefix[..]) || !name.ends_with(&self.suffix[..]) {
+            return false;
+        }
+        if self.prefix.len() + self.suffix.len() > name.len() {
+            return false;
+        }
+
+        let core = &name[self.prefix.len()..name.len() - self.suffix.len()];
+        let case = self.case.map_or(true, |c| c.is_match(core));
+        case && self.pattern.as_ref().map_or(true, |p| p.is_match(name))
+    }
+
+    /// Returns a name which follows this convention and is similar to the supplied name, if any.
+    ///
+    /// The prefix and suffix are removed from the supplied name if present, the remainder is
+    /// converted to the case style, and the prefix and suffix are added back. No name is returned
+    /// if the result does not match the regular expression.
+    pub fn suggest(&self, name: &str) -> Option<String> {
+        let mut core = name;
+        if core.starts_with(&self.prefix[..]) {
+            core = &core[self.prefix.len()..];
+        }
+        if core.ends_with(&self.suffix[..]) {
+            core = &core[..core.len() - self.suffix.len()];
+        }
+
+        let core = self.case.map_or_else(|| core.to_string(), |c| c.convert(core));
+        if core.is_empty() {
+            return None;
+        }
+
+        let suggestion = format!("{}{}{}", self.prefix, core, self.suffix);
+        if suggestion != name && self.is_match(&suggestion) {
+            Some(suggestion)
+        } else {
+            None
+        }
+    }
+}
+
+// NamingConventions _____________________________
+
+/// A rule which finds names which do not follow naming conventions (`naming-conventions`).
+///
+/// Each member of the configuration of this rule named after a kind of name (e.g., `functions`)
+/// sets the convention for that kind of name (see `Convention::from_json`). Violations with
+/// suggested names have fix-its which rename the declaration and the references to it in the files
+/// of the translation unit unless the rename is not possible (see `Rename::new`).
+#[derive(Clone, Debug, Default)]
+pub struct NamingConventions {
+    conventions: BTreeMap<NameKind, Convention>,
+    seen: HashSet<(PathBuf, u32)>,
+}
+
+impl NamingConventions {
+    //- Constructors -----------------------------
+
+    /// Constructs a new `NamingConventions` with no conventions.
+    pub fn new() -> NamingConventions {
+        NamingConventions::default()
+    }
+
+    //- Accessors --------------------------------
+
+    /// Returns the convention which applies to the supplied kind of name, if any.
+    pub fn get_convention(&self, kind: NameKind) -> Option<&Convention> {
+        let mut kind = Some(kind);
+        while let Some(next) = kind {
+            if let Some(convention) = self.conventions.get(&next) {
+                return Some(convention);
+            }
+            kind = next.get_fallback();
+        }
+        None
+    }
+
+    /// Checks the name of the supplied declaration and returns the violation, if any.
+    pub fn check_entity<'tu>(&self, entity: Entity<'tu>) -> Option<Violation<'tu>> {
+        if entity.is_in_system_header() {
+            return None;
+        }
+
+        let kind = match NameKind::from_entity(&entity) {
+            Some(kind) => kind,
+            None => return None,
+        };
+        let name = match entity.get_name() {
+            Some(ref name) if is_checked(name) => name.clone(),
+            _ => return None,
+        };
+        let convention = match self.get_convention(kind) {
+            Some(convention) if !convention.is_match(&name) => convention,
+            _ => return None,
+        };
+
+        let suggestion = convention.suggest(&name);
+        let edits = suggestion.as_ref().and_then(|s| {
+            Rename::new(entity).ok().map(|r| r.get_fix_its(s))
+        }).unwrap_or_else(Vec::new);
+        Some(Violation {
+            entity: entity,
+            kind: kind,
+            name: name,
+            suggestion: suggestion,
+            edits: edits,
+        })
+    }
+
+    /// Checks the names of the declarations in the supplied translation unit and returns the
+    /// violations.
+    ///
+    /// Each declaration is checked once even if it is declared more than once.
+    pub fn check<'tu>(&self, tu: &'tu TranslationUnit<'tu>) -> Vec<Violation<'tu>> {
+        let mut seen = HashSet::new();
+        let mut violations = vec![];
+        tu.get_entity().visit_children(|e, _| {
+            if e.is_in_system_header() {
+                return EntityVisitResult::Continue;
+            }
+
+            if get_key(e).map_or(false, |k| seen.insert(k)) {
+                violations.extend(self.check_entity(e));
+            }
+            EntityVisitResult::Recurse
+        });
+        violations
+    }
+
+    //- Mutators ---------------------------------
+
+    /// Sets the convention for the supplied kind of name.
+    pub fn convention(&mut self, kind: NameKind, convention: Convention) -> &mut NamingConventions {
+        self.conventions.insert(kind, convention);
+        self
+    }
+}
+
+impl Rule for NamingConventions {
+    fn get_name(&self) -> &str {
+        "naming-conventions"
+    }
+
+    fn get_entity_kinds(&self) -> Vec<EntityKind> {
+        vec![
+            EntityKind::ClassDecl,
+            EntityKind::ClassTemplate,
+            EntityKind::EnumConstantDecl,
+            EntityKind::EnumDecl,
+            EntityKind::FieldDecl,
+            EntityKind::FunctionDecl,
+            EntityKind::FunctionTemplate,
+            EntityKind::MacroDefinition,
+            EntityKind::Method,
+            EntityKind::StructDecl,
+            EntityKind::TypeAliasDecl,
+            EntityKind::TypedefDecl,
+            EntityKind::UnionDecl,
+            EntityKind::VarDecl,
+        ]
+    }
+
+    fn configure(&mut self, configuration: &Json) -> Result<(), String> {
+        let object = match configuration.as_object() {
+            Some(object) => object,
+            None => return Ok(()),
+        };
+        for (key, value) in object {
+            if let Some(kind) = NameKind::from_name(key) {
+                let convention = try!(Convention::from_json(value).map_err(|m| {
+                    format!("invalid convention for `{}`: {}", key, m)
+                }));
+                self.conventions.insert(kind, convention);
+            }
+        }
+        Ok(())
+    }
+
+    fn visit_entity<'tu>(&mut self, entity: Entity<'tu>, reporter: &mut Reporter<'tu>) {
+        if !get_key(entity).map_or(false, |k| self.seen.insert(k)) {
+            return;
+        }
+
+        let violation = match self.check_entity(entity) {
+            Some(violation) => violation,
+            None => return,
+        };
+        let range = match get_name_range(entity, &violation.name).or(entity.get_range()) {
+            Some(range) => range,
+            None => return,
+        };
+
+        let mut message = format!(
+            "name `{}` does not follow the `{}` naming convention",
+            violation.name,
+            violation.kind.get_name()
+        );
+        if let Some(ref suggestion) = violation.suggestion {
+            message.push_str(&format!(" (suggested name: `{}`)", suggestion));
+        }
+        reporter.report_with_fix_its(range, message, violation.edits);
+    }
+
+    fn visit_translation_unit<'tu>(&mut self, _: &'tu TranslationUnit<'tu>, _: &mut Reporter<'tu>) {
+        self.seen.clear();
+    }
+}
+
+// Violation _____________________________________
+
+/// A name which does not follow a naming convention.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Violation<'tu> {
+    /// The declaration with the name.
+    pub entity: Entity<'tu>,
+    /// The kind of the name.
+    pub kind: NameKind,
+    /// The name.
+    pub name: String,
+    /// The suggested replacement for the name, if any.
+    pub suggestion: Option<String>,
+    /// The edits which rename the declaration and the references to it to the suggested name, if
+    /// possible.
+    pub edits: Vec<FixIt<'tu>>,
+}
+
+//================================================
+// Functions
+//================================================
+
+fn capitalize(word: &str) -> String {
+    let mut chars = word.chars();
+    match chars.next() {
+        Some(first) => first.to_uppercase().chain(chars.flat_map(|c| c.to_lowercase())).collect(),
+        None => String::new(),
+    }
+}
+
+fn get_key(entity: Entity) -> Option<(PathBuf, u32)> {
+    entity.get_canonical_entity().get_location().map(|l| {
+        let location = l.get_file_location();
+        (location.file.get_path(), location.offset)
+    })
+}
+
+fn get_name_range<'tu>(entity: Entity<'tu>, name: &str) -> Option<SourceRange<'tu>> {
+    entity.get_location().and_then(|l| get_token_range(l, name))
+}
+
+fn get_token_range<'tu>(location: SourceLocation<'tu>, name: &str) -> Option<SourceRange<'tu>> {
+    let location = location.get_file_location();
+    let start = location.file.get_offset_location(location.offset);
+    let end = location.file.get_offset_location(location.offset + name.len() as u32);
+    let range = SourceRange::new(start, end);
+    match range.tokenize().first() {
+        Some(token) if token.get_spelling() == name => Some(range),
+        _ => None,
+    }
+}
+
+fn is_checked(name: &str) -> bool {
+    let mut chars = name.chars();
+    let reserved = match (chars.next(), chars.next()) {
+        (Some('_'), Some(c)) => c == '_' || c.is_uppercase(),
+        (None, _) => return false,
+        _ => false,
+    };
+    !reserved && !(name.starts_with("operator") && is_operator(&name[8..]))
+}
+
+fn is_class(entity: Option<Entity>) -> bool {
+    match entity.map(|e| e.get_kind()) {
+        Some(EntityKind::ClassDecl) | Some(EntityKind::ClassTemplate) |
+        Some(EntityKind::ClassTemplatePartialSpecialization) | Some(EntityKind::StructDecl) |
+        Some(EntityKind::UnionDecl) => true,
+        _ => false,
+    }
+}
+
+fn is_operator(suffix: &str) -> bool {
+    suffix.chars().next().map_or(false, |c| !c.is_alphanumeric() && c != '_')
+}
+
+fn is_static(entity: &Entity) -> bool {
+    let parent = entity.get_semantic_parent();
+    match parent.map(|p| p.get_kind()) {
+        Some(EntityKind::LinkageSpec) | Some(EntityKind::Namespace) |
+        Some(EntityKind::TranslationUnit) => true,
+        _ => is_class(parent) || is_static_local(entity),
+    }
+}
+
+#[cfg(feature="gte_clang_3_6")]
+fn is_static_local(entity: &Entity) -> bool {
+    entity.get_storage_class() == Some(StorageClass::Static)
+}
+
+#[cfg(not(feature="gte_clang_3_6"))]
+fn is_static_local(_: &Entity) -> bool {
+    false
+}
+
+fn parse_case(case: &str) -> Result<CaseStyle, String> {
+    CaseStyle::from_name(case).ok_or_else(|| format!("unknown case style `{}`", case))
+}
+
+fn split_words(name: &str) -> Vec<String> {
+    let chars = name.chars().collect::<Vec<_>>();
+    let mut words = vec![];
+    let mut word = String::new();
+    for (index, &c) in chars.iter().enumerate() {
+        if c == '_' {
+            if !word.is_empty() {
+                words.push(word);
+                word = String::new();
+            }
+            continue;
+        }
+
+        if c.is_uppercase() && !word.is_empty() {
+            let previous = chars[index - 1];
+            let lower = chars.get(index + 1).map_or(false, |n| n.is_lowercase());
+            if !previous.is_uppercase() || lower {
+                words.push(word);
+                word = String::new();
+            }
+        }
+        word.push(c);
+    }
+    if !word.is_empty() {
+        words.push(word);
+    }
+    words
+}
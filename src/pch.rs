@@ -0,0 +1,205 @@
+// Copyright 2016 Kyle Mayes
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Precompiled headers which are rebuilt when the files they depend on change.
+
+use std::fmt;
+use std::collections::{BTreeMap};
+use std::error::{Error};
+use std::path::{Path, PathBuf};
+
+use libc::{time_t};
+
+use utility;
+use super::{Index, SaveError, SourceError};
+
+//================================================
+// Enums
+//================================================
+
+// PchError ______________________________________
+
+/// Indicates the type of error that prevented the building of a precompiled header.
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash)]
+pub enum PchError {
+    /// The header could not be parsed.
+    Source(SourceError),
+    /// The parsed header could not be saved.
+    Save(SaveError),
+}
+
+impl Error for PchError {
+    fn description(&self) -> &str {
+        match *self {
+            PchError::Source(ref error) => error.description(),
+            PchError::Save(ref error) => error.description(),
+        }
+    }
+
+    fn cause(&self) -> Option<&Error> {
+        match *self {
+            PchError::Source(ref error) => Some(error),
+            PchError::Save(ref error) => Some(error),
+        }
+    }
+}
+
+impl From<SaveError> for PchError {
+    fn from(error: SaveError) -> PchError {
+        PchError::Save(error)
+    }
+}
+
+impl From<SourceError> for PchError {
+    fn from(error: SourceError) -> PchError {
+        PchError::Source(error)
+    }
+}
+
+impl fmt::Display for PchError {
+    fn fmt(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+        write!(formatter, "{}", self.description())
+    }
+}
+
+//================================================
+// Structs
+//================================================
+
+// PchBuilder ____________________________________
+
+/// Builds a precompiled header and rebuilds it when the files it depends on change.
+///
+/// The files a precompiled header depends on are the header and the files it directly or
+/// indirectly includes. A dependency is considered to have changed when its last modification time
+/// differs from its last modification time when the precompiled header was built.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct PchBuilder {
+    header: PathBuf,
+    output: PathBuf,
+    arguments: Vec<String>,
+    dependencies: BTreeMap<PathBuf, time_t>,
+}
+
+impl PchBuilder {
+    //- Constructors -----------------------------
+
+    /// Constructs a new `PchBuilder` which builds a precompiled header for the supplied header at
+    /// the supplied output path.
+    pub fn new<H: Into<PathBuf>, O: Into<PathBuf>>(header: H, output: O) -> PchBuilder {
+        PchBuilder {
+            header: header.into(),
+            output: output.into(),
+            arguments: vec![],
+            dependencies: BTreeMap::new(),
+        }
+    }
+
+    //- Accessors --------------------------------
+
+    /// Returns the compiler arguments that include the precompiled header in a translation unit.
+    ///
+    /// A translation unit that includes the precompiled header must be parsed with compiler
+    /// arguments compatible with those used to build the precompiled header.
+    pub fn get_arguments(&self) -> Vec<String> {
+        vec!["-include-pch".into(), self.output.to_string_lossy().into_owned()]
+    }
+
+    /// Returns the files the precompiled header depended on when it was last built and their last
+    /// modification times at that point.
+    pub fn get_dependencies(&self) -> &BTreeMap<PathBuf, time_t> {
+        &self.dependencies
+    }
+
+    /// Returns the path of the header.
+    pub fn get_header(&self) -> &Path {
+        &self.header
+    }
+
+    /// Returns the path the precompiled header is written to.
+    pub fn get_output(&self) -> &Path {
+        &self.output
+    }
+
+    /// Returns whether the precompiled header needs to be built.
+    ///
+    /// This is the case when the precompiled header has not been built by this builder, the
+    /// precompiled header no longer exists, or any of the files it depends on have been modified or
+    /// removed since it was last built.
+    pub fn is_stale(&self) -> bool {
+        self.dependencies.is_empty() || !self.output.exists() || self.dependencies.iter().any(|d| {
+            utility::get_time(d.0) != Some(*d.1)
+        })
+    }
+
+    //- Mutators ---------------------------------
+
+    /// Sets the compiler arguments to provide to `libclang` when parsing the header.
+    ///
+    /// The language should be specified explicitly since headers are otherwise parsed as C
+    /// headers (e.g., `-x c++-header`). Changing the compiler arguments causes the precompiled
+    /// header to be considered stale.
+    pub fn arguments<S: AsRef<str>>(&mut self, arguments: &[S]) -> &mut PchBuilder {
+        self.arguments = arguments.iter().map(|a| a.as_ref().into()).collect();
+        self.dependencies.clear();
+        self
+    }
+
+    /// Parses the header as an incomplete translation unit prepared for serialization, saves it as
+    /// the precompiled header, and records the files it depends on.
+    ///
+    /// # Failures
+    ///
+    /// * the header could not be parsed
+    /// * errors in the header prevent saving
+    pub fn build(&mut self, index: &Index) -> Result<(), PchError> {
+        self.dependencies.clear();
+
+        let mut parser = index.parser(&self.header);
+        parser.arguments(&self.arguments).incomplete(true).for_serialization(true);
+        let tu = try!(parser.parse());
+        try!(tu.save(&self.output));
+
+        let main = tu.get_entity().get_name().and_then(|n| tu.get_file(n));
+        let mut files = main.into_iter().collect::<Vec<_>>();
+        let mut next = 0;
+        while next < files.len() {
+            for include in files[next].get_includes() {
+                if let Some(file) = include.get_file() {
+                    if !files.contains(&file) {
+                        files.push(file);
+                    }
+                }
+            }
+            next += 1;
+        }
+
+        self.dependencies = files.iter().map(|f| (f.get_path(), f.get_time())).collect();
+        Ok(())
+    }
+
+    /// Builds the precompiled header if it is stale and returns whether it was built.
+    ///
+    /// # Failures
+    ///
+    /// * the header could not be parsed
+    /// * errors in the header prevent saving
+    pub fn update(&mut self, index: &Index) -> Result<bool, PchError> {
+        if self.is_stale() {
+            self.build(index).map(|_| true)
+        } else {
+            Ok(false)
+        }
+    }
+}
@@ -0,0 +1,885 @@
+// Copyright 2016 Kyle Mayes
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Structural refactorings which produce previewable sets of text edits.
+//!
+//! Refactorings only change the files of the translation unit they are performed on. Source files
+//! are read from disk, so they should not have unsaved changes.
+
+use std::fmt;
+use std::fs;
+use std::io;
+use std::collections::{BTreeMap};
+use std::error::{Error};
+use std::io::{Write};
+use std::path::{Path, PathBuf};
+
+use utility;
+use super::{Entity, EntityKind, EntityVisitResult, TranslationUnit, Type, TypeKind};
+use super::document::{TextEdit};
+use super::source::{File, SourceRange};
+use super::token::{Token};
+
+//================================================
+// Enums
+//================================================
+
+// RefactorError _________________________________
+
+/// Indicates the error that prevented a refactoring.
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash)]
+pub enum RefactorError {
+    /// Inlining would change how many times an argument is evaluated.
+    ArgumentEvaluation,
+    /// The statements jump out of themselves (e.g., with `return`).
+    ControlFlow,
+    /// A call relies on default arguments.
+    DefaultArgument,
+    /// A variable declared in the statements is used after them.
+    EscapingDeclaration,
+    /// The parameter index is out of bounds.
+    InvalidIndex,
+    /// The source range does not cover whole statements in a function body.
+    InvalidRange,
+    /// A macro expansion makes the refactoring ambiguous.
+    MacroExpansion,
+    /// The statements access members through `this`.
+    MemberAccess,
+    /// There is no function (or function definition) to refactor.
+    NotFound,
+    /// The function body does not consist of a single `return` statement with a value.
+    NotTrivial,
+    /// The parameter is used in the function body.
+    ParameterUsed,
+    /// A source file could not be read.
+    Unreadable,
+}
+
+impl Error for RefactorError {
+    fn description(&self) -> &str {
+        match *self {
+            RefactorError::ArgumentEvaluation => {
+                "inlining would change how many times an argument is evaluated"
+            },
+            RefactorError::ControlFlow => "the statements jump out of themselves",
+            RefactorError::DefaultArgument => "a call relies on default arguments",
+            RefactorError::EscapingDeclaration => {
+                "a variable declared in the statements is used after them"
+            },
+            RefactorError::InvalidIndex => "the parameter index is out of bounds",
+            RefactorError::InvalidRange => {
+                "the source range does not cover whole statements in a function body"
+            },
+            RefactorError::MacroExpansion => "a macro expansion makes the refactoring ambiguous",
+            RefactorError::MemberAccess => "the statements access members through `this`",
+            RefactorError::NotFound => "there is no function to refactor",
+            RefactorError::NotTrivial => "the function body is not a single `return` statement",
+            RefactorError::ParameterUsed => "the parameter is used in the function body",
+            RefactorError::Unreadable => "a source file could not be read",
+        }
+    }
+}
+
+impl fmt::Display for RefactorError {
+    fn fmt(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+        write!(formatter, "{}", self.description())
+    }
+}
+
+//================================================
+// Structs
+//================================================
+
+// EditSet _______________________________________
+
+/// The text edits produced by a refactoring.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct EditSet {
+    edits: BTreeMap<PathBuf, Vec<TextEdit>>,
+}
+
+impl EditSet {
+    //- Constructors -----------------------------
+
+    /// Constructs a new empty `EditSet`.
+    pub fn new() -> EditSet {
+        EditSet::default()
+    }
+
+    //- Accessors --------------------------------
+
+    /// Applies the edits in this edit set to the files on disk.
+    ///
+    /// # Failures
+    ///
+    /// * an I/O error occurs while reading or writing a file
+    /// * a file is not valid UTF-8
+    pub fn apply(&self) -> io::Result<()> {
+        for (path, contents) in try!(self.preview()) {
+            try!(fs::File::create(path).and_then(|mut f| f.write_all(contents.as_bytes())));
+        }
+        Ok(())
+    }
+
+    /// Applies the edits for the file with the supplied path to the supplied contents of that file.
+    pub fn apply_to<P: AsRef<Path>>(&self, path: P, contents: &mut String) {
+        if let Some(edits) = self.edits.get(path.as_ref()) {
+            for edit in edits.iter().rev() {
+                edit.apply(contents);
+            }
+        }
+    }
+
+    /// Returns the edits in this edit set grouped by the paths of the files they apply to.
+    ///
+    /// The edits for each file are sorted by position and do not overlap.
+    pub fn get_edits(&self) -> &BTreeMap<PathBuf, Vec<TextEdit>> {
+        &self.edits
+    }
+
+    /// Returns whether this edit set contains no edits.
+    pub fn is_empty(&self) -> bool {
+        self.edits.is_empty()
+    }
+
+    /// Returns the contents the files changed by this edit set would have if the edits were
+    /// applied to them.
+    ///
+    /// # Failures
+    ///
+    /// * an I/O error occurs while reading a file
+    /// * a file is not valid UTF-8
+    pub fn preview(&self) -> io::Result<BTreeMap<PathBuf, String>> {
+        let mut files = BTreeMap::new();
+        for path in self.edits.keys() {
+            let mut contents = try!(utility::read_file(path));
+            self.apply_to(path, &mut contents);
+            files.insert(path.clone(), contents);
+        }
+        Ok(files)
+    }
+
+    //- Mutators ---------------------------------
+
+    /// Adds the edits in the supplied edit set which do not overlap edits in this edit set.
+    pub fn extend(&mut self, other: EditSet) {
+        for (path, edits) in other.edits {
+            for edit in edits {
+                self.insert(path.clone(), edit);
+            }
+        }
+    }
+
+    /// Adds the supplied edit for the file with the supplied path and returns whether it was added.
+    ///
+    /// Edits which overlap an edit already in this edit set are not added.
+    pub fn insert<P: Into<PathBuf>>(&mut self, path: P, edit: TextEdit) -> bool {
+        let edits = self.edits.entry(path.into()).or_insert_with(Vec::new);
+        if edits.iter().any(|e| edit.start < e.end && e.start < edit.end) {
+            return false;
+        }
+
+        let index = edits.iter().position(|e| (e.start, e.end) > (edit.start, edit.end));
+        edits.insert(index.unwrap_or(edits.len()), edit);
+        true
+    }
+}
+
+// Span __________________________________________
+
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+struct Span<'tu> {
+    file: File<'tu>,
+    start: u32,
+    end: u32,
+}
+
+impl<'tu> Span<'tu> {
+    //- Constructors -----------------------------
+
+    fn new(range: SourceRange<'tu>) -> Option<Span<'tu>> {
+        let (start, end) = (range.get_start(), range.get_end());
+        let (s, e) = (start.get_file_location(), end.get_file_location());
+        let expanded = start.get_expansion_location().offset != s.offset ||
+            end.get_expansion_location().offset != e.offset;
+        if !expanded && s.file == e.file && s.offset < e.offset {
+            Some(Span { file: s.file, start: s.offset, end: e.offset })
+        } else {
+            None
+        }
+    }
+
+    fn from_entity(entity: Entity<'tu>) -> Result<Span<'tu>, RefactorError> {
+        entity.get_range().and_then(Span::new).ok_or(RefactorError::MacroExpansion)
+    }
+
+    //- Accessors --------------------------------
+
+    fn contains(&self, other: &Span) -> bool {
+        self.file == other.file && self.start <= other.start && other.end <= self.end
+    }
+
+    fn get_position(&self, offset: u32) -> (u32, u32) {
+        let location = self.file.get_offset_location(offset).get_file_location();
+        (location.line, location.column)
+    }
+
+    fn get_text<'c>(&self, contents: &'c str) -> &'c str {
+        &contents[self.start as usize..self.end as usize]
+    }
+
+    fn replace<S: Into<String>>(&self, edits: &mut EditSet, start: u32, end: u32, text: S) {
+        let edit = TextEdit::new(self.get_position(start), self.get_position(end), text);
+        edits.insert(self.file.get_path(), edit);
+    }
+
+    fn tokenize(&self) -> Vec<Token<'tu>> {
+        let start = self.file.get_offset_location(self.start);
+        let end = self.file.get_offset_location(self.end);
+        SourceRange::new(start, end).tokenize().into_iter().filter(|t| {
+            let offset = t.get_location().get_file_location().offset;
+            offset >= self.start && offset < self.end
+        }).collect()
+    }
+}
+
+//================================================
+// Functions
+//================================================
+
+/// Adds a parameter with the supplied declaration (e.g., `int flags`) to the supplied function at
+/// the supplied index and adds the supplied argument to the calls to the function.
+///
+/// All the declarations of the function and the calls to it in the files of the translation unit
+/// which are not system headers are changed. Other uses of the function (e.g., taking its address)
+/// are not changed.
+///
+/// # Failures
+///
+/// * the entity is not a function or method
+/// * the index is greater than the number of parameters
+/// * a call relies on default arguments before the index
+/// * a declaration or call is produced by a macro expansion
+pub fn add_parameter(
+    function: Entity, index: usize, declaration: &str, argument: &str
+) -> Result<EditSet, RefactorError> {
+    let (declarations, calls) = try!(get_declarations_and_calls(function));
+
+    let mut edits = EditSet::new();
+    for entity in declarations {
+        let parameters = try!(get_parameters(entity));
+        let span = try!(Span::from_entity(entity));
+        if index > parameters.len() {
+            return Err(RefactorError::InvalidIndex);
+        } else if index < parameters.len() {
+            let start = parameters[index].start;
+            span.replace(&mut edits, start, start, format!("{}, ", declaration));
+        } else if let Some(last) = parameters.last() {
+            span.replace(&mut edits, last.end, last.end, format!(", {}", declaration));
+        } else {
+            let (start, end) = try!(get_parentheses(entity, span));
+            span.replace(&mut edits, start, end, declaration);
+        }
+    }
+
+    for call in calls {
+        let arguments = try!(get_arguments(call));
+        let span = try!(Span::from_entity(call));
+        if index > arguments.len() {
+            return Err(RefactorError::DefaultArgument);
+        } else if index < arguments.len() {
+            let start = arguments[index].start;
+            span.replace(&mut edits, start, start, format!("{}, ", argument));
+        } else if let Some(last) = arguments.last() {
+            span.replace(&mut edits, last.end, last.end, format!(", {}", argument));
+        } else {
+            let close = try!(get_closing_parenthesis(span));
+            span.replace(&mut edits, close, close, argument);
+        }
+    }
+    Ok(edits)
+}
+
+/// Extracts the statements in the supplied source range into a new `static` function with the
+/// supplied name which is inserted before the function containing the statements and replaces the
+/// statements with a call to the new function.
+///
+/// The source range has to cover one or more consecutive statements in a compound statement. The
+/// local variables and parameters declared outside of the statements which are used in them become
+/// the parameters of the new function. Variables which are only read in the statements are passed
+/// by value, or by `const` reference in C++ if they are records. Other variables (e.g., variables
+/// which are assigned to, have a member or element assigned to, have their address taken, or are
+/// passed to a non-`const` reference parameter) are passed by reference in C++ and by pointer in
+/// C. Arrays are passed as pointers to their first elements.
+///
+/// # Failures
+///
+/// * the source range does not cover whole statements in a function body
+/// * the statements contain a `goto` or `return` statement or a `break` or `continue` statement
+///   which does not belong to a loop or `switch` statement in the statements
+/// * the statements declare a variable which is used after them
+/// * the statements access members through `this`
+/// * the source range is produced by a macro expansion
+/// * an I/O error occurs while reading the source file
+pub fn extract_function<'tu>(
+    tu: &'tu TranslationUnit<'tu>, range: SourceRange<'tu>, name: &str
+) -> Result<EditSet, RefactorError> {
+    let selection = try!(Span::new(range).ok_or(RefactorError::InvalidRange));
+
+    let mut function = None;
+    let mut compound = None;
+    tu.get_entity().visit_children(|e, _| {
+        if e.is_in_system_header() {
+            return EntityVisitResult::Continue;
+        }
+
+        let span = match e.get_range().and_then(Span::new) {
+            Some(span) if span.contains(&selection) => span,
+            _ => return EntityVisitResult::Continue,
+        };
+        if is_function(e) && e.is_definition() {
+            function = Some(e);
+            compound = None;
+        } else if e.get_kind() == EntityKind::CompoundStmt && span.start < selection.start {
+            compound = Some((e, span));
+        }
+        EntityVisitResult::Recurse
+    });
+    let function = try!(function.ok_or(RefactorError::InvalidRange));
+    let (compound, span) = try!(compound.ok_or(RefactorError::InvalidRange));
+
+    let mut statements = vec![];
+    for child in compound.get_children() {
+        let child_span = try!(Span::from_entity(child));
+        if selection.contains(&child_span) {
+            statements.push(child);
+        } else if child_span.start < selection.end && selection.start < child_span.end {
+            return Err(RefactorError::InvalidRange);
+        }
+    }
+
+    let start = match statements.first() {
+        Some(statement) => try!(Span::from_entity(*statement)).start,
+        None => return Err(RefactorError::InvalidRange),
+    };
+    let mut end = try!(Span::from_entity(statements[statements.len() - 1])).end;
+    let rest = Span { file: span.file, start: end, end: span.end };
+    if let Some(token) = rest.tokenize().first() {
+        if token.get_spelling() == ";" {
+            end = token.get_range().get_end().get_file_location().offset;
+        }
+    }
+    let selection = Span { file: span.file, start: start, end: end };
+
+    if statements.iter().any(|s| has_jump(*s, false, false)) {
+        return Err(RefactorError::ControlFlow);
+    }
+
+    let mut parameters: Vec<Entity> = vec![];
+    let mut uses = vec![];
+    let mut declared = vec![];
+    let mut modified = vec![];
+    let mut member = false;
+    for statement in &statements {
+        walk(*statement, &mut vec![], &mut |e, ancestors| match e.get_kind() {
+            EntityKind::ThisExpr => member = true,
+            EntityKind::MemberRefExpr if e.get_children().is_empty() => member = true,
+            EntityKind::VarDecl => declared.push(e),
+            EntityKind::DeclRefExpr => {
+                let local = get_local(e).and_then(|l| {
+                    let span = l.get_location().map(|l| l.get_file_location());
+                    let inside = span.map_or(false, |s| {
+                        s.file == selection.file && s.offset >= start && s.offset < end
+                    });
+                    if inside { None } else { Some(l) }
+                });
+                if let Some(local) = local {
+                    if !parameters.contains(&local) {
+                        parameters.push(local);
+                    }
+                    if !is_read(e, ancestors) && !modified.contains(&local) {
+                        modified.push(local);
+                    }
+                    uses.push((e, local));
+                }
+            },
+            _ => { },
+        });
+    }
+
+    if member {
+        return Err(RefactorError::MemberAccess);
+    }
+
+    let mut escaping = false;
+    function.visit_children(|e, _| {
+        if e.get_kind() == EntityKind::DeclRefExpr {
+            let offset = e.get_location().map(|l| l.get_file_location().offset);
+            let reference = e.get_reference();
+            if offset >= Some(end) && reference.map_or(false, |r| declared.contains(&r)) {
+                escaping = true;
+                return EntityVisitResult::Break;
+            }
+        }
+        EntityVisitResult::Recurse
+    });
+    if escaping {
+        return Err(RefactorError::EscapingDeclaration);
+    }
+
+    let main = tu.get_entity().get_name().map(PathBuf::from);
+    let cplusplus = main.map_or(false, |m| utility::is_cplusplus(&m));
+
+    let mut declarations = vec![];
+    let mut arguments = vec![];
+    let mut replacements = vec![];
+    for parameter in &parameters {
+        let name = parameter.get_name().unwrap_or_else(String::new);
+        let type_ = parameter.get_type();
+        let element = type_.and_then(|t| if is_array(t) { t.get_element_type() } else { None });
+        let display = element.or(type_).map_or_else(String::new, |t| t.get_display_name());
+        let kind = type_.map(|t| t.get_canonical_type().get_kind());
+        let reference = kind == Some(TypeKind::LValueReference) ||
+            kind == Some(TypeKind::RValueReference);
+
+        if element.is_some() {
+            declarations.push(format_declaration(&display, "*", &name));
+            arguments.push(name);
+        } else if reference {
+            declarations.push(format_declaration(&display, "", &name));
+            arguments.push(name);
+        } else if !modified.contains(parameter) && cplusplus && kind == Some(TypeKind::Record) {
+            let constant = type_.map_or(false, |t| t.is_const_qualified());
+            let display = if constant { display } else { format!("const {}", display) };
+            declarations.push(format_declaration(&display, "&", &name));
+            arguments.push(name);
+        } else if !modified.contains(parameter) {
+            declarations.push(format_declaration(&display, "", &name));
+            arguments.push(name);
+        } else if cplusplus {
+            declarations.push(format_declaration(&display, "&", &name));
+            arguments.push(name);
+        } else {
+            for &(use_, _) in uses.iter().filter(|&&(_, l)| l == *parameter) {
+                let span = try!(Span::from_entity(use_));
+                replacements.push((span.start, span.end, format!("(*{})", name)));
+            }
+            declarations.push(format_declaration(&display, "*", &name));
+            arguments.push(format!("&{}", name));
+        }
+    }
+
+    let contents = try!(read(selection.file));
+    let line = contents[..start as usize].rfind('\n').map_or(0, |i| i + 1);
+    let indentation = &contents[line..start as usize];
+    let indentation = if indentation.trim().is_empty() { indentation } else { "" };
+
+    let text = substitute(selection.get_text(&contents), start, replacements);
+    let body = text.lines().enumerate().map(|(i, l)| {
+        let l = if i != 0 && l.starts_with(indentation) { &l[indentation.len()..] } else { l };
+        if l.trim().is_empty() { String::new() } else { format!("    {}", l) }
+    }).collect::<Vec<_>>().join("\n");
+
+    let declarations = if declarations.is_empty() && !cplusplus {
+        "void".into()
+    } else {
+        declarations.join(", ")
+    };
+    let definition = format!("static void {}({}) {{\n{}\n}}\n\n", name, declarations, body);
+    let call = format!("{}({});", name, arguments.join(", "));
+
+    let mut top = function;
+    while let Some(parent) = top.get_lexical_parent() {
+        match parent.get_kind() {
+            EntityKind::LinkageSpec | EntityKind::Namespace | EntityKind::TranslationUnit => break,
+            _ => top = parent,
+        }
+    }
+    let location = top.get_comment_range().and_then(Span::new).map_or_else(|| {
+        Span::from_entity(top).map(|s| s.start)
+    }, |s| Ok(s.start));
+    let (line, _) = selection.get_position(try!(location));
+
+    let mut edits = EditSet::new();
+    edits.insert(selection.file.get_path(), TextEdit::insertion((line, 1), definition));
+    selection.replace(&mut edits, start, end, call);
+    Ok(edits)
+}
+
+fn format_declaration(type_: &str, marker: &str, name: &str) -> String {
+    if type_.ends_with('*') || type_.ends_with('&') {
+        format!("{}{}{}", type_, marker, name)
+    } else {
+        format!("{} {}{}", type_, marker, name)
+    }
+}
+
+fn get_arguments<'tu>(call: Entity<'tu>) -> Result<Vec<Span<'tu>>, RefactorError> {
+    let arguments = call.get_arguments().unwrap_or_else(Vec::new);
+    let arguments = arguments.into_iter().filter(|a| a.get_range().is_some());
+    let arguments = try!(arguments.map(Span::from_entity).collect::<Result<Vec<_>, _>>());
+    let span = try!(Span::from_entity(call));
+    if arguments.iter().all(|a| span.contains(a)) {
+        Ok(arguments)
+    } else {
+        Err(RefactorError::MacroExpansion)
+    }
+}
+
+fn get_closing_parenthesis(span: Span) -> Result<u32, RefactorError> {
+    match span.tokenize().last() {
+        Some(token) if token.get_spelling() == ")" => {
+            Ok(token.get_location().get_file_location().offset)
+        },
+        _ => Err(RefactorError::MacroExpansion),
+    }
+}
+
+fn get_declarations_and_calls<'tu>(
+    function: Entity<'tu>
+) -> Result<(Vec<Entity<'tu>>, Vec<Entity<'tu>>), RefactorError> {
+    let function = function.get_canonical_entity();
+    match function.get_kind() {
+        EntityKind::FunctionDecl | EntityKind::Method => { },
+        _ => return Err(RefactorError::NotFound),
+    }
+
+    let mut declarations = vec![];
+    let mut calls = vec![];
+    function.get_translation_unit().get_entity().visit_children(|e, _| {
+        if e.is_in_system_header() {
+            return EntityVisitResult::Continue;
+        }
+
+        if e.get_canonical_entity() == function {
+            declarations.push(e);
+        } else if e.get_kind() == EntityKind::CallExpr {
+            if e.get_reference().map(|r| r.get_canonical_entity()) == Some(function) {
+                calls.push(e);
+            }
+        }
+        EntityVisitResult::Recurse
+    });
+    Ok((declarations, calls))
+}
+
+fn get_local(entity: Entity) -> Option<Entity> {
+    if entity.get_kind() != EntityKind::DeclRefExpr {
+        return None;
+    }
+
+    entity.get_reference().and_then(|r| match r.get_kind() {
+        EntityKind::ParmDecl | EntityKind::VarDecl => {
+            if r.get_semantic_parent().map_or(false, is_function) { Some(r) } else { None }
+        },
+        _ => None,
+    })
+}
+
+fn get_parameters<'tu>(declaration: Entity<'tu>) -> Result<Vec<Span<'tu>>, RefactorError> {
+    let parameters = declaration.get_arguments().unwrap_or_else(Vec::new);
+    parameters.into_iter().map(Span::from_entity).collect()
+}
+
+fn get_parentheses(declaration: Entity, span: Span) -> Result<(u32, u32), RefactorError> {
+    let name = try!(declaration.get_location().ok_or(RefactorError::MacroExpansion));
+    let name = name.get_file_location().offset;
+
+    let tokens = span.tokenize();
+    let mut tokens = tokens.iter().filter(|t| t.get_location().get_file_location().offset > name);
+    match tokens.next() {
+        Some(token) if token.get_spelling() == "(" => { },
+        _ => return Err(RefactorError::MacroExpansion),
+    }
+
+    let offset = |t: &Token| t.get_location().get_file_location().offset;
+    match (tokens.next(), tokens.next()) {
+        (Some(close), _) if close.get_spelling() == ")" => Ok((offset(close), offset(close))),
+        (Some(void), Some(close)) if void.get_spelling() == "void" => {
+            if close.get_spelling() == ")" {
+                Ok((offset(void), offset(close)))
+            } else {
+                Err(RefactorError::MacroExpansion)
+            }
+        },
+        _ => Err(RefactorError::MacroExpansion),
+    }
+}
+
+fn get_removal(spans: &[Span], index: usize) -> (u32, u32) {
+    if index + 1 < spans.len() {
+        (spans[index].start, spans[index + 1].start)
+    } else if index > 0 {
+        (spans[index - 1].end, spans[index].end)
+    } else {
+        (spans[index].start, spans[index].end)
+    }
+}
+
+fn has_jump(entity: Entity, in_loop: bool, in_switch: bool) -> bool {
+    let kind = entity.get_kind();
+    match kind {
+        EntityKind::GotoStmt | EntityKind::IndirectGotoStmt | EntityKind::ReturnStmt => true,
+        EntityKind::BreakStmt => !in_loop && !in_switch,
+        EntityKind::ContinueStmt => !in_loop,
+        EntityKind::LambdaExpr => false,
+        _ => {
+            let in_loop = in_loop || match kind {
+                EntityKind::DoStmt | EntityKind::ForRangeStmt | EntityKind::ForStmt |
+                EntityKind::WhileStmt => true,
+                _ => false,
+            };
+            let in_switch = in_switch || kind == EntityKind::SwitchStmt;
+            entity.get_children().into_iter().any(|c| has_jump(c, in_loop, in_switch))
+        },
+    }
+}
+
+fn inline(call: Entity, edits: &mut EditSet) -> Result<(), RefactorError> {
+    if call.get_kind() != EntityKind::CallExpr {
+        return Err(RefactorError::NotFound);
+    }
+    let function = match call.get_reference() {
+        Some(function) if function.get_kind() == EntityKind::FunctionDecl => function,
+        _ => return Err(RefactorError::NotFound),
+    };
+    let definition = try!(function.get_definition().ok_or(RefactorError::NotFound));
+
+    let body = definition.get_children().into_iter().filter(|c| {
+        c.get_kind() == EntityKind::CompoundStmt
+    }).last();
+    let statements = body.map_or_else(Vec::new, |b| b.get_children());
+    if statements.len() != 1 || statements[0].get_kind() != EntityKind::ReturnStmt {
+        return Err(RefactorError::NotTrivial);
+    }
+    let expression = match statements[0].get_children().first() {
+        Some(expression) => *expression,
+        None => return Err(RefactorError::NotTrivial),
+    };
+    let span = try!(Span::from_entity(expression));
+
+    let parameters = definition.get_arguments().unwrap_or_else(Vec::new);
+    let arguments = try!(get_arguments(call));
+    if arguments.len() != parameters.len() {
+        return Err(RefactorError::DefaultArgument);
+    }
+
+    let call_span = try!(Span::from_entity(call));
+    let name = function.get_name();
+    let tokens = call_span.tokenize();
+    let closed = tokens.last().map_or(false, |t| t.get_spelling() == ")");
+    if !closed || !tokens.iter().any(|t| Some(t.get_spelling()) == name) {
+        return Err(RefactorError::MacroExpansion);
+    }
+
+    let contents = try!(read(span.file));
+    let call_contents = if call_span.file == span.file {
+        contents.clone()
+    } else {
+        try!(read(call_span.file))
+    };
+
+    let mut uses = vec![vec![]; parameters.len()];
+    visit(expression, &mut |e| {
+        let reference = e.get_reference();
+        if e.get_kind() == EntityKind::DeclRefExpr {
+            if let Some(index) = parameters.iter().position(|p| Some(*p) == reference) {
+                uses[index].push(e);
+            }
+        }
+    });
+
+    let mut replacements = vec![];
+    for (index, argument) in arguments.iter().enumerate() {
+        let text = argument.get_text(&call_contents);
+        let simple = argument.tokenize().len() == 1;
+        if uses[index].len() != 1 && !simple {
+            return Err(RefactorError::ArgumentEvaluation);
+        }
+
+        let text = if simple { text.to_string() } else { format!("({})", text) };
+        for use_ in &uses[index] {
+            let use_ = try!(Span::from_entity(*use_));
+            replacements.push((use_.start, use_.end, text.clone()));
+        }
+    }
+
+    let simple = span.tokenize().len() == 1 || expression.get_kind() == EntityKind::ParenExpr;
+    let text = substitute(span.get_text(&contents), span.start, replacements);
+    let text = if simple { text } else { format!("({})", text) };
+    call_span.replace(edits, call_span.start, call_span.end, text);
+    Ok(())
+}
+
+/// Replaces the supplied call with the returned expression of the called function.
+///
+/// The called function has to be a function (not a method) whose body consists of a single
+/// `return` statement with a value. The uses of the parameters in the returned expression are
+/// replaced with the corresponding arguments.
+///
+/// # Failures
+///
+/// * the entity is not a call to a function with a definition
+/// * the function body is not a single `return` statement with a value
+/// * the call relies on default arguments
+/// * a parameter is not used exactly once and the corresponding argument is not a single token
+/// * the call or the function body is produced by a macro expansion
+/// * an I/O error occurs while reading a source file
+pub fn inline_call(call: Entity) -> Result<EditSet, RefactorError> {
+    let mut edits = EditSet::new();
+    try!(inline(call, &mut edits));
+    Ok(edits)
+}
+
+/// Replaces the calls to the supplied function in the files of the translation unit which are not
+/// system headers with the returned expression of the function (see `inline_call`).
+///
+/// The definition of the function is not removed since other translation units may call it. When
+/// calls are nested, only the outermost call is inlined.
+///
+/// # Failures
+///
+/// * any of the failures of `inline_call` for any of the calls
+pub fn inline_function(function: Entity) -> Result<EditSet, RefactorError> {
+    let (_, calls) = try!(get_declarations_and_calls(function));
+    let mut edits = EditSet::new();
+    for call in calls {
+        try!(inline(call, &mut edits));
+    }
+    Ok(edits)
+}
+
+fn is_array(type_: Type) -> bool {
+    match type_.get_kind() {
+        TypeKind::ConstantArray | TypeKind::DependentSizedArray |
+        TypeKind::IncompleteArray | TypeKind::VariableArray => true,
+        _ => false,
+    }
+}
+
+fn is_function(entity: Entity) -> bool {
+    match entity.get_kind() {
+        EntityKind::Constructor | EntityKind::ConversionFunction | EntityKind::Destructor |
+        EntityKind::FunctionDecl | EntityKind::FunctionTemplate | EntityKind::Method => true,
+        _ => false,
+    }
+}
+
+fn is_read(use_: Entity, ancestors: &[Entity]) -> bool {
+    let mut child = use_;
+    for parent in ancestors.iter().rev() {
+        match parent.get_kind() {
+            EntityKind::ArraySubscriptExpr | EntityKind::ParenExpr => { },
+            EntityKind::UnexposedExpr => if !child.get_type().map_or(false, is_array) {
+                return true;
+            },
+            EntityKind::MemberRefExpr => match parent.get_reference() {
+                Some(r) if r.get_kind() == EntityKind::FieldDecl => { },
+                Some(r) if r.get_kind() == EntityKind::Method => return r.is_const_method(),
+                _ => return false,
+            },
+            _ => return false,
+        }
+        child = *parent;
+    }
+    false
+}
+
+fn read(file: File) -> Result<String, RefactorError> {
+    utility::read_file(&file.get_path()).map_err(|_| RefactorError::Unreadable)
+}
+
+/// Removes the parameter at the supplied index from the supplied function and removes the
+/// corresponding argument from the calls to the function.
+///
+/// All the declarations of the function and the calls to it in the files of the translation unit
+/// which are not system headers are changed. Other uses of the function (e.g., taking its address)
+/// are not changed.
+///
+/// # Failures
+///
+/// * the entity is not a function or method
+/// * the index is not less than the number of parameters
+/// * the parameter is used in the definition of the function
+/// * a declaration or call is produced by a macro expansion
+pub fn remove_parameter(function: Entity, index: usize) -> Result<EditSet, RefactorError> {
+    let (declarations, calls) = try!(get_declarations_and_calls(function));
+
+    if let Some(definition) = function.get_definition() {
+        let parameters = definition.get_arguments().unwrap_or_else(Vec::new);
+        let parameter = try!(parameters.get(index).ok_or(RefactorError::InvalidIndex));
+        let mut used = false;
+        definition.visit_children(|e, _| {
+            used = e.get_kind() == EntityKind::DeclRefExpr && e.get_reference() == Some(*parameter);
+            if used { EntityVisitResult::Break } else { EntityVisitResult::Recurse }
+        });
+        if used {
+            return Err(RefactorError::ParameterUsed);
+        }
+    }
+
+    let mut edits = EditSet::new();
+    for declaration in declarations {
+        let parameters = try!(get_parameters(declaration));
+        if index >= parameters.len() {
+            return Err(RefactorError::InvalidIndex);
+        }
+        let (start, end) = get_removal(&parameters, index);
+        parameters[index].replace(&mut edits, start, end, "");
+    }
+
+    for call in calls {
+        let arguments = try!(get_arguments(call));
+        if index < arguments.len() {
+            let (start, end) = get_removal(&arguments, index);
+            arguments[index].replace(&mut edits, start, end, "");
+        }
+    }
+    Ok(edits)
+}
+
+fn substitute(text: &str, base: u32, mut replacements: Vec<(u32, u32, String)>) -> String {
+    replacements.sort_by_key(|r| r.0);
+    let mut result = String::new();
+    let mut position = 0;
+    for (start, end, replacement) in replacements {
+        let (start, end) = ((start - base) as usize, (end - base) as usize);
+        if start >= position && end <= text.len() {
+            result.push_str(&text[position..start]);
+            result.push_str(&replacement);
+            position = end;
+        }
+    }
+    result.push_str(&text[position..]);
+    result
+}
+
+fn visit<'tu, F: FnMut(Entity<'tu>)>(entity: Entity<'tu>, f: &mut F) {
+    f(entity);
+    entity.visit_children(|e, _| {
+        f(e);
+        EntityVisitResult::Recurse
+    });
+}
+
+fn walk<'tu, F: FnMut(Entity<'tu>, &[Entity<'tu>])>(
+    entity: Entity<'tu>, ancestors: &mut Vec<Entity<'tu>>, f: &mut F
+) {
+    f(entity, ancestors);
+    ancestors.push(entity);
+    for child in entity.get_children() {
+        walk(child, ancestors, f);
+    }
+    ancestors.pop();
+}
@@ -0,0 +1,285 @@
+// Copyright 2016 Kyle Mayes
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Renaming declarations and the references to them.
+
+use std::fmt;
+use std::collections::{BTreeMap, HashSet};
+use std::error::{Error};
+use std::path::{PathBuf};
+
+use super::{Entity, EntityKind, EntityVisitResult, TranslationUnit};
+use super::diagnostic::{FixIt};
+use super::document::{TextEdit};
+use super::source::{File, SourceLocation, SourceRange};
+
+//================================================
+// Enums
+//================================================
+
+// RenameError ___________________________________
+
+/// Indicates the error that prevented a rename.
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash)]
+pub enum RenameError {
+    /// The declaration or a reference to it is produced by a macro expansion.
+    MacroExpansion,
+    /// There is no named declaration to rename.
+    NotFound,
+    /// The declaration is in a system header.
+    SystemHeader,
+}
+
+impl Error for RenameError {
+    fn description(&self) -> &str {
+        match *self {
+            RenameError::MacroExpansion => "a macro expansion makes the rename ambiguous",
+            RenameError::NotFound => "there is no named declaration to rename",
+            RenameError::SystemHeader => "the declaration is in a system header",
+        }
+    }
+}
+
+impl fmt::Display for RenameError {
+    fn fmt(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+        write!(formatter, "{}", self.description())
+    }
+}
+
+//================================================
+// Structs
+//================================================
+
+// Rename ________________________________________
+
+/// The occurrences of the name of a declaration in the files of a translation unit.
+///
+/// The occurrences include the names of all the declarations of the declaration, the references to
+/// it in files which are not system headers, and, for classes, the names of the constructors and
+/// destructors of the class.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Rename<'tu> {
+    entity: Entity<'tu>,
+    name: String,
+    ranges: Vec<SourceRange<'tu>>,
+}
+
+impl<'tu> Rename<'tu> {
+    //- Constructors -----------------------------
+
+    /// Finds the occurrences of the name of the supplied declaration or of the declaration referred
+    /// to by the supplied entity.
+    ///
+    /// Constructors and destructors are treated as their classes.
+    ///
+    /// # Failures
+    ///
+    /// * the entity is not a named declaration and does not refer to one
+    /// * the declaration is in a system header
+    /// * the declaration or a reference to it is produced by a macro expansion
+    pub fn new(entity: Entity<'tu>) -> Result<Rename<'tu>, RenameError> {
+        let entity = entity.get_reference().unwrap_or(entity);
+        let entity = match entity.get_kind() {
+            EntityKind::Constructor | EntityKind::Destructor => {
+                entity.get_semantic_parent().unwrap_or(entity)
+            },
+            _ => entity,
+        };
+        let entity = entity.get_canonical_entity();
+
+        let name = match entity.get_name() {
+            Some(name) => name,
+            None => return Err(RenameError::NotFound),
+        };
+        if entity.get_location().is_none() {
+            return Err(RenameError::NotFound);
+        }
+        if entity.is_in_system_header() {
+            return Err(RenameError::SystemHeader);
+        }
+
+        let mut declarations = vec![];
+        let mut references = vec![];
+        let mut targets = HashSet::new();
+        targets.insert(entity);
+        entity.get_translation_unit().get_entity().visit_children(|e, _| {
+            if e.is_in_system_header() {
+                return EntityVisitResult::Continue;
+            }
+
+            let parent = e.get_semantic_parent().map(|p| p.get_canonical_entity());
+            if is_record(entity) && parent == Some(entity) && is_special_member(e, &name) {
+                targets.insert(e.get_canonical_entity());
+                declarations.push(e);
+            } else if e.get_canonical_entity() == entity {
+                declarations.push(e);
+            } else if is_reference(e) {
+                if e.get_reference().map(|r| r.get_canonical_entity()) == Some(entity) {
+                    references.push(e);
+                }
+            }
+            EntityVisitResult::Recurse
+        });
+
+        let mut ranges = BTreeMap::new();
+        for declaration in declarations {
+            let range = try!(get_declaration_range(declaration, &name));
+            insert(&mut ranges, range);
+        }
+        for reference in references {
+            if let Some(location) = reference.get_location() {
+                let range = try!(find_name(location, &name).ok_or(RenameError::MacroExpansion));
+                insert(&mut ranges, range);
+            }
+        }
+        for file in get_files(entity.get_translation_unit()) {
+            for target in &targets {
+                let ended = file.visit_references(*target, |_, r| {
+                    match find_name(r.get_start(), &name) {
+                        Some(range) => {
+                            insert(&mut ranges, range);
+                            true
+                        },
+                        None => false,
+                    }
+                });
+                if ended {
+                    return Err(RenameError::MacroExpansion);
+                }
+            }
+        }
+
+        let ranges = ranges.into_iter().map(|(_, r)| r).collect();
+        Ok(Rename { entity: entity, name: name, ranges: ranges })
+    }
+
+    /// Finds the occurrences of the name of the declaration at the supplied source location or of
+    /// the declaration referred to by the entity at the supplied source location.
+    ///
+    /// # Failures
+    ///
+    /// * there is no entity at the source location
+    /// * any of the failures of `Rename::new`
+    pub fn from_location(location: SourceLocation<'tu>) -> Result<Rename<'tu>, RenameError> {
+        location.get_entity().ok_or(RenameError::NotFound).and_then(Rename::new)
+    }
+
+    //- Accessors --------------------------------
+
+    /// Returns the canonical declaration being renamed.
+    pub fn get_entity(&self) -> Entity<'tu> {
+        self.entity
+    }
+
+    /// Returns the current name of the declaration being renamed.
+    pub fn get_name(&self) -> &str {
+        &self.name
+    }
+
+    /// Returns the source ranges of the occurrences of the name, sorted by file path and offset.
+    pub fn get_ranges(&self) -> &[SourceRange<'tu>] {
+        &self.ranges
+    }
+
+    /// Returns the text edits which replace the occurrences of the name with the supplied name
+    /// grouped by the paths of the files they apply to.
+    ///
+    /// The edits for each file are sorted by position and do not overlap.
+    pub fn get_edits(&self, name: &str) -> BTreeMap<PathBuf, Vec<TextEdit>> {
+        let mut edits = BTreeMap::new();
+        for range in &self.ranges {
+            let start = range.get_start().get_file_location();
+            let end = range.get_end().get_file_location();
+            let edit = TextEdit::new((start.line, start.column), (end.line, end.column), name);
+            edits.entry(start.file.get_path()).or_insert_with(Vec::new).push(edit);
+        }
+        edits
+    }
+
+    /// Returns the fix-its which replace the occurrences of the name with the supplied name.
+    pub fn get_fix_its(&self, name: &str) -> Vec<FixIt<'tu>> {
+        self.ranges.iter().map(|r| FixIt::Replacement(*r, name.into())).collect()
+    }
+}
+
+//================================================
+// Functions
+//================================================
+
+fn find_name<'tu>(location: SourceLocation<'tu>, name: &str) -> Option<SourceRange<'tu>> {
+    let location = location.get_file_location();
+    let start = location.file.get_offset_location(location.offset);
+    let limit = location.offset + name.len() as u32 + 1;
+    let end = location.file.get_offset_location(limit);
+    SourceRange::new(start, end).tokenize().into_iter().find(|t| {
+        t.get_spelling() == name && t.get_location().get_file_location().offset < limit
+    }).map(|t| t.get_range())
+}
+
+fn get_declaration_range<'tu>(
+    entity: Entity<'tu>, name: &str
+) -> Result<SourceRange<'tu>, RenameError> {
+    let location = try!(entity.get_location().ok_or(RenameError::NotFound));
+    let offset = location.get_file_location().offset;
+    let location = entity.get_name_ranges().into_iter().map(|r| {
+        (r.get_start(), r.get_start().get_file_location(), r.get_end().get_file_location())
+    }).find(|&(_, s, e)| s.file == e.file).map_or(location, |(start, s, e)| {
+        if s.offset <= offset && offset <= e.offset { location } else { start }
+    });
+    find_name(location, name).ok_or(RenameError::MacroExpansion)
+}
+
+fn get_files<'tu>(tu: &'tu TranslationUnit<'tu>) -> Vec<File<'tu>> {
+    let main = tu.get_entity().get_name().and_then(|n| tu.get_file(n));
+    let mut files = main.into_iter().collect::<Vec<_>>();
+    let mut visited = vec![];
+    while let Some(file) = files.pop() {
+        if !visited.contains(&file) && !file.get_offset_location(0).is_in_system_header() {
+            visited.push(file);
+            files.extend(file.get_includes().into_iter().filter_map(|i| i.get_file()));
+        }
+    }
+    visited
+}
+
+fn insert<'tu>(ranges: &mut BTreeMap<(PathBuf, u32), SourceRange<'tu>>, range: SourceRange<'tu>) {
+    let location = range.get_start().get_file_location();
+    ranges.insert((location.file.get_path(), location.offset), range);
+}
+
+fn is_record(entity: Entity) -> bool {
+    match entity.get_kind() {
+        EntityKind::ClassDecl | EntityKind::ClassTemplate |
+        EntityKind::ClassTemplatePartialSpecialization | EntityKind::StructDecl |
+        EntityKind::UnionDecl => true,
+        _ => false,
+    }
+}
+
+fn is_reference(entity: Entity) -> bool {
+    match entity.get_kind() {
+        EntityKind::DeclRefExpr | EntityKind::MacroExpansion | EntityKind::MemberRef |
+        EntityKind::MemberRefExpr | EntityKind::NamespaceRef | EntityKind::OverloadedDeclRef |
+        EntityKind::TemplateRef | EntityKind::TypeRef | EntityKind::VariableRef => true,
+        _ => false,
+    }
+}
+
+fn is_special_member(entity: Entity, name: &str) -> bool {
+    match entity.get_kind() {
+        EntityKind::Constructor | EntityKind::Destructor => true,
+        EntityKind::FunctionTemplate => entity.get_name().map_or(false, |n| n == name),
+        _ => false,
+    }
+}
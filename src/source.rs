@@ -17,6 +17,7 @@
 use std::cmp;
 use std::fmt;
 use std::hash;
+use std::io;
 use std::mem;
 use std::slice;
 use std::path::{Path, PathBuf};
@@ -27,7 +28,7 @@ use libc::{c_uint, time_t};
 
 use utility::{self, Nullable};
 use super::{Entity, TranslationUnit};
-use super::token::{Token};
+use super::token::{Token, TokenStream};
 
 //================================================
 // Structs
@@ -96,6 +97,14 @@ impl<'tu> File<'tu> {
         unsafe { clang_isFileMultipleIncludeGuarded(self.tu.ptr, self.ptr) != 0 }
     }
 
+    /// Returns whether this file is null (e.g., the file of a source location which is not in a
+    /// file).
+    ///
+    /// The other methods of a null file should not be called.
+    pub fn is_null(&self) -> bool {
+        self.ptr.0.is_null()
+    }
+
     /// Returns the source location at the supplied line and column in this file.
     ///
     /// # Panics
@@ -138,6 +147,27 @@ impl<'tu> File<'tu> {
         references
     }
 
+    /// Tokenizes the contents of this file on disk and returns the resulting tokens and trivia.
+    ///
+    /// # Failures
+    ///
+    /// * an I/O error occurs while reading this file
+    /// * this file is not valid UTF-8
+    pub fn tokenize(&self) -> io::Result<TokenStream<'tu>> {
+        utility::read_file(&self.get_path()).map(|c| self.tokenize_contents(&c))
+    }
+
+    /// Tokenizes the supplied contents of this file and returns the resulting tokens and trivia.
+    ///
+    /// The supplied contents should be the contents this file had when the translation unit that
+    /// contains this file was parsed (e.g., the contents of an unsaved file).
+    pub fn tokenize_contents(&self, contents: &str) -> TokenStream<'tu> {
+        let start = self.get_offset_location(0);
+        let end = self.get_offset_location(contents.len() as u32);
+        let tokens = SourceRange::new(start, end).tokenize();
+        TokenStream::from_tokens(tokens, *self, contents)
+    }
+
     /// Visits the inclusion directives in this file and returns whether visitation was ended by the
     /// callback returning `false`.
     pub fn visit_includes<F: FnMut(Entity<'tu>, SourceRange<'tu>) -> bool>(&self, f: F) -> bool {
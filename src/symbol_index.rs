@@ -0,0 +1,465 @@
+// Copyright 2016 Kyle Mayes
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Persistent indexes of the symbols in many translation units.
+
+use std::fs;
+use std::io;
+use std::mem;
+use std::collections::{BTreeMap, BTreeSet, HashMap};
+use std::collections::btree_map::{Entry};
+use std::io::{BufReader, BufWriter, Read, Write};
+use std::path::{Path, PathBuf};
+
+use super::{Entity, EntityKind, EntityVisitResult, TranslationUnit, Usr};
+
+/// The bytes that start a saved symbol index.
+const MAGIC: &'static [u8] = b"CSIX";
+
+/// The version of the format of a saved symbol index.
+const VERSION: u32 = 1;
+
+//================================================
+// Enums
+//================================================
+
+// Role __________________________________________
+
+/// Indicates how an occurrence of a symbol relates to that symbol.
+#[derive(Copy, Clone, Debug, PartialEq, Eq, PartialOrd, Ord, Hash)]
+#[repr(C)]
+pub enum Role {
+    /// The occurrence is a declaration of the symbol that is not a definition.
+    Declaration = 0,
+    /// The occurrence is the definition of the symbol.
+    Definition = 1,
+    /// The occurrence is a reference to the symbol.
+    Reference = 2,
+}
+
+//================================================
+// Structs
+//================================================
+
+// Occurrence ____________________________________
+
+/// A declaration, definition, or reference of a symbol.
+#[derive(Clone, Debug, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct Occurrence {
+    /// The path of the source file the occurrence is in.
+    pub path: PathBuf,
+    /// The line of the occurrence.
+    pub line: u32,
+    /// The column of the occurrence.
+    pub column: u32,
+    /// How the occurrence relates to the symbol.
+    pub role: Role,
+}
+
+// Symbol ________________________________________
+
+/// A declared entity and its occurrences across the translation units in a symbol index.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Symbol {
+    /// The USR of the symbol.
+    pub usr: Usr,
+    /// The name of the symbol.
+    pub name: String,
+    /// The categorization of the declarations of the symbol.
+    pub kind: EntityKind,
+    /// The occurrences of the symbol, sorted by position.
+    pub occurrences: Vec<Occurrence>,
+    /// The USRs of the methods the symbol overrides, if the symbol is a method.
+    pub overrides: Vec<Usr>,
+    /// The USRs of the base classes of the symbol, if the symbol is a class.
+    pub bases: Vec<Usr>,
+}
+
+// SymbolIndex ___________________________________
+
+/// An index of the declarations, definitions, and references of the symbols in a set of
+/// translation units.
+///
+/// Symbols are keyed by their USRs, so the occurrences of a symbol in different translation units
+/// are combined. A symbol index can be saved to and loaded from a file so that the source files
+/// do not need to be parsed again to query it.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct SymbolIndex {
+    symbols: BTreeMap<Usr, Symbol>,
+}
+
+impl SymbolIndex {
+    //- Constructors -----------------------------
+
+    /// Constructs a new empty `SymbolIndex`.
+    pub fn new() -> SymbolIndex {
+        SymbolIndex::default()
+    }
+
+    /// Loads a symbol index from a file created by `save`.
+    ///
+    /// # Failures
+    ///
+    /// * an I/O error occurs while reading the file
+    /// * the file is not a symbol index or was created by an incompatible version of this crate
+    pub fn load<F: AsRef<Path>>(file: F) -> io::Result<SymbolIndex> {
+        let mut reader = BufReader::new(try!(fs::File::open(file)));
+
+        let mut magic = [0; 4];
+        try!(reader.read_exact(&mut magic));
+        if &magic[..] != MAGIC || try!(read_u32(&mut reader)) != VERSION {
+            return Err(invalid("not a symbol index or an unsupported version"));
+        }
+
+        let mut paths = vec![];
+        for _ in 0..try!(read_u32(&mut reader)) {
+            paths.push(PathBuf::from(try!(read_string(&mut reader))));
+        }
+
+        let mut symbols = vec![];
+        let mut relations = vec![];
+        for _ in 0..try!(read_u32(&mut reader)) {
+            let usr = Usr(try!(read_string(&mut reader)));
+            let name = try!(read_string(&mut reader));
+            let kind = try!(to_kind(try!(read_u32(&mut reader))).ok_or(invalid("invalid kind")));
+
+            let mut occurrences = vec![];
+            for _ in 0..try!(read_u32(&mut reader)) {
+                let path = try!(read_u32(&mut reader)) as usize;
+                let path = try!(paths.get(path).ok_or(invalid("invalid path")));
+                let line = try!(read_u32(&mut reader));
+                let column = try!(read_u32(&mut reader));
+                let role = match try!(read_u32(&mut reader)) {
+                    0 => Role::Declaration,
+                    1 => Role::Definition,
+                    2 => Role::Reference,
+                    _ => return Err(invalid("invalid role")),
+                };
+                occurrences.push(Occurrence {
+                    path: path.clone(), line: line, column: column, role: role
+                });
+            }
+
+            let mut overrides = vec![];
+            for _ in 0..try!(read_u32(&mut reader)) {
+                overrides.push(try!(read_u32(&mut reader)) as usize);
+            }
+            let mut bases = vec![];
+            for _ in 0..try!(read_u32(&mut reader)) {
+                bases.push(try!(read_u32(&mut reader)) as usize);
+            }
+            relations.push((overrides, bases));
+
+            symbols.push(Symbol {
+                usr: usr,
+                name: name,
+                kind: kind,
+                occurrences: occurrences,
+                overrides: vec![],
+                bases: vec![],
+            });
+        }
+
+        let usrs = symbols.iter().map(|s| s.usr.clone()).collect::<Vec<_>>();
+        let resolve = |indices: Vec<usize>| -> io::Result<Vec<Usr>> {
+            indices.into_iter().map(|i| {
+                usrs.get(i).cloned().ok_or(invalid("invalid symbol"))
+            }).collect()
+        };
+
+        let mut index = SymbolIndex::new();
+        for (mut symbol, (overrides, bases)) in symbols.into_iter().zip(relations.into_iter()) {
+            symbol.overrides = try!(resolve(overrides));
+            symbol.bases = try!(resolve(bases));
+            index.symbols.insert(symbol.usr.clone(), symbol);
+        }
+        Ok(index)
+    }
+
+    //- Accessors --------------------------------
+
+    /// Returns the declarations of the symbol with the supplied USR that are not definitions.
+    pub fn find_declarations(&self, usr: &Usr) -> Vec<&Occurrence> {
+        self.find_occurrences(usr, Role::Declaration)
+    }
+
+    /// Returns the definition of the symbol with the supplied USR, if any.
+    pub fn find_definition(&self, usr: &Usr) -> Option<&Occurrence> {
+        self.find_occurrences(usr, Role::Definition).into_iter().next()
+    }
+
+    fn find_occurrences(&self, usr: &Usr, role: Role) -> Vec<&Occurrence> {
+        self.symbols.get(usr).map_or(vec![], |s| {
+            s.occurrences.iter().filter(|o| o.role == role).collect()
+        })
+    }
+
+    /// Returns the methods which directly override the method with the supplied USR.
+    pub fn find_overrides(&self, usr: &Usr) -> Vec<&Symbol> {
+        self.symbols.values().filter(|s| s.overrides.contains(usr)).collect()
+    }
+
+    /// Returns the references to the symbol with the supplied USR.
+    pub fn find_references(&self, usr: &Usr) -> Vec<&Occurrence> {
+        self.find_occurrences(usr, Role::Reference)
+    }
+
+    /// Returns the classes which directly inherit from the class with the supplied USR.
+    pub fn find_subclasses(&self, usr: &Usr) -> Vec<&Symbol> {
+        self.symbols.values().filter(|s| s.bases.contains(usr)).collect()
+    }
+
+    /// Returns the symbols with the supplied name.
+    pub fn find_symbols<N: AsRef<str>>(&self, name: N) -> Vec<&Symbol> {
+        self.symbols.values().filter(|s| s.name == name.as_ref()).collect()
+    }
+
+    /// Returns the symbol with the supplied USR, if any.
+    pub fn get_symbol(&self, usr: &Usr) -> Option<&Symbol> {
+        self.symbols.get(usr)
+    }
+
+    /// Returns the symbols in this symbol index, sorted by USR.
+    pub fn get_symbols(&self) -> Vec<&Symbol> {
+        self.symbols.values().collect()
+    }
+
+    /// Saves this symbol index to a file.
+    ///
+    /// # Failures
+    ///
+    /// * an I/O error occurs while writing the file
+    pub fn save<F: AsRef<Path>>(&self, file: F) -> io::Result<()> {
+        let paths = self.symbols.values().flat_map(|s| {
+            s.occurrences.iter().map(|o| &o.path)
+        }).collect::<BTreeSet<_>>();
+        let indices = paths.iter().enumerate().map(|(i, p)| (*p, i)).collect::<HashMap<_, _>>();
+        let symbols = self.symbols.keys().enumerate().map(|(i, u)| (u, i));
+        let symbols = symbols.collect::<HashMap<_, _>>();
+
+        let mut writer = BufWriter::new(try!(fs::File::create(file)));
+        try!(writer.write_all(MAGIC));
+        try!(write_u32(&mut writer, VERSION));
+
+        try!(write_u32(&mut writer, paths.len() as u32));
+        for path in &paths {
+            try!(write_string(&mut writer, &path.to_string_lossy()));
+        }
+
+        try!(write_u32(&mut writer, self.symbols.len() as u32));
+        for symbol in self.symbols.values() {
+            try!(write_string(&mut writer, &symbol.usr.0));
+            try!(write_string(&mut writer, &symbol.name));
+            try!(write_u32(&mut writer, symbol.kind as u32));
+
+            try!(write_u32(&mut writer, symbol.occurrences.len() as u32));
+            for occurrence in &symbol.occurrences {
+                try!(write_u32(&mut writer, indices[&occurrence.path] as u32));
+                try!(write_u32(&mut writer, occurrence.line));
+                try!(write_u32(&mut writer, occurrence.column));
+                try!(write_u32(&mut writer, occurrence.role as u32));
+            }
+
+            for usrs in &[&symbol.overrides, &symbol.bases] {
+                let usrs = usrs.iter().filter_map(|u| symbols.get(u)).collect::<Vec<_>>();
+                try!(write_u32(&mut writer, usrs.len() as u32));
+                for index in usrs {
+                    try!(write_u32(&mut writer, *index as u32));
+                }
+            }
+        }
+
+        writer.flush()
+    }
+
+    //- Mutators ---------------------------------
+
+    /// Adds the declarations, definitions, and references in the supplied translation unit to this
+    /// symbol index.
+    ///
+    /// Declarations and references in system headers are not added, but references to symbols
+    /// declared in system headers are. The translation unit should be constructed with a detailed
+    /// preprocessing record for macro definitions and expansions to be added.
+    pub fn add<'tu>(&mut self, tu: &'tu TranslationUnit<'tu>) {
+        tu.get_entity().visit_children(|e, _| {
+            if e.is_in_system_header() {
+                return EntityVisitResult::Continue;
+            }
+            self.add_entity(e);
+            EntityVisitResult::Recurse
+        });
+
+        for symbol in self.symbols.values_mut() {
+            normalize(symbol);
+        }
+    }
+
+    fn add_entity(&mut self, entity: Entity) {
+        let kind = entity.get_kind();
+        if entity.is_declaration() || kind == EntityKind::MacroDefinition {
+            let role = if entity.is_definition() || kind == EntityKind::MacroDefinition {
+                Role::Definition
+            } else {
+                Role::Declaration
+            };
+            let usr = match self.add_symbol(entity) {
+                Some(usr) => usr,
+                None => return,
+            };
+            self.add_occurrence(entity, &usr, role);
+
+            let overrides = entity.get_overridden_methods().unwrap_or_else(Vec::new);
+            let overrides = overrides.into_iter().filter_map(|o| self.add_symbol(o));
+            let overrides = overrides.collect::<Vec<_>>();
+            let bases = entity.get_children().into_iter().filter(|c| {
+                c.get_kind() == EntityKind::BaseSpecifier
+            }).filter_map(|b| {
+                b.get_type().and_then(|t| t.get_declaration()).or_else(|| b.get_reference())
+            }).filter_map(|b| self.add_symbol(b)).collect::<Vec<_>>();
+
+            let symbol = self.symbols.get_mut(&usr).unwrap();
+            symbol.overrides.extend(overrides);
+            symbol.bases.extend(bases);
+        } else if entity.is_reference() || is_expression_reference(kind) {
+            if let Some(usr) = entity.get_reference().and_then(|r| self.add_symbol(r)) {
+                self.add_occurrence(entity, &usr, Role::Reference);
+            }
+        }
+    }
+
+    fn add_occurrence(&mut self, entity: Entity, usr: &Usr, role: Role) {
+        if let Some(location) = entity.get_location() {
+            let location = location.get_file_location();
+            let occurrence = Occurrence {
+                path: location.file.get_path(),
+                line: location.line,
+                column: location.column,
+                role: role,
+            };
+            self.symbols.get_mut(usr).unwrap().occurrences.push(occurrence);
+        }
+    }
+
+    fn add_symbol(&mut self, entity: Entity) -> Option<Usr> {
+        // Only symbols with kinds that can be loaded from a saved symbol index are added.
+        if to_kind(entity.get_kind() as u32).is_none() {
+            return None;
+        }
+
+        entity.get_usr().map(|usr| {
+            self.symbols.entry(usr.clone()).or_insert_with(|| Symbol {
+                usr: usr.clone(),
+                name: entity.get_name().unwrap_or_else(String::new),
+                kind: entity.get_kind(),
+                occurrences: vec![],
+                overrides: vec![],
+                bases: vec![],
+            });
+            usr
+        })
+    }
+
+    /// Adds the symbols in the supplied symbol index to this symbol index.
+    pub fn merge(&mut self, other: SymbolIndex) {
+        for (usr, symbol) in other.symbols {
+            match self.symbols.entry(usr) {
+                Entry::Occupied(mut entry) => {
+                    let existing = entry.get_mut();
+                    existing.occurrences.extend(symbol.occurrences);
+                    existing.overrides.extend(symbol.overrides);
+                    existing.bases.extend(symbol.bases);
+                    normalize(existing);
+                },
+                Entry::Vacant(entry) => {
+                    entry.insert(symbol);
+                },
+            }
+        }
+    }
+
+    /// Removes the occurrences in the source file with the supplied path from this symbol index.
+    ///
+    /// This should be used before a translation unit which contains a modified source file is added
+    /// to this symbol index again. Symbols that no longer have occurrences are removed.
+    pub fn remove_file<F: AsRef<Path>>(&mut self, file: F) {
+        for symbol in self.symbols.values_mut() {
+            symbol.occurrences.retain(|o| o.path != file.as_ref());
+        }
+        let empty = self.symbols.values().filter(|s| s.occurrences.is_empty()).map(|s| {
+            s.usr.clone()
+        }).collect::<Vec<_>>();
+        for usr in empty {
+            self.symbols.remove(&usr);
+        }
+    }
+}
+
+//================================================
+// Functions
+//================================================
+
+fn invalid(message: &str) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, message)
+}
+
+fn is_expression_reference(kind: EntityKind) -> bool {
+    match kind {
+        EntityKind::DeclRefExpr | EntityKind::MemberRefExpr | EntityKind::MacroExpansion => true,
+        _ => false,
+    }
+}
+
+fn normalize(symbol: &mut Symbol) {
+    symbol.occurrences.sort();
+    symbol.occurrences.dedup();
+    symbol.overrides.sort();
+    symbol.overrides.dedup();
+    symbol.bases.sort();
+    symbol.bases.dedup();
+}
+
+fn read_string<R: Read>(reader: &mut R) -> io::Result<String> {
+    let length = try!(read_u32(reader)) as usize;
+    let mut bytes = vec![0; length];
+    try!(reader.read_exact(&mut bytes));
+    String::from_utf8(bytes).map_err(|_| invalid("invalid string"))
+}
+
+fn read_u32<R: Read>(reader: &mut R) -> io::Result<u32> {
+    let mut bytes = [0; 4];
+    try!(reader.read_exact(&mut bytes));
+    let bytes = bytes.iter().enumerate().map(|(i, b)| (*b as u32) << (i * 8));
+    Ok(bytes.fold(0, |a, b| a | b))
+}
+
+fn to_kind(raw: u32) -> Option<EntityKind> {
+    match raw {
+        1...39 | 501 | 600 => Some(unsafe { mem::transmute(raw as i32) }),
+        #[cfg(feature="gte_clang_3_8")]
+        601 => Some(EntityKind::TypeAliasTemplateDecl),
+        #[cfg(feature="gte_clang_3_9")]
+        602 => Some(EntityKind::StaticAssert),
+        _ => None,
+    }
+}
+
+fn write_string<W: Write>(writer: &mut W, string: &str) -> io::Result<()> {
+    try!(write_u32(writer, string.len() as u32));
+    writer.write_all(string.as_bytes())
+}
+
+fn write_u32<W: Write>(writer: &mut W, value: u32) -> io::Result<()> {
+    let bytes = [value as u8, (value >> 8) as u8, (value >> 16) as u8, (value >> 24) as u8];
+    writer.write_all(&bytes)
+}
@@ -16,12 +16,13 @@
 
 use std::fmt;
 use std::mem;
+use std::path::{PathBuf};
 
 use clang_sys::*;
 
 use utility;
-use super::{TranslationUnit};
-use super::source::{SourceLocation, SourceRange};
+use super::{Index, SourceError, TranslationUnit, Unsaved};
+use super::source::{File, SourceLocation, SourceRange};
 
 //================================================
 // Enums
@@ -45,10 +46,78 @@ pub enum TokenKind {
     Punctuation = 0,
 }
 
+// TriviaKind ____________________________________
+
+/// Indicates the categorization of a piece of trivia.
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash)]
+pub enum TriviaKind {
+    /// A comment.
+    Comment,
+    /// A line ending (`"\n"`, `"\r\n"`, or `"\r"`).
+    Newline,
+    /// Text that was not lexed as a token (e.g., a line continuation).
+    Skipped,
+    /// Horizontal whitespace.
+    Whitespace,
+}
+
 //================================================
 // Structs
 //================================================
 
+// Lexer _________________________________________
+
+/// Lexes an unsaved file.
+///
+/// The unsaved file is parsed as an incomplete translation unit with function bodies skipped so
+/// that it can be lexed without being fully parsed.
+#[derive(Debug)]
+pub struct Lexer<'i> {
+    tu: TranslationUnit<'i>,
+    path: PathBuf,
+    contents: String,
+}
+
+impl<'i> Lexer<'i> {
+    //- Constructors -----------------------------
+
+    /// Constructs a new `Lexer` which lexes the supplied unsaved file.
+    ///
+    /// The supplied compiler arguments determine the language (and thus the keywords) the unsaved
+    /// file is lexed with.
+    ///
+    /// # Failures
+    ///
+    /// * `libclang` crashes
+    /// * an unknown error occurs
+    pub fn new<S: AsRef<str>>(
+        index: &'i Index<'i>, unsaved: &Unsaved, arguments: &[S]
+    ) -> Result<Lexer<'i>, SourceError> {
+        let path = PathBuf::from(unsaved.path.to_string_lossy().into_owned());
+        let contents = unsaved.contents.to_string_lossy().into_owned();
+        let mut parser = index.parser(&path);
+        parser.arguments(arguments).unsaved(&[Unsaved::new(&path, &contents)]);
+        let tu = try!(parser.incomplete(true).skip_function_bodies(true).parse());
+        Ok(Lexer { tu: tu, path: path, contents: contents })
+    }
+
+    //- Accessors --------------------------------
+
+    /// Returns the translation unit the unsaved file was parsed into.
+    pub fn get_translation_unit(&self) -> &TranslationUnit<'i> {
+        &self.tu
+    }
+
+    /// Tokenizes the unsaved file and returns the resulting tokens and trivia.
+    pub fn tokenize<'l>(&'l self) -> TokenStream<'l> {
+        let tu: &'l TranslationUnit<'l> = &self.tu;
+        match tu.get_file(&self.path) {
+            Some(file) => file.tokenize_contents(&self.contents),
+            None => TokenStream { tokens: vec![], trailing: split_trivia(&self.contents, 0) },
+        }
+    }
+}
+
 // Token _________________________________________
 
 /// A lexed piece of a source file.
@@ -98,3 +167,191 @@ impl<'tu> fmt::Debug for Token<'tu> {
             .finish()
     }
 }
+
+// TokenStream ___________________________________
+
+/// The tokens and trivia in a source file.
+///
+/// Every byte of the source file is covered by exactly one token or piece of trivia so the source
+/// file can be reconstructed exactly.
+#[derive(Clone, Debug)]
+pub struct TokenStream<'tu> {
+    /// The tokens, excluding comments which are considered trivia.
+    pub tokens: Vec<TriviaToken<'tu>>,
+    /// The trivia that follows the trailing trivia of the last token (or all of the trivia if there
+    /// are no tokens).
+    pub trailing: Vec<Trivia>,
+}
+
+impl<'tu> TokenStream<'tu> {
+    //- Constructors -----------------------------
+
+    #[doc(hidden)]
+    pub fn from_tokens(tokens: Vec<Token<'tu>>, file: File<'tu>, text: &str) -> TokenStream<'tu> {
+        enum Piece<'tu> {
+            Token(Token<'tu>, usize, usize),
+            Trivia(Trivia),
+        }
+
+        let offset = |l: SourceLocation| l.get_file_location().offset as usize;
+
+        let mut pieces = vec![];
+        let mut start = 0;
+        for token in tokens {
+            let range = token.get_range();
+            if range.get_start().get_file_location().file != file {
+                continue;
+            }
+
+            let (begin, end) = (offset(range.get_start()), offset(range.get_end()));
+            if begin < start || end > text.len() || !text.is_char_boundary(begin) ||
+               !text.is_char_boundary(end) {
+                continue;
+            }
+
+            pieces.extend(split_trivia(&text[start..begin], start).into_iter().map(Piece::Trivia));
+            if token.get_kind() == TokenKind::Comment {
+                let comment = Trivia {
+                    kind: TriviaKind::Comment, text: text[begin..end].into(), offset: begin
+                };
+                pieces.push(Piece::Trivia(comment));
+            } else {
+                pieces.push(Piece::Token(token, begin, end));
+            }
+            start = end;
+        }
+        pieces.extend(split_trivia(&text[start..], start).into_iter().map(Piece::Trivia));
+
+        let mut stream = TokenStream { tokens: vec![], trailing: vec![] };
+        let mut leading = vec![];
+        let mut trailing = false;
+        for piece in pieces {
+            match piece {
+                Piece::Token(token, begin, end) => {
+                    stream.tokens.push(TriviaToken {
+                        token: token,
+                        text: text[begin..end].into(),
+                        offset: begin,
+                        leading: mem::replace(&mut leading, vec![]),
+                        trailing: vec![],
+                    });
+                    trailing = true;
+                },
+                Piece::Trivia(trivia) => if trailing {
+                    trailing = trivia.kind != TriviaKind::Newline;
+                    stream.tokens.last_mut().unwrap().trailing.push(trivia);
+                } else {
+                    leading.push(trivia);
+                },
+            }
+        }
+        stream.trailing = leading;
+        stream
+    }
+
+    //- Accessors --------------------------------
+
+    /// Returns the text of the source file reconstructed from the tokens and trivia.
+    pub fn get_text(&self) -> String {
+        let mut text = String::new();
+        for token in &self.tokens {
+            token.write_text(&mut text);
+        }
+        for trivia in &self.trailing {
+            text.push_str(&trivia.text);
+        }
+        text
+    }
+}
+
+// Trivia ________________________________________
+
+/// A piece of a source file that is not significant to the meaning of the source file.
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub struct Trivia {
+    /// The categorization of this trivia.
+    pub kind: TriviaKind,
+    /// The text of this trivia.
+    pub text: String,
+    /// The byte offset of this trivia in the source file.
+    pub offset: usize,
+}
+
+// TriviaToken ___________________________________
+
+/// A token and the trivia that surrounds it.
+///
+/// The trailing trivia of a token is the trivia that follows the token up to and including the end
+/// of the line the token ends on. The leading trivia of a token is all of the preceding trivia that
+/// is not the trailing trivia of the previous token.
+#[derive(Clone, Debug)]
+pub struct TriviaToken<'tu> {
+    /// The token.
+    pub token: Token<'tu>,
+    /// The text of the token exactly as it appears in the source file.
+    pub text: String,
+    /// The byte offset of the token in the source file.
+    pub offset: usize,
+    /// The leading trivia.
+    pub leading: Vec<Trivia>,
+    /// The trailing trivia.
+    pub trailing: Vec<Trivia>,
+}
+
+impl<'tu> TriviaToken<'tu> {
+    //- Accessors --------------------------------
+
+    /// Returns the text of this token and its leading and trailing trivia.
+    pub fn get_full_text(&self) -> String {
+        let mut text = String::new();
+        self.write_text(&mut text);
+        text
+    }
+
+    fn write_text(&self, text: &mut String) {
+        for trivia in &self.leading {
+            text.push_str(&trivia.text);
+        }
+        text.push_str(&self.text);
+        for trivia in &self.trailing {
+            text.push_str(&trivia.text);
+        }
+    }
+}
+
+//================================================
+// Functions
+//================================================
+
+fn split_trivia(text: &str, offset: usize) -> Vec<Trivia> {
+    let classify = |c: char| {
+        match c {
+            '\n' | '\r' => TriviaKind::Newline,
+            ' ' | '\t' | '\x0B' | '\x0C' => TriviaKind::Whitespace,
+            _ => TriviaKind::Skipped,
+        }
+    };
+
+    let mut trivia: Vec<Trivia> = vec![];
+    let mut chars = text.char_indices().peekable();
+    while let Some((index, c)) = chars.next() {
+        let kind = classify(c);
+        let mut end = index + c.len_utf8();
+        if kind == TriviaKind::Newline {
+            if c == '\r' && chars.peek().map_or(false, |&(_, n)| n == '\n') {
+                chars.next();
+                end += 1;
+            }
+        } else {
+            while let Some(&(next, n)) = chars.peek() {
+                if classify(n) != kind {
+                    break;
+                }
+                chars.next();
+                end = next + n.len_utf8();
+            }
+        }
+        trivia.push(Trivia { kind: kind, text: text[index..end].into(), offset: offset + index });
+    }
+    trivia
+}
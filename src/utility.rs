@@ -12,13 +12,19 @@
 // See the License for the specific language governing permissions and
 // limitations under the License.
 
+use std::fs;
+use std::io;
 use std::mem;
 use std::ffi::{CStr, CString};
+use std::io::{Read};
 use std::path::{Path};
+use std::time::{UNIX_EPOCH};
 
 use clang_sys::*;
 
-use libc::{c_void};
+use libc::{c_void, time_t};
+
+use super::{Entity, EntityKind};
 
 //================================================
 // Macros
@@ -82,6 +88,20 @@ macro_rules! iter_option {
     });
 }
 
+// object! _______________________________________
+
+/// Constructs a JSON object from a list of keys and values.
+macro_rules! object {
+    ($($key:expr => $value:expr), *) => ({
+        #[allow(unused_mut)]
+        let mut object = ::std::collections::BTreeMap::new();
+        $(object.insert(String::from($key), $value.to_json());)*
+        ::rustc_serialize::json::Json::Object(object)
+    });
+
+    ($($key:expr => $value:expr), +,) => (object!($($key => $value), +));
+}
+
 // options! ______________________________________
 
 /// Defines a struct that maps bitflags to fields.
@@ -277,6 +297,18 @@ pub fn addressof<T>(value: &mut T) -> *mut c_void {
     (value as *mut T) as *mut c_void
 }
 
+pub fn escape_html(text: &str, html: &mut String) {
+    for c in text.chars() {
+        match c {
+            '&' => html.push_str("&amp;"),
+            '<' => html.push_str("&lt;"),
+            '>' => html.push_str("&gt;"),
+            '"' => html.push_str("&quot;"),
+            c => html.push(c),
+        }
+    }
+}
+
 pub fn from_path<P: AsRef<Path>>(path: P) -> CString {
     from_string(path.as_ref().as_os_str().to_str().expect("invalid C string"))
 }
@@ -285,6 +317,36 @@ pub fn from_string<S: AsRef<str>>(string: S) -> CString {
     CString::new(string.as_ref()).expect("invalid C string")
 }
 
+pub fn get_condition_count(children: &[Entity]) -> usize {
+    match children.first() {
+        Some(child) if child.get_kind() == EntityKind::VarDecl => 2,
+        _ => 1,
+    }
+}
+
+pub fn get_time(path: &Path) -> Option<time_t> {
+    let modified = fs::metadata(path).and_then(|m| m.modified()).ok();
+    modified.and_then(|m| m.duration_since(UNIX_EPOCH).ok()).map(|d| d.as_secs() as time_t)
+}
+
+pub fn is_cplusplus(path: &Path) -> bool {
+    match path.extension().and_then(|e| e.to_str()) {
+        Some("C") | Some("cc") | Some("cp") | Some("cpp") | Some("cxx") | Some("c++") => true,
+        Some("hh") | Some("hpp") | Some("hxx") | Some("ii") | Some("mm") => true,
+        _ => false,
+    }
+}
+
+pub fn quote_dot(string: &str) -> String {
+    format!("\"{}\"", string.replace('\\', "\\\\").replace('"', "\\\""))
+}
+
+pub fn read_file(path: &Path) -> io::Result<String> {
+    let mut contents = String::new();
+    try!(try!(fs::File::open(path)).read_to_string(&mut contents));
+    Ok(contents)
+}
+
 pub fn to_string(clang: CXString) -> String {
     unsafe {
         let c = CStr::from_ptr(clang_getCString(clang));
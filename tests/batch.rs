@@ -0,0 +1,52 @@
+use std::thread;
+use std::sync::{Arc};
+
+use clang::*;
+use clang::batch::*;
+
+pub fn test(clang: &Arc<Clang>) {
+    let files = &[
+        ("a.cpp", "int a = 322;\n"),
+        ("b.cpp", "int b = 644;\nint c = 0;\n"),
+        ("c.cpp", "int c = 1;\n"),
+    ];
+
+    super::with_temporary_files(files, |d, fs| {
+        let mut batch = Batch::new(2);
+        batch.add(&fs[0], &["-std=c++11"]).add(&fs[1], &["-std=c++11"]);
+        batch.add(d.join("missing.cpp"), &["-std=c++11"]).add(&fs[2], &["-DVALUE"]);
+
+        let mut options = ThreadOptions::default();
+        options.indexing = true;
+        batch.thread_options(options).options(TranslationUnit::get_default_parse_options());
+
+        assert_eq!(batch.get_jobs().len(), 4);
+        assert_eq!(batch.get_threads(), 2);
+        assert_eq!(batch.get_jobs()[2].path, d.join("missing.cpp"));
+        assert_eq!(batch.get_jobs()[3].arguments, &["-DVALUE"]);
+
+        let outcomes = batch.run(clang, |p, tu| {
+            (p.to_path_buf(), tu.get_entity().get_children().len())
+        });
+        assert_eq!(outcomes.len(), 4);
+        assert_eq!(outcomes[0].path, fs[0]);
+        assert_eq!(outcomes[0].result, Ok((fs[0].clone(), 1)));
+        assert_eq!(outcomes[1].result, Ok((fs[1].clone(), 2)));
+        assert!(outcomes[2].result.is_err());
+        assert_eq!(outcomes[3].result, Ok((fs[2].clone(), 1)));
+
+        let outcomes = Batch::new(0).run(clang, |_, _| ());
+        assert!(outcomes.is_empty());
+
+        let caller = thread::current().name().map(|n| n.to_string());
+        let outcomes = batch.run(clang, |_, _| thread::current().name().map(|n| n.to_string()));
+        for outcome in outcomes.iter().filter(|o| o.result.is_ok()) {
+            let name = outcome.result.as_ref().unwrap();
+            if cfg!(feature="runtime") {
+                assert_eq!(name, &caller);
+            } else {
+                assert!(name.as_ref().map_or(false, |n| n.starts_with("clang-batch-")));
+            }
+        }
+    });
+}
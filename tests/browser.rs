@@ -0,0 +1,72 @@
+use std::path::{PathBuf};
+
+use clang::*;
+use clang::browser::*;
+use clang::highlight::{SemanticKind};
+
+pub fn test(clang: &Clang) {
+    let files = &[
+        ("math.h", "/// Adds two integers.\nint add(int a, int b);\n"),
+        ("math.cpp", "#include \"math.h\"\nint add(int a, int b) { return a + b; }\n"),
+        ("main.cpp", "#include \"math.h\"\nint main() { return add(1, 2); }\n"),
+    ];
+
+    super::with_temporary_files(files, |d, fs| {
+        let index = Index::new(&clang, false, false);
+        let mut site = Site::new(d);
+        for f in fs.iter().rev().take(2) {
+            site.add(&index.parser(f).parse().unwrap()).unwrap();
+        }
+
+        let position = |path: &PathBuf, line, column| {
+            Position { path: path.clone(), line: line, column: column }
+        };
+
+        let mut symbols = site.get_symbols().iter().enumerate();
+        let (i, add) = symbols.find(|&(_, s)| s.name == "add").unwrap();
+        assert_eq!(site.get_symbol(&add.usr), Some(add));
+        assert_eq!(add.kind, SemanticKind::Function);
+        assert_eq!(add.type_, Some("int (int, int)".into()));
+        assert!(add.documentation.as_ref().unwrap().contains("Adds two integers."));
+        assert_eq!(add.declaration, Some(position(&fs[0], 2, 5)));
+        assert_eq!(add.definition, Some(position(&fs[1], 2, 5)));
+        let references = add.references.iter().map(|r| r.position.clone()).collect::<Vec<_>>();
+        assert_eq!(references, &[
+            position(&fs[2], 2, 21), position(&fs[1], 2, 5), position(&fs[0], 2, 5)
+        ]);
+        assert_eq!(add.references[0].text, "int main() { return add(1, 2); }");
+
+        let pages = site.render();
+        let page = |path: &str| &pages[&PathBuf::from(path)];
+
+        for path in &["index.html", "main.cpp.html", "math.cpp.html", "math.h.html", "style.css"] {
+            assert!(pages.contains_key(&PathBuf::from(path)));
+        }
+        assert!(page("index.html").contains("<a href=\"math.h.html\">math.h</a>"));
+
+        let main = page("main.cpp.html");
+        assert!(main.contains("<a id=\"L2\" href=\"#L2\">2</a>"));
+        assert!(main.contains("<span class=\"keyword\">return</span>"));
+        assert!(main.contains(
+            "<a class=\"function\" href=\"math.cpp.html#L2\" title=\"int (int, int)\n\nAdds two"
+        ));
+        let definition = format!("<a class=\"function declaration\" href=\"refs/{}.html\"", i);
+        assert!(page("math.cpp.html").contains(&definition));
+        let comment = "<span class=\"comment\">/// Adds two integers.</span>";
+        assert!(page("math.h.html").contains(comment));
+
+        let references = page(&format!("refs/{}.html", i));
+        assert!(references.contains("<h1 class=\"function\">add</h1>"));
+        assert!(references.contains(
+            "<p>Definition: <a href=\"../math.cpp.html#L2\">math.cpp:2:5</a></p>"
+        ));
+        assert!(references.contains(concat!(
+            "<li><a href=\"../main.cpp.html#L2\">main.cpp:2:21</a> ",
+            "<code>int main() { return add(1, 2); }</code></li>",
+        )));
+
+        site.write(d.join("site")).unwrap();
+        assert!(d.join("site").join("main.cpp.html").is_file());
+        assert!(d.join("site").join("refs").join(format!("{}.html", i)).is_file());
+    });
+}
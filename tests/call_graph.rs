@@ -0,0 +1,88 @@
+use std::collections::{BTreeSet};
+
+use clang::*;
+use clang::call_graph::*;
+
+use rustc_serialize::json::{ToJson};
+
+pub fn test(clang: &Clang) {
+    let source = "
+        struct Base { virtual void run() { } };
+        void helper() { }
+        void unused() { helper(); }
+        void apply(void (*f)()) { f(); }
+        void call(Base& b) { b.run(); }
+        int main() { helper(); apply(helper); return 0; }
+    ";
+
+    super::with_translation_unit(clang, "test.cpp", source, &[], |_, f, tu| {
+        let mut graph = CallGraph::new();
+        graph.add(&tu);
+
+        let usr = |name: &str| {
+            graph.get_functions().iter().find(|f| f.name == name).unwrap().usr.clone()
+        };
+        let (run, helper, unused) = (usr("run()"), usr("helper()"), usr("unused()"));
+        let (apply, call, main) = (usr("apply(void (*)())"), usr("call(Base &)"), usr("main()"));
+
+        assert!(graph.get_function(&helper).unwrap().defined);
+        assert_eq!(graph.get_function(&helper).unwrap().path, Some(f.into()));
+
+        assert_eq!(graph.get_callees(&unused), &[Call {
+            caller: unused.clone(),
+            callee: Some(helper.clone()),
+            kind: CallKind::Direct,
+            path: f.into(),
+            line: 4,
+            column: 25,
+        }]);
+
+        let calls = graph.get_callees(&apply);
+        assert_eq!(calls.len(), 1);
+        assert_eq!(calls[0].kind, CallKind::Indirect);
+        assert_eq!((calls[0].line, calls[0].column), (5, 35));
+        assert!(graph.get_function(calls[0].callee.as_ref().unwrap()).is_none());
+
+        let calls = graph.get_callees(&call);
+        assert_eq!(calls.len(), 1);
+        assert_eq!(calls[0].kind, CallKind::Virtual);
+        assert_eq!(calls[0].callee, Some(run.clone()));
+
+        let calls = graph.get_callees(&main).iter().map(|c| {
+            (c.callee.clone().unwrap(), c.kind, c.line, c.column)
+        }).collect::<Vec<_>>();
+        assert_eq!(calls, &[
+            (helper.clone(), CallKind::Direct, 7, 22),
+            (apply.clone(), CallKind::Direct, 7, 32),
+        ]);
+
+        let callers = graph.get_callers(&helper).iter().map(|c| c.caller.clone()).collect();
+        assert_eq!(callers, [main.clone(), unused.clone()].iter().cloned().collect::<BTreeSet<_>>());
+        assert_eq!(graph.get_transitive_callers(&helper), callers);
+        assert!(graph.get_transitive_callers(&main).is_empty());
+
+        let reachable = [main.clone(), helper.clone(), apply.clone()].iter().cloned().collect();
+        assert_eq!(graph.get_reachable(&[main.clone()]), reachable);
+        let mut unreachable = graph.get_unreachable(&[main.clone()]).iter().map(|f| {
+            f.name.clone()
+        }).collect::<Vec<_>>();
+        unreachable.sort();
+        assert_eq!(unreachable, &["call(Base &)", "run()", "unused()"]);
+
+        let dot = graph.to_dot();
+        assert!(dot.starts_with("digraph calls {\n"));
+        assert!(dot.contains(&format!("    \"{}\" [label=\"main()\", shape=ellipse];", main.0)));
+        assert!(dot.contains(&format!("    \"{}\" -> \"{}\" [style=solid];", main.0, helper.0)));
+        assert!(dot.contains(&format!("    \"{}\" -> \"{}\" [style=dashed];", call.0, run.0)));
+        assert!(dot.contains(&format!("    \"{}\" -> \"(indirect)\" [style=dotted];", apply.0)));
+
+        let json = graph.to_json();
+        let function = json.find_path(&["functions", &main.0]).unwrap();
+        assert_eq!(function.find("name").unwrap().as_string(), Some("main()"));
+        let calls = json.find_path(&["calls", &unused.0]).unwrap().as_array().unwrap();
+        assert_eq!(calls.len(), 1);
+        assert_eq!(calls[0].find("callee").unwrap().as_string(), Some(&helper.0[..]));
+        assert_eq!(calls[0].find("kind").unwrap().as_string(), Some("direct"));
+        assert_eq!(calls[0].find("line").unwrap().as_u64(), Some(4));
+    });
+}
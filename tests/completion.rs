@@ -117,5 +117,94 @@ pub fn test(clang: &Clang) {
             CompletionChunk::TypedText("A".into()),
             CompletionChunk::Text("::".into()),
         ]);
+
+        let item = results[0].as_item();
+        assert_eq!(item.label, "operator=(const A &)");
+        assert_eq!(item.detail, Some("A &".into()));
+        assert_eq!(item.documentation, None);
+        assert_eq!(item.kind, CompletionItemKind::Method);
+        assert_eq!(item.filter_text, Some("operator=".into()));
+        assert_eq!(item.snippets, &["operator=(${1:const A &})"]);
+
+        let item = results[2 + offset].as_item();
+        assert_eq!(item.label, "a");
+        assert_eq!(item.detail, Some("int".into()));
+        assert_eq!(item.documentation, Some("An integer field.".into()));
+        assert_eq!(item.kind, CompletionItemKind::Field);
+        assert_eq!(item.snippets, &["a"]);
+    });
+
+    // CompletionItem ____________________________
+
+    let source = "
+        /// \\brief Does things.
+        void f(int a, int b = 0, int c = 0);
+        void g() { f }
+    ";
+
+    super::with_temporary_file("test.cpp", source, |_, f| {
+        let index = Index::new(&clang, false, false);
+        let tu = index.parser(f).briefs_in_completion_results(true).parse().unwrap();
+
+        let results = tu.completer(f, 4, 20).briefs(true).complete();
+        let results = results.get_results();
+        let result = results.iter().find(|r| {
+            r.kind == EntityKind::FunctionDecl && r.string.get_typed_text() == Some("f".into())
+        }).unwrap();
+
+        let item = result.as_item();
+        assert_eq!(item.label, "f(int a)");
+        assert_eq!(item.detail, Some("void".into()));
+        assert_eq!(item.documentation, Some("Does things.".into()));
+        assert_eq!(item.kind, CompletionItemKind::Function);
+        assert_eq!(item.snippets, &[
+            "f(${1:int a})",
+            "f(${1:int a}, ${2:int b})",
+            "f(${1:int a}, ${2:int b}, ${3:int c})",
+        ]);
+    });
+
+    // SignatureHelp _____________________________
+
+    let source = "
+        /// \\brief Adds integers.
+        int add(int a, int b);
+        float add(float a, float b);
+        int main() { return add(1, ); }
+    ";
+
+    super::with_temporary_file("test.cpp", source, |_, f| {
+        #[cfg(feature="gte_clang_3_7")]
+        fn test_signature_help(tu: &TranslationUnit, f: &::std::path::Path) {
+            let mut help = tu.completer(f, 5, 36).briefs(true).signature_help();
+            help.signatures.sort_by(|a, b| a.label.cmp(&b.label));
+            assert_eq!(help.signatures.len(), 2);
+            assert!(help.active_signature.is_some());
+            assert_eq!(help.get_active_parameter(), Some(1));
+
+            assert_eq!(help.signatures[0], Signature {
+                label: "float add(float a, float b)".into(),
+                name: "add".into(),
+                result_type: Some("float".into()),
+                parameters: vec!["float a".into(), "float b".into()],
+                active_parameter: Some(1),
+                documentation: None,
+            });
+            assert_eq!(help.signatures[1], Signature {
+                label: "int add(int a, int b)".into(),
+                name: "add".into(),
+                result_type: Some("int".into()),
+                parameters: vec!["int a".into(), "int b".into()],
+                active_parameter: Some(1),
+                documentation: Some("Adds integers.".into()),
+            });
+        }
+
+        #[cfg(not(feature="gte_clang_3_7"))]
+        fn test_signature_help(_: &TranslationUnit, _: &::std::path::Path) { }
+
+        let index = Index::new(&clang, false, false);
+        let tu = index.parser(f).briefs_in_completion_results(true).parse().unwrap();
+        test_signature_help(&tu, f);
     });
 }
@@ -0,0 +1,194 @@
+use clang::*;
+use clang::control_flow::*;
+
+fn get_successors(cfg: &ControlFlowGraph) -> Vec<Vec<(usize, EdgeKind)>> {
+    let blocks = cfg.get_blocks().iter();
+    blocks.map(|b| b.successors.iter().map(|e| (e.target, e.kind)).collect()).collect()
+}
+
+pub fn test(clang: &Clang) {
+    use clang::control_flow::EdgeKind::*;
+
+    let source = "
+        int f(int x) {
+            if (x) {
+                return 1;
+            } else {
+                x++;
+            }
+            while (x < 10) {
+                if (x == 5) break;
+                x++;
+                continue;
+            }
+            return x;
+            x--;
+        }
+
+        struct S { int m() { return 0; } };
+
+        int h();
+    ";
+
+    super::with_entity(&clang, source, |e| {
+        let children = e.get_children();
+
+        let cfg = ControlFlowGraph::new(children[0]).unwrap();
+        assert_eq!(cfg.get_entity(), children[0]);
+        assert_eq!(cfg.get_entry(), 0);
+        assert_eq!(cfg.get_exit(), 1);
+        assert_eq!(get_successors(&cfg), &[
+            vec![(2, Unconditional)],
+            vec![],
+            vec![(3, True), (4, False)],
+            vec![(1, Unconditional)],
+            vec![(5, Unconditional)],
+            vec![(6, Unconditional)],
+            vec![(7, True), (10, False)],
+            vec![(8, True), (9, False)],
+            vec![(10, Unconditional)],
+            vec![(6, Unconditional)],
+            vec![(1, Unconditional)],
+            vec![(1, Unconditional)],
+        ]);
+
+        let blocks = cfg.get_blocks();
+        assert_eq!(blocks[2].statements.len(), 1);
+        assert_eq!(blocks[2].terminator.map(|t| t.get_kind()), Some(EntityKind::IfStmt));
+        assert_eq!(blocks[3].statements[0].get_kind(), EntityKind::ReturnStmt);
+        assert_eq!(blocks[4].statements[0].get_kind(), EntityKind::UnaryOperator);
+        assert_eq!(blocks[6].terminator.map(|t| t.get_kind()), Some(EntityKind::WhileStmt));
+        assert_eq!(blocks[8].terminator.map(|t| t.get_kind()), Some(EntityKind::BreakStmt));
+        assert_eq!(blocks[9].terminator.map(|t| t.get_kind()), Some(EntityKind::ContinueStmt));
+
+        assert_eq!(cfg.get_predecessors(6), &[5, 9]);
+        assert_eq!(cfg.get_unreachable_blocks(), &[11]);
+        assert_eq!(cfg.get_cyclomatic_complexity(), 4);
+        assert!(!cfg.can_fall_off_end());
+
+        let dot = cfg.to_dot();
+        assert!(dot.starts_with("digraph cfg {\n"));
+        assert!(dot.contains("    0 [label=\"ENTRY\\l\", shape=ellipse];\n"));
+        assert!(dot.contains("    4 [label=\"B4\\lx ++\\l\", shape=box];\n"));
+        assert!(dot.contains("    0 -> 2;\n"));
+        assert!(dot.contains("    2 -> 3 [label=true];\n"));
+        assert!(dot.ends_with("}\n"));
+
+        let method = children[1].get_children()[0];
+        let cfg = ControlFlowGraph::new(method).unwrap();
+        assert_eq!(get_successors(&cfg), &[
+            vec![(2, Unconditional)],
+            vec![],
+            vec![(1, Unconditional)],
+        ]);
+        assert_eq!(cfg.get_cyclomatic_complexity(), 1);
+
+        assert_eq!(ControlFlowGraph::new(children[2]), None);
+        assert_eq!(ControlFlowGraph::new(e), None);
+    });
+
+    let source = "
+        void g(int x) {
+            switch (x) {
+            case 0:
+                x++;
+            case 1:
+                break;
+            default:
+                x--;
+            }
+            for (int i = 0; i < x; i++) {
+                if (i) continue;
+            }
+            do {
+                x--;
+            } while (x);
+            goto end;
+            x = 0;
+        end:
+            try {
+                throw 1;
+            } catch (int e) {
+                x = e;
+            } catch (...) {
+            }
+        }
+    ";
+
+    super::with_entity(&clang, source, |e| {
+        let cfg = ControlFlowGraph::new(e.get_children()[0]).unwrap();
+        assert_eq!(get_successors(&cfg), &[
+            vec![(2, Unconditional)],
+            vec![],
+            vec![(3, Case), (4, Case), (5, Default)],
+            vec![(4, Unconditional)],
+            vec![(6, Unconditional)],
+            vec![(6, Unconditional)],
+            vec![(7, Unconditional)],
+            vec![(8, True), (12, False)],
+            vec![(9, True), (10, False)],
+            vec![(11, Unconditional)],
+            vec![(11, Unconditional)],
+            vec![(7, Unconditional)],
+            vec![(13, Unconditional)],
+            vec![(14, Unconditional)],
+            vec![(13, True), (15, False)],
+            vec![(17, Unconditional)],
+            vec![(17, Unconditional)],
+            vec![(18, Unconditional), (19, Exception), (20, Exception)],
+            vec![(19, Exception), (20, Exception)],
+            vec![(21, Unconditional)],
+            vec![(21, Unconditional)],
+            vec![(1, Unconditional)],
+        ]);
+
+        let blocks = cfg.get_blocks();
+        let labels = blocks.iter().map(|b| b.label.map(|l| l.get_kind())).collect::<Vec<_>>();
+        assert_eq!(labels[3], Some(EntityKind::CaseStmt));
+        assert_eq!(labels[5], Some(EntityKind::DefaultStmt));
+        assert_eq!(labels[17], Some(EntityKind::LabelStmt));
+        assert_eq!(labels[19], Some(EntityKind::CatchStmt));
+        assert_eq!(labels[20], Some(EntityKind::CatchStmt));
+        assert_eq!(blocks[6].statements[0].get_kind(), EntityKind::DeclStmt);
+        assert_eq!(blocks[11].statements[0].get_kind(), EntityKind::UnaryOperator);
+        assert_eq!(blocks[15].terminator.map(|t| t.get_kind()), Some(EntityKind::GotoStmt));
+        assert_eq!(blocks[17].terminator.map(|t| t.get_kind()), Some(EntityKind::TryStmt));
+        assert_eq!(blocks[18].statements[0].get_kind(), EntityKind::ThrowExpr);
+        assert_eq!(blocks[19].statements[0].get_kind(), EntityKind::VarDecl);
+
+        assert_eq!(cfg.get_unreachable_blocks(), &[16]);
+        assert!(cfg.can_fall_off_end());
+
+        let dot = cfg.to_dot();
+        assert!(dot.contains("    3 [label=\"B3\\lcase 0:\\lx ++\\l\", shape=box];\n"));
+        assert!(dot.contains("    15 [label=\"B15\\lgoto end\\l\", shape=box];\n"));
+        assert!(dot.contains("    17 -> 19 [label=exception];\n"));
+    });
+
+    let source = "
+        int h(int x) {
+            while (1) {
+                if (x) return x;
+            }
+        }
+        int i(int x) {
+            do {
+                if (x) return x;
+            } while ((true));
+        }
+        int j(int x) {
+            while (x) {
+                if (x) return x;
+            }
+        }
+    ";
+
+    super::with_entity(&clang, source, |e| {
+        let children = e.get_children();
+        let cfg = ControlFlowGraph::new(children[0]).unwrap();
+        assert_eq!(get_successors(&cfg)[3], &[(4, Unconditional)]);
+        assert!(!cfg.can_fall_off_end());
+        assert!(!ControlFlowGraph::new(children[1]).unwrap().can_fall_off_end());
+        assert!(ControlFlowGraph::new(children[2]).unwrap().can_fall_off_end());
+    });
+}
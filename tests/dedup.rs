@@ -0,0 +1,59 @@
+use clang::*;
+use clang::dedup::*;
+
+pub fn test(clang: &Clang) {
+    let files = &[
+        ("header.h", "#pragma once\nstruct S { int x; };\nint f();\n"),
+        ("a.cpp", "#include \"header.h\"\nint a() { return f(); }\n"),
+        ("b.cpp", "#include \"header.h\"\nint f() { return 0; }\nint b();\n"),
+    ];
+
+    super::with_temporary_files(files, |_, fs| {
+        let index = Index::new(&clang, false, false);
+        let a = index.parser(&fs[1]).parse().unwrap();
+        let b = index.parser(&fs[2]).parse().unwrap();
+
+        let visit = |deduplicator: &mut Deduplicator, tu: &TranslationUnit| {
+            let mut files = vec![];
+            let mut names = vec![];
+            deduplicator.visit(tu, |f| files.push(f.get_path()), |e, _| {
+                names.push(e.get_name().unwrap());
+                EntityVisitResult::Recurse
+            });
+            (files, names)
+        };
+
+        let mut deduplicator = Deduplicator::new(KeyKind::Location);
+        let (files, names) = visit(&mut deduplicator, &a);
+        assert_eq!(files, &[fs[0].clone(), fs[1].clone()]);
+        assert_eq!(names, &["S", "x", "f", "a"]);
+
+        let (files, names) = visit(&mut deduplicator, &b);
+        assert_eq!(files, &[fs[2].clone()]);
+        assert_eq!(names, &["f", "b"]);
+        assert_eq!(deduplicator.get_declaration_count(), 6);
+        assert_eq!(deduplicator.get_file_count(), 3);
+
+        let s = a.get_entity().get_children()[0];
+        assert!(deduplicator.is_seen(s));
+        assert!(deduplicator.is_seen_file(a.get_file(&fs[0]).unwrap()));
+        assert!(!deduplicator.insert(s));
+        match deduplicator.get_key(s) {
+            Some(Key::Location(id, 20, EntityKind::StructDecl)) => {
+                assert_eq!(id, a.get_file(&fs[0]).unwrap().get_id())
+            },
+            key => panic!("unexpected key: {:?}", key),
+        }
+
+        deduplicator.clear();
+        assert_eq!(deduplicator.get_declaration_count(), 0);
+        assert!(!deduplicator.is_seen(s));
+
+        let mut deduplicator = Deduplicator::new(KeyKind::Usr);
+        let (_, names) = visit(&mut deduplicator, &a);
+        assert_eq!(names, &["S", "x", "f", "a"]);
+        let (_, names) = visit(&mut deduplicator, &b);
+        assert_eq!(names, &["b"]);
+        assert_eq!(deduplicator.get_key(s), Some(Key::Usr(Usr("c:@S@S".into()))));
+    });
+}
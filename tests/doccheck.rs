@@ -0,0 +1,61 @@
+use clang::*;
+use clang::doccheck::*;
+use clang::documentation::{ParameterDirection};
+
+pub fn test(clang: &Clang) {
+    let source = r#"
+        /// \brief Documented properly.
+        /// \param [in] a An input.
+        /// \param [out] b An output.
+        /// \return A value.
+        int good(int a, int *b);
+
+        void undocumented(int a);
+
+        /// \brief Missing things.
+        /// \param [out] a Wrong direction.
+        /// \param c Not a parameter.
+        int bad(const int *a, int b);
+
+        static void hidden(int a);
+
+        /// \brief A template.
+        /// \tparam U Not a template parameter.
+        /// \param t A value.
+        template <typename T>
+        void templated(T t);
+
+        class C {
+        public:
+            /// \brief A method.
+            void method(int a);
+        private:
+            void secret(int a);
+        };
+    "#;
+
+    super::with_entity(&clang, source, |e| {
+        let findings = check_documentation(e.get_children());
+
+        let actual = findings.iter().map(|f| {
+            (f.entity.get_name().unwrap(), f.kind.clone())
+        }).collect::<Vec<_>>();
+        assert_eq!(actual, &[
+            ("undocumented".into(), FindingKind::Undocumented),
+            ("bad".into(), FindingKind::DirectionMismatch("a".into(), ParameterDirection::Out)),
+            ("bad".into(), FindingKind::UndocumentedParameter("b".into())),
+            ("bad".into(), FindingKind::UnknownParameter("c".into())),
+            ("bad".into(), FindingKind::MissingReturn),
+            ("templated".into(), FindingKind::UndocumentedTemplateParameter("T".into())),
+            ("templated".into(), FindingKind::UnknownTemplateParameter("U".into())),
+            ("method".into(), FindingKind::UndocumentedParameter("a".into())),
+        ]);
+
+        let line = |i: usize| findings[i].range.get_start().get_spelling_location().line;
+        assert_eq!(line(0), 8);
+        assert_eq!(line(1), 13);
+        assert_eq!(line(2), 13);
+        assert_eq!(line(3), 10);
+        assert_eq!(line(4), 10);
+    });
+}
@@ -0,0 +1,75 @@
+use clang::*;
+use clang::diagnostic::*;
+use clang::document::*;
+
+pub fn test(clang: &Clang) {
+    // Document __________________________________
+
+    let files = &[
+        ("header.h", "int header();\n"),
+        ("main.cpp", "#include \"header.h\"\nint main() { return header(); }\n"),
+    ];
+
+    super::with_temporary_files(files, |_, fs| {
+        let index = Index::new(&clang, false, false);
+        let mut document = Document::new(&index, &fs[1], &["-Wall"]);
+        assert_eq!(document.get_path(), fs[1].as_path());
+        assert!(document.get_translation_unit().is_none());
+        assert!(document.is_stale());
+
+        let reparse = document.update().unwrap().unwrap();
+        assert!(reparse.parsed);
+        assert!(reparse.added.is_empty());
+        assert!(document.get_translation_unit().is_some());
+        assert!(!document.is_stale());
+        assert_eq!(document.update(), Ok(None));
+
+        document.edit(&fs[1], &[TextEdit::new((2, 21), (2, 27), "missing")]).unwrap();
+        let contents = "#include \"header.h\"\nint main() { return missing(); }\n";
+        assert_eq!(document.get_contents(&fs[1]), Some(contents));
+        assert_eq!(document.get_unsaved_changes(), &[fs[1].as_path()]);
+        assert!(document.is_stale());
+
+        let reparse = document.update().unwrap().unwrap();
+        assert!(!reparse.parsed);
+        assert_eq!(reparse.unsaved, &[fs[1].clone()]);
+        assert!(reparse.modified.is_empty());
+        assert_eq!(reparse.added.len(), 1);
+        assert_eq!(reparse.added[0].severity, Severity::Error);
+        assert_eq!(reparse.added[0].path.as_ref(), Some(&fs[1]));
+        assert_eq!((reparse.added[0].line, reparse.added[0].column), (2, 21));
+        assert!(reparse.removed.is_empty());
+        assert_eq!(document.get_diagnostics(), &reparse.added[..]);
+        assert!(!document.is_stale());
+
+        document.set_contents(fs[1].as_path(), contents);
+        assert!(!document.is_stale());
+
+        document.remove_contents(&fs[1]);
+        assert_eq!(document.get_contents(&fs[1]), None);
+        assert!(document.is_stale());
+
+        let reparse = document.update().unwrap().unwrap();
+        assert!(reparse.added.is_empty());
+        assert_eq!(reparse.removed.len(), 1);
+        assert!(document.get_diagnostics().is_empty());
+
+        let mut document = Document::new(&index, &fs[1], &["-Wunknown-flag"]);
+        let reparse = document.update().unwrap().unwrap();
+        assert_eq!(reparse.added.len(), 1);
+        assert_eq!(reparse.added[0].severity, Severity::Warning);
+        assert_eq!(reparse.added[0].path, None);
+    });
+
+    // TextEdit __________________________________
+
+    let mut text = String::from("int a = 322;\nint b = 644;\n");
+    TextEdit::new((1, 9), (1, 12), "7").apply(&mut text);
+    assert_eq!(text, "int a = 7;\nint b = 644;\n");
+    TextEdit::new((1, 11), (2, 1), " ").apply(&mut text);
+    assert_eq!(text, "int a = 7; int b = 644;\n");
+    TextEdit::insertion((1, 100), " // a").apply(&mut text);
+    assert_eq!(text, "int a = 7; int b = 644; // a\n");
+    TextEdit::insertion((5, 1), "int c;\n").apply(&mut text);
+    assert_eq!(text, "int a = 7; int b = 644; // a\nint c;\n");
+}
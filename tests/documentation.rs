@@ -67,14 +67,126 @@ pub fn test(clang: &Clang) {
             CommentChild::Text(" ".into()),
         ]));
         assert_eq!(children[5], CommentChild::TParamCommand(TParamCommand {
-            position: Some((1, 0)), parameter: "T".into(),
+            position: Some((1, 0)), parameter: "T".into(), children: vec![
+                CommentChild::Text(" This template parameter doesn't actually do anything.".into()),
+            ],
         }));
         assert_eq!(children[6], CommentChild::ParamCommand(ParamCommand {
-            index: Some(0), parameter: "i".into(), direction: Some(ParameterDirection::In),
+            index: Some(0),
+            parameter: "i".into(),
+            direction: Some(ParameterDirection::In),
+            children: vec![
+                CommentChild::Text(
+                    " This parameter alters the behavior of the function in some way.".into()
+                ),
+            ],
         }));
         assert_eq!(children[7], CommentChild::Paragraph(vec![
             CommentChild::Text(" ".into()),
         ]));
         assert_eq!(children[8], CommentChild::VerbatimCommand(vec![" *nullptr ".into()]));
+
+        assert_eq!(comment.as_markdown(true), [
+            "This is a function.",
+            "",
+            "This function does stuff and then returns an `int` for reasons unknown.",
+            "",
+            "<br /> <a href=\"http://example.com\">More information.</a>",
+            "",
+            "```",
+            "*nullptr",
+            "```",
+            "",
+            "**Template parameters:**",
+            "",
+            "- `T`: This template parameter doesn't actually do anything.",
+            "",
+            "**Parameters:**",
+            "",
+            "- `i`: This parameter alters the behavior of the function in some way.",
+        ].join("\n"));
+        assert!(comment.as_markdown(false).contains("\n\nMore information.\n\n"));
+
+        assert_eq!(comment.as_text(), [
+            "This is a function.",
+            "",
+            "This function does stuff and then returns an int for reasons unknown.",
+            "",
+            "More information.",
+            "",
+            "    *nullptr",
+            "",
+            "Template parameters:",
+            "    T: This template parameter doesn't actually do anything.",
+            "",
+            "Parameters:",
+            "    i: This parameter alters the behavior of the function in some way.",
+        ].join("\n"));
+    });
+
+    let source = r#"
+        /// Returns the sum of two integers.
+        ///
+        /// Overflow is \b undefined and \e must be avoided.
+        ///
+        /// \param a The first integer.
+        /// \param b The second integer.
+        /// \return The sum of \p a and \p b.
+        /// \note This is a note.
+        /// \sa sub
+        int add(int a, int b);
+    "#;
+
+    super::with_entity(&clang, source, |e| {
+        let comment = e.get_children()[0].get_parsed_comment().unwrap();
+
+        let doc = comment.get_doc_comment();
+        assert_eq!(doc, DocComment {
+            brief: Some("Returns the sum of two integers.".into()),
+            details: vec![
+                "Overflow is undefined and must be avoided.".into(),
+                "Note: This is a note.".into(),
+            ],
+            parameters: vec![
+                ("a".into(), "The first integer.".into()),
+                ("b".into(), "The second integer.".into()),
+            ],
+            template_parameters: vec![],
+            returns: Some("The sum of a and b.".into()),
+            see_also: vec!["sub".into()],
+        });
+        assert_eq!(doc.get_parameter("b"), Some("The second integer."));
+        assert_eq!(doc.get_parameter("c"), None);
+        assert_eq!(doc.get_template_parameter("a"), None);
+
+        assert_eq!(comment.as_markdown(true), [
+            "Returns the sum of two integers.",
+            "",
+            "Overflow is **undefined** and *must* be avoided.",
+            "",
+            "**Note:** This is a note.",
+            "",
+            "**Parameters:**",
+            "",
+            "- `a`: The first integer.",
+            "- `b`: The second integer.",
+            "",
+            "**Returns:** The sum of `a` and `b`.",
+            "",
+            "**See also:** sub",
+        ].join("\n"));
+    });
+
+    let source = "
+        /// \\verbatim
+        ///   first
+        ///  \u{a0}second
+        /// \\endverbatim
+        int f();
+    ";
+
+    super::with_entity(&clang, source, |e| {
+        let comment = e.get_children()[0].get_parsed_comment().unwrap();
+        assert!(comment.as_text().contains("\u{a0}second"));
     });
 }
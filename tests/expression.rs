@@ -0,0 +1,124 @@
+use clang::*;
+use clang::expression::*;
+
+fn collect<'tu>(entity: Entity<'tu>, kinds: &[EntityKind]) -> Vec<Entity<'tu>> {
+    let mut entities = vec![];
+    entity.visit_children(|e, _| {
+        if kinds.contains(&e.get_kind()) {
+            entities.push(e);
+        }
+        EntityVisitResult::Recurse
+    });
+    entities
+}
+
+pub fn test(clang: &Clang) {
+    let source = "
+        #define N 42
+        struct S { int x; };
+        void f(int* p, S s) {
+            int a = 1 + 2;
+            a += 3;
+            -a;
+            a++;
+            !a;
+            p->x;
+            s.x;
+            (long) a;
+            static_cast<long>(a);
+            int b = long(a);
+            'a';
+            L'\\x41';
+            \"s\" \"t\\n\";
+            R\"x(raw)x\";
+            1.5f;
+            0x10;
+            010u;
+            true;
+            nullptr;
+            N;
+        }
+    ";
+
+    super::with_translation_unit(clang, "test.cpp", source, &["-std=c++11"], |_, _, tu| {
+        let entity = tu.get_entity();
+
+        let binary = collect(entity, &[EntityKind::BinaryOperator]);
+        let operators = binary.iter().map(|e| get_binary_operator(*e)).collect::<Vec<_>>();
+        assert_eq!(operators, &[Some(BinaryOperator::Add)]);
+
+        let compound = collect(entity, &[EntityKind::CompoundAssignOperator]);
+        let operator = get_binary_operator(compound[0]).unwrap();
+        assert_eq!(operator, BinaryOperator::AddAssign);
+        assert_eq!(operator.get_compound_operator(), Some(BinaryOperator::Add));
+        assert_eq!(operator.get_spelling(), "+=");
+        assert!(operator.is_assignment());
+        assert!(!operator.is_comparison());
+        assert!(!operator.is_logical());
+
+        let unary = collect(entity, &[EntityKind::UnaryOperator]);
+        let operators = unary.iter().map(|e| get_unary_operator(*e)).collect::<Vec<_>>();
+        assert_eq!(operators, &[
+            Some(UnaryOperator::Minus),
+            Some(UnaryOperator::PostIncrement),
+            Some(UnaryOperator::LogicalNot),
+        ]);
+        assert!(UnaryOperator::PostIncrement.is_postfix());
+        assert!(UnaryOperator::PostIncrement.is_increment_or_decrement());
+        assert_eq!(UnaryOperator::PostIncrement.get_spelling(), "++");
+
+        let members = collect(entity, &[EntityKind::MemberRefExpr]);
+        let accesses = members.iter().map(|e| get_member_access(*e)).collect::<Vec<_>>();
+        assert_eq!(accesses, &[Some(MemberAccess::Arrow), Some(MemberAccess::Dot)]);
+
+        let casts = collect(entity, &[
+            EntityKind::CStyleCastExpr, EntityKind::StaticCastExpr, EntityKind::FunctionalCastExpr
+        ]);
+        let kinds = casts.iter().map(|e| get_cast_kind(*e)).collect::<Vec<_>>();
+        assert_eq!(kinds, &[
+            Some(CastKind::CStyle), Some(CastKind::Static), Some(CastKind::Functional)
+        ]);
+
+        let literals = collect(entity, &[
+            EntityKind::IntegerLiteral,
+            EntityKind::FloatingLiteral,
+            EntityKind::CharacterLiteral,
+            EntityKind::StringLiteral,
+            EntityKind::BoolLiteralExpr,
+            EntityKind::NullPtrLiteralExpr,
+        ]);
+        let values = literals.iter().map(|e| get_literal(*e)).collect::<Vec<_>>();
+        assert_eq!(&values[..values.len() - 1], &[
+            Some(Literal::Integer(1)),
+            Some(Literal::Integer(2)),
+            Some(Literal::Integer(3)),
+            Some(Literal::Character(0x61)),
+            Some(Literal::Character(0x41)),
+            Some(Literal::String("st\n".into())),
+            Some(Literal::String("raw".into())),
+            Some(Literal::Float(1.5)),
+            Some(Literal::Integer(16)),
+            Some(Literal::Integer(8)),
+            Some(Literal::Bool(true)),
+            Some(Literal::Nullptr),
+        ]);
+
+        #[cfg(feature="gte_clang_3_9")]
+        fn test_evaluate(value: &Option<Literal>) {
+            assert_eq!(value, &Some(Literal::Integer(42)));
+        }
+
+        #[cfg(not(feature="gte_clang_3_9"))]
+        fn test_evaluate(value: &Option<Literal>) {
+            assert_eq!(value, &None);
+        }
+
+        test_evaluate(&values[values.len() - 1]);
+
+        assert_eq!(get_binary_operator(entity), None);
+        assert_eq!(get_cast_kind(entity), None);
+        assert_eq!(get_literal(entity), None);
+        assert_eq!(get_member_access(entity), None);
+        assert_eq!(get_unary_operator(entity), None);
+    });
+}
@@ -0,0 +1,110 @@
+use clang::*;
+use clang::highlight::*;
+use clang::source::{SourceRange};
+use clang::token::{TokenKind};
+
+pub fn test(clang: &Clang) {
+    let source = "
+        #define SIZE 4
+        namespace n { struct S { int field; }; }
+        static int f(const int p) __attribute__((deprecated));
+        int g(n::S s) { int local = SIZE; return s.field + local + f(1); }
+        // done
+    ";
+
+    super::with_temporary_file("test.cpp", source, |_, f| {
+        let index = Index::new(&clang, false, false);
+        let tu = index.parser(f).detailed_preprocessing_record(true).parse().unwrap();
+        let file = tu.get_file(f).unwrap();
+
+        let range = SourceRange::new(
+            file.get_offset_location(0), file.get_offset_location(source.len() as u32)
+        );
+        let tokens = classify(&tu, &range.tokenize());
+
+        macro_rules! modifiers {
+            ($($modifier:ident), *) => ({
+                #[allow(unused_mut)]
+                let mut modifiers = SemanticModifiers::default();
+                $(modifiers.$modifier = true;)*
+                modifiers
+            });
+        }
+
+        let line = |l: u32| {
+            tokens.iter().filter(|t| {
+                t.token.get_location().get_file_location().line == l
+            }).map(|t| (t.token.get_spelling(), t.kind, t.modifiers)).collect::<Vec<_>>()
+        };
+
+        assert_eq!(line(3), &[
+            ("namespace".into(), SemanticKind::Keyword, modifiers!()),
+            ("n".into(), SemanticKind::Namespace, modifiers!(declaration)),
+            ("struct".into(), SemanticKind::Keyword, modifiers!()),
+            ("S".into(), SemanticKind::Type, modifiers!(declaration)),
+            ("int".into(), SemanticKind::Keyword, modifiers!()),
+            ("field".into(), SemanticKind::Field, modifiers!(declaration)),
+        ]);
+        assert_eq!(line(4)[2], ("f".into(), SemanticKind::Function, modifiers!(
+            declaration, static_, deprecated
+        )));
+        assert_eq!(line(4)[5], ("p".into(), SemanticKind::Parameter, modifiers!(
+            declaration, const_
+        )));
+        assert_eq!(line(5), &[
+            ("int".into(), SemanticKind::Keyword, modifiers!()),
+            ("g".into(), SemanticKind::Function, modifiers!(declaration)),
+            ("n".into(), SemanticKind::Namespace, modifiers!()),
+            ("S".into(), SemanticKind::Type, modifiers!()),
+            ("s".into(), SemanticKind::Parameter, modifiers!(declaration)),
+            ("int".into(), SemanticKind::Keyword, modifiers!()),
+            ("local".into(), SemanticKind::LocalVariable, modifiers!(declaration)),
+            ("SIZE".into(), SemanticKind::Macro, modifiers!()),
+            ("return".into(), SemanticKind::Keyword, modifiers!()),
+            ("s".into(), SemanticKind::Parameter, modifiers!()),
+            ("field".into(), SemanticKind::Field, modifiers!()),
+            ("local".into(), SemanticKind::LocalVariable, modifiers!()),
+            ("f".into(), SemanticKind::Function, modifiers!(static_, deprecated)),
+            ("1".into(), SemanticKind::Number, modifiers!()),
+        ]);
+        assert_eq!(line(6), &[("// done".into(), SemanticKind::Comment, modifiers!())]);
+
+        let line = tokens.iter().filter(|t| {
+            t.token.get_location().get_file_location().line == 5
+        }).cloned().collect::<Vec<_>>();
+        let types = get_lsp_types();
+        let index = |t: &str| types.iter().position(|p| *p == t).unwrap() as u32;
+        assert_eq!(&encode(&line)[..10], &[
+            4, 8, 3, index("keyword"), 0,
+            0, 4, 1, index("function"), 1,
+        ]);
+
+        let html = render_html(&file.tokenize().unwrap(), &tokens);
+        assert!(html.starts_with("<pre class=\"source\">\n        <span class=\"macro"));
+        assert!(html.contains("<span class=\"function declaration static deprecated\">f</span>"));
+        assert!(html.contains("<span class=\"local-variable\">local</span>"));
+        assert!(html.contains("<span class=\"comment\">// done</span>"));
+        assert!(html.ends_with("</pre>"));
+    });
+
+    let source = "int n = 1'000; const char *s = u8\"a\"; char c = 'c'; char d = u8'd';
+const char *r = u8R\"(raw)\"; const wchar_t *w = LR\"(wide)\";";
+    super::with_translation_unit(&clang, "test.cpp", source, &["-std=c++1z"], |_, f, tu| {
+        let file = tu.get_file(f).unwrap();
+        let range = SourceRange::new(
+            file.get_offset_location(0), file.get_offset_location(source.len() as u32)
+        );
+        let tokens = classify(&tu, &range.tokenize());
+        let literals = tokens.iter().filter(|t| {
+            t.token.get_kind() == TokenKind::Literal
+        }).map(|t| (t.token.get_spelling(), t.kind)).collect::<Vec<_>>();
+        assert_eq!(literals, &[
+            ("1'000".into(), SemanticKind::Number),
+            ("u8\"a\"".into(), SemanticKind::String),
+            ("'c'".into(), SemanticKind::String),
+            ("u8'd'".into(), SemanticKind::String),
+            ("u8R\"(raw)\"".into(), SemanticKind::String),
+            ("LR\"(wide)\"".into(), SemanticKind::String),
+        ]);
+    });
+}
@@ -0,0 +1,70 @@
+use clang::*;
+use clang::include_graph::*;
+
+use rustc_serialize::json::{ToJson};
+
+pub fn test(clang: &Clang) {
+    let files = &[
+        ("a.h", "#pragma once\n#include \"b.h\"\nint a();\n"),
+        ("b.h", "#pragma once\n#include \"a.h\"\nint b();\n"),
+        ("c.h", "#pragma once\nint c();\n"),
+        ("unused.h", "#pragma once\nint unused();\n"),
+        ("main.cpp", concat!(
+            "#include \"a.h\"\n",
+            "#include \"c.h\"\n",
+            "#include \"unused.h\"\n",
+            "int main() { return b() + c(); }\n",
+        )),
+    ];
+
+    super::with_temporary_files(files, |_, fs| {
+        let index = Index::new(&clang, false, false);
+        let tu = index.parser(&fs[4]).parse().unwrap();
+        let graph = IncludeGraph::new(&tu).unwrap();
+
+        let file = |i: usize| tu.get_file(&fs[i]).unwrap();
+        let (a, b, c, unused, main) = (file(0), file(1), file(2), file(3), file(4));
+
+        assert_eq!(graph.get_main_file(), main);
+        assert_eq!(graph.get_files(), &[main, a, c, unused, b]);
+
+        let edges = graph.get_all_includes().iter().map(|i| {
+            (i.includer, i.included, i.range.get_start().get_file_location().line)
+        }).collect::<Vec<_>>();
+        assert_eq!(edges, &[(main, a, 1), (main, c, 2), (main, unused, 3), (a, b, 2), (b, a, 2)]);
+
+        let includes = graph.get_includes(main);
+        assert_eq!(includes.len(), 3);
+        assert_eq!(includes[0].directive.get_kind(), EntityKind::InclusionDirective);
+        assert_eq!(includes[0].range, range!(main, 1, 1, 1, 15));
+
+        let includers = graph.get_includers(a).iter().map(|i| i.includer).collect::<Vec<_>>();
+        assert_eq!(includers, &[main, b]);
+
+        let chain = graph.get_include_chain(b).unwrap();
+        let chain = chain.iter().map(|i| (i.includer, i.included)).collect::<Vec<_>>();
+        assert_eq!(chain, &[(main, a), (a, b)]);
+        assert_eq!(graph.get_depth(main), Some(0));
+        assert_eq!(graph.get_depth(c), Some(1));
+        assert_eq!(graph.get_depth(b), Some(2));
+
+        assert_eq!(graph.get_cycles(), &[vec![a, b]]);
+
+        let unused = graph.get_unused_includes(main);
+        assert_eq!(unused.iter().map(|i| i.included).collect::<Vec<_>>(), &[file(3)]);
+
+        let dot = graph.to_dot();
+        assert!(dot.starts_with("digraph includes {\n"));
+        assert!(dot.contains(&format!("    {:?} -> {:?} [label=\"3\"];", fs[4], fs[3])));
+
+        let json = graph.to_json();
+        assert_eq!(json.find("main").unwrap().as_string(), fs[4].to_str());
+        let files = json.find("files").unwrap().as_array().unwrap();
+        assert_eq!(files[4].find("path").unwrap().as_string(), fs[1].to_str());
+        assert_eq!(files[4].find("depth").unwrap().as_u64(), Some(2));
+        let includes = json.find("includes").unwrap().as_array().unwrap();
+        assert_eq!(includes.len(), 5);
+        assert_eq!(includes[3].find("includer").unwrap().as_string(), fs[0].to_str());
+        assert_eq!(includes[3].find("line").unwrap().as_u64(), Some(2));
+    });
+}
@@ -0,0 +1,76 @@
+use clang::*;
+use clang::diagnostic::*;
+use clang::iwyu::*;
+use clang::source::{SourceRange};
+
+pub fn test(clang: &Clang) {
+    let files = &[
+        ("used.h", "struct Used { int x; };\n"),
+        ("ptr.h", "struct Ptr { int y; };\n"),
+        ("unused.h", "int unused();\n"),
+        ("indirect.h", "int indirect();\n"),
+        ("wrapper.h", "#include \"indirect.h\"\n"),
+        ("main.cpp", concat!(
+            "#include \"used.h\"\n",
+            "#include \"ptr.h\"\n",
+            "#include \"unused.h\"\n",
+            "#include \"wrapper.h\"\n",
+            "int main() { Used u; Ptr* p = 0; return u.x + indirect(); }\n",
+        )),
+    ];
+
+    super::with_temporary_files(files, |_, fs| {
+        let index = Index::new(&clang, false, false);
+        let tu = index.parser(&fs[5]).parse().unwrap();
+
+        let file = |i: usize| tu.get_file(&fs[i]).unwrap();
+        let main = file(5);
+
+        let analysis = analyze(&tu, main);
+
+        assert_eq!(analysis.missing.len(), 1);
+        assert_eq!(analysis.missing[0].header, file(3));
+        let symbols = analysis.missing[0].symbols.iter().map(|s| s.get_name()).collect::<Vec<_>>();
+        assert_eq!(symbols, &[Some("indirect".into())]);
+        let location = main.get_location(5, 1);
+        let insertion = FixIt::Insertion(location, "#include \"indirect.h\"\n".into());
+        assert_eq!(analysis.missing[0].fix_it, insertion);
+
+        let unused = analysis.unused.iter().map(|u| u.header).collect::<Vec<_>>();
+        assert_eq!(unused, &[file(1), file(2), file(4)]);
+        assert_eq!(analysis.unused[0].directive.get_kind(), EntityKind::InclusionDirective);
+        assert_eq!(analysis.unused[0].fix_it, FixIt::Deletion(range!(main, 2, 1, 3, 1)));
+        assert_eq!(analysis.unused[2].fix_it, FixIt::Deletion(range!(main, 4, 1, 5, 1)));
+
+        assert_eq!(analysis.forward_declarations.len(), 1);
+        let declaration = &analysis.forward_declarations[0];
+        assert_eq!(declaration.entity.get_name(), Some("Ptr".into()));
+        assert_eq!(declaration.declaration, "struct Ptr;");
+        assert_eq!(declaration.fix_it, FixIt::Insertion(location, "struct Ptr;\n".into()));
+    });
+
+    let files = &[
+        ("ptr.h", "struct Ptr { int y; };\n"),
+        ("indirect.h", "int indirect();\n"),
+        ("wrapper.h", "#include \"indirect.h\"\n"),
+        ("main.cpp", concat!(
+            "#include \"wrapper.h\"\n",
+            "int f() { return indirect(); }\n",
+            "#include \"ptr.h\"",
+        )),
+    ];
+
+    super::with_temporary_files(files, |_, fs| {
+        let index = Index::new(&clang, false, false);
+        let tu = index.parser(&fs[3]).parse().unwrap();
+        let main = tu.get_file(&fs[3]).unwrap();
+
+        let analysis = analyze(&tu, main);
+
+        let location = main.get_offset_location(68);
+        let insertion = FixIt::Insertion(location, "\n#include \"indirect.h\"".into());
+        assert_eq!(analysis.missing[0].fix_it, insertion);
+        let deletion = FixIt::Deletion(SourceRange::new(main.get_location(3, 1), location));
+        assert_eq!(analysis.unused[1].fix_it, deletion);
+    });
+}
@@ -0,0 +1,163 @@
+use clang::*;
+use clang::diagnostic::*;
+use clang::lint::*;
+use clang::token::*;
+
+use rustc_serialize::json::{Json};
+
+struct Todo;
+
+impl Rule for Todo {
+    fn get_name(&self) -> &str {
+        "todo"
+    }
+
+    fn get_severity(&self) -> Severity {
+        Severity::Note
+    }
+
+    fn visit_token<'tu>(&mut self, token: Token<'tu>, reporter: &mut Reporter<'tu>) {
+        if token.get_kind() == TokenKind::Comment && token.get_spelling().contains("TODO") {
+            reporter.report(token.get_range(), "TODO comment");
+        }
+    }
+}
+
+fn get_lines<'a>(findings: &'a [Finding]) -> Vec<(&'a str, u32)> {
+    findings.iter().map(|f| {
+        (&f.rule[..], f.range.get_start().get_file_location().line)
+    }).filter(|&(r, _)| r != "non-const-globals").collect()
+}
+
+pub fn test(clang: &Clang) {
+    #[cfg(feature="gte_clang_3_6")]
+    fn add_non_const_globals(linter: &mut Linter) {
+        linter.add_rule(Box::new(NonConstGlobals::new()));
+    }
+
+    #[cfg(not(feature="gte_clang_3_6"))]
+    fn add_non_const_globals(_: &mut Linter) { }
+
+    #[cfg(feature="gte_clang_3_6")]
+    fn test_non_const_globals(findings: &[Finding]) {
+        let findings = findings.iter().filter(|f| f.rule == "non-const-globals");
+        let findings = findings.collect::<Vec<_>>();
+        assert_eq!(findings.len(), 3);
+        assert_eq!(findings[0].range.get_start().get_file_location().line, 2);
+        assert_eq!(findings[0].message, "non-const global variable `g`");
+        assert_eq!(findings[1].range.get_start().get_file_location().line, 6);
+        assert_eq!(findings[1].message, "non-const global variable `s`");
+        assert_eq!(findings[2].range.get_start().get_file_location().line, 17);
+        assert_eq!(findings[2].message, "non-const global variable `x`");
+    }
+
+    #[cfg(not(feature="gte_clang_3_6"))]
+    fn test_non_const_globals(_: &[Finding]) { }
+
+    let source = "char* strcpy(char* d, const char* s);
+int g = 0;
+const int c = 1;
+extern int e;
+void f(char* a, const char* b) {
+    static int s = 0;
+    int l = 0;
+    strcpy(a, b);
+    strcpy(a, b); // NOLINT(banned-functions)
+    double d = (double) l;
+    // NOLINTNEXTLINE
+    long m = (long) l;
+    char* p = (char*) b;
+    // TODO: remove
+}
+struct T { static int x; };
+int T::x = 0;
+";
+
+    super::with_translation_unit(&clang, "test.cpp", source, &[], |d, _, tu| {
+        let mut linter = Linter::new();
+        linter.add_rule(Box::new(BannedFunctions::new()));
+        linter.add_rule(Box::new(CStyleCasts::new()));
+        linter.add_rule(Box::new(Todo));
+        add_non_const_globals(&mut linter);
+
+        let findings = linter.run(&tu);
+        assert_eq!(get_lines(&findings), &[
+            ("banned-functions", 8),
+            ("c-style-casts", 10),
+            ("c-style-casts", 13),
+            ("todo", 14),
+        ]);
+        test_non_const_globals(&findings);
+
+        let banned = findings.iter().find(|f| f.rule == "banned-functions").unwrap();
+        assert_eq!(banned.severity, Severity::Warning);
+        assert_eq!(banned.message, "call to banned function `strcpy`");
+        assert!(banned.fix_its.is_empty());
+
+        let casts = findings.iter().filter(|f| f.rule == "c-style-casts").collect::<Vec<_>>();
+        assert_eq!(casts[0].fix_its.len(), 2);
+        match (&casts[0].fix_its[0], &casts[0].fix_its[1]) {
+            (&FixIt::Replacement(_, ref replacement), &FixIt::Insertion(_, ref insertion)) => {
+                assert_eq!(replacement, "static_cast<double>(");
+                assert_eq!(insertion, ")");
+            },
+            _ => unreachable!(),
+        }
+        assert!(casts[1].fix_its.is_empty());
+
+        let todo = findings.iter().find(|f| f.rule == "todo").unwrap();
+        assert_eq!(todo.severity, Severity::Note);
+
+        let baseline = Baseline::from_findings(&findings);
+        assert_eq!(baseline.get_count(), findings.len());
+        assert!(baseline.contains(&findings[0]));
+
+        let path = d.join("baseline.json");
+        baseline.save(&path).unwrap();
+        let loaded = Baseline::load(&path).unwrap();
+        assert_eq!(loaded, baseline);
+
+        linter.baseline(loaded);
+        assert_eq!(linter.run(&tu), &[]);
+
+        let known = findings.iter().filter(|f| {
+            f.range.get_start().get_file_location().line != 13
+        }).cloned().collect::<Vec<_>>();
+        let baseline = Baseline::from_findings(&known);
+        assert_eq!(baseline.get_count(), findings.len() - 1);
+        linter.baseline(baseline);
+        assert_eq!(get_lines(&linter.run(&tu)), &[("c-style-casts", 13)]);
+        linter.baseline(Baseline::new());
+
+        let error = linter.configure(&Json::from_str("[]").unwrap()).unwrap_err();
+        assert_eq!(error, ConfigError { rule: None, message: "expected an object".into() });
+
+        let error = linter.configure(&Json::from_str(r#"{"unknown": {}}"#).unwrap()).unwrap_err();
+        assert_eq!(error.to_string(), "unknown rule `unknown`");
+
+        let configuration = r#"{"banned-functions": {"severity": "bad"}}"#;
+        let error = linter.configure(&Json::from_str(configuration).unwrap()).unwrap_err();
+        assert_eq!(error.to_string(), "banned-functions: expected `severity` to be a severity");
+
+        let configuration = r#"{
+            "banned-functions": {"severity": "error", "functions": {"strcpy": "strlcpy"}},
+            "c-style-casts": {"enabled": false}
+        }"#;
+        linter.configure(&Json::from_str(configuration).unwrap()).unwrap();
+        assert_eq!(&linter.get_enabled_rules()[..2], &["banned-functions", "todo"]);
+
+        let findings = linter.run(&tu);
+        assert_eq!(get_lines(&findings), &[("banned-functions", 8), ("todo", 14)]);
+        let banned = findings.iter().find(|f| f.rule == "banned-functions").unwrap();
+        assert_eq!(banned.severity, Severity::Error);
+        assert_eq!(banned.fix_its.len(), 1);
+        match banned.fix_its[0] {
+            FixIt::Replacement(range, ref replacement) => {
+                let start = range.get_start().get_file_location();
+                assert_eq!((start.line, start.column), (8, 5));
+                assert_eq!(replacement, "strlcpy");
+            },
+            _ => unreachable!(),
+        }
+    });
+}
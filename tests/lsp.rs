@@ -0,0 +1,117 @@
+use std::io::{Cursor};
+
+use clang::*;
+use clang::lsp::{self, Server, SymbolKind};
+
+use rustc_serialize::json::{Json};
+
+pub fn test(clang: &Clang) {
+    let source = concat!(
+        "/// An integer.\n",
+        "int a = 322;\n",
+        "struct B { int c; };\n",
+        "int f() { B b; return b. + a; }\n",
+    );
+
+    super::with_temporary_file("test.cpp", source, |_, f| {
+        let uri = lsp::path_to_uri(f);
+        assert_eq!(lsp::uri_to_path(&uri), Some(f.into()));
+
+        let document = format!(r#"{{"uri": {}}}"#, Json::String(uri.clone()));
+        let position = |line, character| format!(
+            r#""textDocument": {}, "position": {{"line": {}, "character": {}}}"#,
+            document,
+            line,
+            character,
+        );
+
+        let requests = vec![
+            r#"{"jsonrpc": "2.0", "id": 0, "method": "initialize", "params": {}}"#.into(),
+            format!(
+                r#"{{"jsonrpc": "2.0", "method": "textDocument/didOpen", "params": {{"textDocument": {{"uri": {}, "languageId": "cpp", "version": 1, "text": {}}}}}}}"#,
+                Json::String(uri.clone()),
+                Json::String(source.into()),
+            ),
+            format!(
+                r#"{{"jsonrpc": "2.0", "id": 1, "method": "textDocument/completion", "params": {{{}}}}}"#,
+                position(3, 24),
+            ),
+            format!(
+                r#"{{"jsonrpc": "2.0", "id": 2, "method": "textDocument/hover", "params": {{{}}}}}"#,
+                position(3, 27),
+            ),
+            format!(
+                r#"{{"jsonrpc": "2.0", "id": 3, "method": "textDocument/definition", "params": {{{}}}}}"#,
+                position(3, 27),
+            ),
+            format!(
+                r#"{{"jsonrpc": "2.0", "id": 4, "method": "textDocument/documentSymbol", "params": {{"textDocument": {}}}}}"#,
+                document,
+            ),
+            r#"{"jsonrpc": "2.0", "id": 5, "method": "unknown"}"#.into(),
+            r#"{"jsonrpc": "2.0", "id": 6, "method": "shutdown"}"#.into(),
+            r#"{"jsonrpc": "2.0", "method": "exit"}"#.into(),
+        ];
+
+        let mut input = vec![];
+        for request in &requests {
+            lsp::write_message(&mut input, &Json::from_str(request).unwrap()).unwrap();
+        }
+
+        let index = Index::new(&clang, false, false);
+        let mut server = Server::new(&index, &["-std=c++11"]);
+        let mut output = vec![];
+        assert!(server.run(&mut Cursor::new(input), &mut output).unwrap());
+        assert!(server.is_shutdown());
+        assert!(server.is_exited());
+
+        let mut output = Cursor::new(output);
+        let mut messages = vec![];
+        while let Some(message) = lsp::read_message(&mut output).unwrap() {
+            messages.push(message);
+        }
+        assert_eq!(messages.len(), 8);
+
+        let capabilities = messages[0].find_path(&["result", "capabilities"]).unwrap();
+        assert_eq!(capabilities.find("hoverProvider"), Some(&Json::Boolean(true)));
+
+        let method = messages[1].find("method").unwrap().as_string();
+        assert_eq!(method, Some("textDocument/publishDiagnostics"));
+        let diagnostics = messages[1].find_path(&["params", "diagnostics"]).unwrap();
+        let diagnostics = diagnostics.as_array().unwrap();
+        assert_eq!(diagnostics.len(), 1);
+        assert_eq!(diagnostics[0].find("severity").unwrap().as_u64(), Some(1));
+        assert_eq!(diagnostics[0].find_path(&["range", "start", "line"]).unwrap().as_u64(), Some(3));
+
+        let items = messages[2].find_path(&["result", "items"]).unwrap().as_array().unwrap();
+        let item = items.iter().find(|i| i.find("label").unwrap().as_string() == Some("c")).unwrap();
+        assert_eq!(item.find("kind").unwrap().as_u64(), Some(5));
+        assert_eq!(item.find("detail").unwrap().as_string(), Some("int"));
+        assert_eq!(item.find("insertText").unwrap().as_string(), Some("c"));
+
+        let contents = messages[3].find_path(&["result", "contents", "value"]).unwrap();
+        assert_eq!(contents.as_string(), Some("```cpp\nint a\n```\n\nAn integer."));
+
+        let location = messages[4].find("result").unwrap();
+        assert_eq!(location.find("uri").unwrap().as_string(), Some(&uri[..]));
+        assert_eq!(location.find_path(&["range", "start", "line"]).unwrap().as_u64(), Some(1));
+        assert_eq!(location.find_path(&["range", "start", "character"]).unwrap().as_u64(), Some(4));
+
+        let symbols = messages[5].find("result").unwrap().as_array().unwrap();
+        let symbols = symbols.iter().map(|s| {
+            let name = s.find("name").unwrap().as_string().unwrap().to_string();
+            let kind = s.find("kind").unwrap().as_u64().unwrap();
+            let container = s.find("containerName").and_then(|c| c.as_string()).map(|c| c.to_string());
+            (name, kind, container)
+        }).collect::<Vec<_>>();
+        assert_eq!(symbols, &[
+            ("a".into(), SymbolKind::Variable as u64, None),
+            ("B".into(), SymbolKind::Struct as u64, None),
+            ("c".into(), SymbolKind::Field as u64, Some("B".into())),
+            ("f".into(), SymbolKind::Function as u64, None),
+        ]);
+
+        assert_eq!(messages[6].find_path(&["error", "code"]).unwrap().as_i64(), Some(-32601));
+        assert_eq!(messages[7].find("result"), Some(&Json::Null));
+    });
+}
@@ -0,0 +1,89 @@
+use clang::*;
+use clang::metrics::*;
+
+use rustc_serialize::json::{ToJson};
+
+pub fn test(clang: &Clang) {
+    let source = "// A function.
+int f(int a, int b) {
+    // Comment.
+    if (a && b) {
+        while (a) {
+            a--;
+        }
+    } else if (b) {
+        return b;
+    }
+    return a ? 1 : 0;
+}
+
+struct S {
+    void m() { }
+};
+";
+
+    super::with_translation_unit(&clang, "test.cpp", source, &[], |_, f, tu| {
+        let report = Report::new(&tu).unwrap();
+        assert_eq!(report.files.len(), 1);
+
+        let file = &report.files[0];
+        assert_eq!(file.path, f.to_path_buf());
+        assert_eq!(file.lines, 16);
+        assert_eq!(file.code_lines, 13);
+        assert_eq!(file.comment_lines, 2);
+
+        assert_eq!(file.functions, &[
+            FunctionMetrics {
+                name: "f(int, int)".into(),
+                path: f.to_path_buf(),
+                line: 2,
+                lines: 11,
+                code_lines: 10,
+                comment_lines: 1,
+                statements: 6,
+                parameters: 2,
+                complexity: 6,
+                nesting: 2,
+            },
+            FunctionMetrics {
+                name: "m()".into(),
+                path: f.to_path_buf(),
+                line: 15,
+                lines: 1,
+                code_lines: 1,
+                comment_lines: 0,
+                statements: 0,
+                parameters: 0,
+                complexity: 1,
+                nesting: 0,
+            },
+        ]);
+        assert_eq!(report.get_functions().len(), 2);
+
+        let summary = report.get_summary();
+        assert_eq!(summary, file.get_summary());
+        assert_eq!(summary, Summary {
+            files: 1,
+            functions: 2,
+            lines: 16,
+            code_lines: 13,
+            comment_lines: 2,
+            statements: 6,
+            complexity: 7,
+            max_complexity: 6,
+            max_nesting: 2,
+            max_parameters: 2,
+        });
+        assert_eq!(summary.get_average_complexity(), 3.5);
+        assert_eq!(summary.get_comment_density(), 0.125);
+
+        let json = report.to_json();
+        assert_eq!(json.find_path(&["summary", "max_complexity"]).unwrap().as_u64(), Some(6));
+        let files = json.find("files").unwrap().as_array().unwrap();
+        let functions = files[0].find("functions").unwrap().as_array().unwrap();
+        assert_eq!(functions[1].find("name").unwrap().as_string(), Some("m()"));
+
+        let declaration = tu.get_entity().get_children()[1];
+        assert_eq!(FunctionMetrics::new(declaration), None);
+    });
+}
@@ -0,0 +1,170 @@
+use clang::*;
+use clang::diagnostic::*;
+use clang::lint::*;
+use clang::naming::*;
+
+use rustc_serialize::json::{Json};
+
+fn get_edits(violation: &Violation) -> Vec<(String, u32, u32)> {
+    violation.edits.iter().map(|e| match *e {
+        FixIt::Replacement(range, ref replacement) => {
+            let location = range.get_start().get_file_location();
+            let name = location.file.get_path().file_name().unwrap().to_str().unwrap().to_string();
+            assert_eq!(Some(replacement), violation.suggestion.as_ref());
+            (name, location.line, location.column)
+        },
+        _ => unreachable!(),
+    }).collect()
+}
+
+pub fn test(clang: &Clang) {
+    assert_eq!(CaseStyle::from_name("snake_case"), Some(CaseStyle::SnakeCase));
+    assert_eq!(CaseStyle::from_name("snake"), None);
+    assert_eq!(CaseStyle::LowerCamelCase.get_name(), "camelBack");
+
+    assert_eq!(CaseStyle::CamelCase.convert("http_server"), "HttpServer");
+    assert_eq!(CaseStyle::CamelCase.convert("HTTPServer"), "HttpServer");
+    assert_eq!(CaseStyle::LowerCamelCase.convert("Get_Value"), "getValue");
+    assert_eq!(CaseStyle::SnakeCase.convert("fooBar2Baz"), "foo_bar2_baz");
+    assert_eq!(CaseStyle::UpperCase.convert("maxSize"), "MAX_SIZE");
+
+    assert!(CaseStyle::SnakeCase.is_match("foo_bar"));
+    assert!(!CaseStyle::SnakeCase.is_match("foo__bar"));
+    assert!(!CaseStyle::SnakeCase.is_match("fooBar"));
+    assert!(CaseStyle::CamelCase.is_match("Vector3"));
+    assert!(!CaseStyle::UpperCase.is_match(""));
+
+    assert_eq!(NameKind::from_name("const-methods"), Some(NameKind::ConstMethod));
+    assert_eq!(NameKind::ConstMethod.get_fallback(), Some(NameKind::Method));
+    assert_eq!(NameKind::Type.get_fallback(), None);
+
+    let convention = Convention::from_json(&Json::from_str(r#"{
+        "case": "snake_case", "prefix": "m_"
+    }"#).unwrap()).unwrap();
+    assert!(convention.is_match("m_count"));
+    assert!(!convention.is_match("count"));
+    assert!(!convention.is_match("m_Count"));
+    assert_eq!(convention.suggest("count"), Some("m_count".into()));
+    assert_eq!(convention.suggest("m_Count"), Some("m_count".into()));
+    assert_eq!(convention.suggest("m_count"), None);
+
+    let convention = Convention::from_json(&Json::String("UPPER_CASE".into())).unwrap();
+    assert_eq!(convention.case, Some(CaseStyle::UpperCase));
+
+    let error = Convention::from_json(&Json::String("Upper".into())).unwrap_err();
+    assert_eq!(error, "unknown case style `Upper`");
+    let error = Convention::from_json(&Json::from_str(r#"{"case": 1}"#).unwrap()).unwrap_err();
+    assert_eq!(error, "expected a string for `case`");
+
+    let header = "#define maxSize 10
+int GlobalCounter = 0;
+void DoThing(int a);
+";
+    let source = r#"#include "header.hpp"
+const int limit = maxSize;
+class Widget {
+    int Count;
+public:
+    int getValue() const { return Count; }
+};
+void DoThing(int a) {
+    GlobalCounter += a;
+}
+void Run() { DoThing(1); }
+enum Color { red };
+"#;
+
+    let files = &[("header.hpp", header), ("main.cpp", source)];
+    super::with_temporary_files(files, |_, fs| {
+        let index = Index::new(clang, false, false);
+        let tu = index.parser(&fs[1]).detailed_preprocessing_record(true).parse().unwrap();
+
+        let mut conventions = NamingConventions::new();
+        assert!(conventions.check(&tu).is_empty());
+
+        let configuration = r#"{
+            "types": "CamelCase",
+            "functions": "snake_case",
+            "private-members": {"case": "snake_case", "prefix": "m_"},
+            "globals": {"case": "snake_case", "prefix": "g_"},
+            "constants": "UPPER_CASE",
+            "macros": "UPPER_CASE",
+            "enum-constants": {"pattern": "^k[A-Z]"}
+        }"#;
+        conventions.configure(&Json::from_str(configuration).unwrap()).unwrap();
+        assert!(conventions.get_convention(NameKind::ConstMethod).is_some());
+        assert!(conventions.get_convention(NameKind::Member).is_none());
+
+        let violations = conventions.check(&tu);
+        assert_eq!(violations.len(), 8);
+
+        let find = |name: &str| violations.iter().find(|v| v.name == name).unwrap();
+
+        let violation = find("maxSize");
+        assert_eq!(violation.kind, NameKind::Macro);
+        assert_eq!(violation.suggestion, Some("MAX_SIZE".into()));
+        assert_eq!(get_edits(violation), &[
+            ("header.hpp".to_string(), 1, 9), ("main.cpp".to_string(), 2, 19)
+        ]);
+
+        let violation = find("GlobalCounter");
+        assert_eq!(violation.kind, NameKind::Global);
+        assert_eq!(violation.suggestion, Some("g_global_counter".into()));
+        assert_eq!(get_edits(violation), &[
+            ("header.hpp".to_string(), 2, 5), ("main.cpp".to_string(), 9, 5)
+        ]);
+
+        let violation = find("DoThing");
+        assert_eq!(violation.kind, NameKind::Function);
+        assert_eq!(violation.suggestion, Some("do_thing".into()));
+        assert_eq!(get_edits(violation), &[
+            ("header.hpp".to_string(), 3, 6),
+            ("main.cpp".to_string(), 8, 6),
+            ("main.cpp".to_string(), 11, 14),
+        ]);
+
+        let violation = find("limit");
+        assert_eq!(violation.kind, NameKind::Constant);
+        assert_eq!(get_edits(violation), &[("main.cpp".to_string(), 2, 11)]);
+
+        let violation = find("Count");
+        assert_eq!(violation.kind, NameKind::PrivateMember);
+        assert_eq!(violation.suggestion, Some("m_count".into()));
+        assert_eq!(get_edits(violation), &[
+            ("main.cpp".to_string(), 4, 9), ("main.cpp".to_string(), 6, 35)
+        ]);
+
+        let violation = find("getValue");
+        assert_eq!(violation.kind, NameKind::ConstMethod);
+        assert_eq!(violation.suggestion, Some("get_value".into()));
+
+        let violation = find("Run");
+        assert_eq!(violation.kind, NameKind::Function);
+        assert_eq!(violation.suggestion, Some("run".into()));
+
+        let violation = find("red");
+        assert_eq!(violation.kind, NameKind::EnumConstant);
+        assert_eq!(violation.suggestion, None);
+        assert!(violation.edits.is_empty());
+
+        let mut linter = Linter::new();
+        linter.add_rule(Box::new(NamingConventions::new()));
+        let configuration = format!(r#"{{"naming-conventions": {}}}"#, configuration);
+        linter.configure(&Json::from_str(&configuration).unwrap()).unwrap();
+
+        let findings = linter.run(&tu);
+        assert_eq!(findings.len(), 8);
+        let finding = findings.iter().find(|f| f.message.contains("`Run`")).unwrap();
+        assert_eq!(finding.range.get_start().get_file_location().line, 11);
+        assert_eq!(
+            finding.message,
+            "name `Run` does not follow the `functions` naming convention (suggested name: `run`)"
+        );
+        assert_eq!(finding.fix_its.len(), 1);
+
+        let configuration = r#"{"naming-conventions": {"types": {"pattern": "("}}}"#;
+        let error = linter.configure(&Json::from_str(configuration).unwrap()).unwrap_err();
+        assert_eq!(error.rule, Some("naming-conventions".into()));
+        assert!(error.message.starts_with("invalid convention for `types`: "));
+    });
+}
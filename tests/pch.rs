@@ -0,0 +1,53 @@
+use std::fs;
+use std::thread;
+use std::io::{Write};
+use std::time::{Duration};
+
+use clang::*;
+use clang::pch::*;
+
+pub fn test(clang: &Clang) {
+    let files = &[
+        ("dependency.h", "int dependency();\n"),
+        ("header.h", "#include \"dependency.h\"\nint header();\n"),
+        ("main.cpp", "int main() { return header() + dependency(); }\n"),
+    ];
+
+    super::with_temporary_files(files, |d, paths| {
+        let index = Index::new(&clang, false, false);
+
+        let mut builder = PchBuilder::new(&paths[1], d.join("header.pch"));
+        builder.arguments(&["-x", "c++-header"]);
+        assert_eq!(builder.get_header(), paths[1].as_path());
+        assert_eq!(builder.get_output(), d.join("header.pch").as_path());
+        assert!(builder.is_stale());
+
+        assert_eq!(builder.update(&index), Ok(true));
+        assert!(!builder.is_stale());
+        assert_eq!(builder.update(&index), Ok(false));
+
+        let dependencies = builder.get_dependencies().keys().collect::<Vec<_>>();
+        assert_eq!(dependencies, &[&paths[0], &paths[1]]);
+
+        let arguments = builder.get_arguments();
+        assert_eq!(arguments[0], "-include-pch");
+        let tu = index.parser(&paths[2]).arguments(&arguments).parse().unwrap();
+        assert!(tu.get_diagnostics().is_empty());
+
+        fs::remove_file(d.join("header.pch")).unwrap();
+        assert!(builder.is_stale());
+        assert_eq!(builder.update(&index), Ok(true));
+
+        builder.arguments(&["-x", "c++-header", "-DVALUE=1"]);
+        assert!(builder.is_stale());
+        assert_eq!(builder.update(&index), Ok(true));
+        assert!(!builder.is_stale());
+
+        thread::sleep(Duration::from_secs(1));
+        fs::File::create(&paths[0]).unwrap().write_all(b"int dependency(int = 0);\n").unwrap();
+        assert!(builder.is_stale());
+        assert_eq!(builder.update(&index), Ok(true));
+        assert!(!builder.is_stale());
+        assert_eq!(builder.update(&index), Ok(false));
+    });
+}
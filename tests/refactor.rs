@@ -0,0 +1,163 @@
+use std::fs;
+use std::io::{Read};
+
+use clang::*;
+use clang::refactor::*;
+use clang::source::*;
+
+fn preview(edits: &EditSet) -> String {
+    let files = edits.preview().unwrap();
+    assert_eq!(files.len(), 1);
+    files.into_iter().next().unwrap().1
+}
+
+pub fn test(clang: &Clang) {
+    let source = "int square(int x) { return x * x; }
+int twice(int x) { return 2 * x; }
+int add(int a, int b, int unused) { return a + b; }
+int compute(int n) {
+    int total = 0;
+    int values[3] = {1, 2, 3};
+    for (int i = 0; i < n; ++i) {
+        total += values[i % 3];
+    }
+    return twice(total) + add(1, 2, 3) + twice(n + 1) + square(n) + square(n + 1);
+}
+";
+
+    super::with_translation_unit(clang, "test.cpp", source, &[], |_, f, tu| {
+        let file = tu.get_file(f).unwrap();
+        let range = |s: (u32, u32), e: (u32, u32)| {
+            SourceRange::new(file.get_location(s.0, s.1), file.get_location(e.0, e.1))
+        };
+        let function = |name: &str| {
+            let children = tu.get_entity().get_children();
+            children.into_iter().find(|c| c.get_name() == Some(name.into())).unwrap()
+        };
+
+        let edits = extract_function(&tu, range((7, 5), (9, 6)), "accumulate").unwrap();
+        assert_eq!(edits.get_edits().values().next().unwrap().len(), 2);
+        assert_eq!(preview(&edits), "int square(int x) { return x * x; }
+int twice(int x) { return 2 * x; }
+int add(int a, int b, int unused) { return a + b; }
+static void accumulate(int n, int &total, int *values) {
+    for (int i = 0; i < n; ++i) {
+        total += values[i % 3];
+    }
+}
+
+int compute(int n) {
+    int total = 0;
+    int values[3] = {1, 2, 3};
+    accumulate(n, total, values);
+    return twice(total) + add(1, 2, 3) + twice(n + 1) + square(n) + square(n + 1);
+}
+");
+
+        let error = extract_function(&tu, range((10, 5), (10, 84)), "f").unwrap_err();
+        assert_eq!(error, RefactorError::ControlFlow);
+        let error = extract_function(&tu, range((5, 5), (5, 19)), "f").unwrap_err();
+        assert_eq!(error, RefactorError::EscapingDeclaration);
+        let error = extract_function(&tu, range((7, 5), (8, 10)), "f").unwrap_err();
+        assert_eq!(error, RefactorError::InvalidRange);
+
+        let edits = inline_function(function("twice")).unwrap();
+        let contents = preview(&edits);
+        assert_eq!(contents.lines().nth(9), Some(
+            "    return (2 * total) + add(1, 2, 3) + (2 * (n + 1)) + square(n) + square(n + 1);"
+        ));
+
+        let mut calls = vec![];
+        function("compute").visit_children(|e, _| {
+            if e.get_kind() == EntityKind::CallExpr && e.get_name() == Some("square".into()) {
+                calls.push(e);
+            }
+            EntityVisitResult::Recurse
+        });
+        assert_eq!(calls.len(), 2);
+
+        let contents = preview(&inline_call(calls[0]).unwrap());
+        assert!(contents.lines().nth(9).unwrap().ends_with("+ (n * n) + square(n + 1);"));
+        assert_eq!(inline_call(calls[1]).unwrap_err(), RefactorError::ArgumentEvaluation);
+        assert_eq!(inline_function(function("add")).unwrap_err(), RefactorError::NotTrivial);
+
+        let add = function("add");
+        let contents = preview(&remove_parameter(add, 2).unwrap());
+        assert_eq!(contents.lines().nth(2), Some("int add(int a, int b) { return a + b; }"));
+        assert!(contents.contains("+ add(1, 2) +"));
+        assert_eq!(remove_parameter(add, 0).unwrap_err(), RefactorError::ParameterUsed);
+        assert_eq!(remove_parameter(add, 3).unwrap_err(), RefactorError::InvalidIndex);
+
+        let contents = preview(&add_parameter(add, 0, "int c", "0").unwrap());
+        let line = "int add(int c, int a, int b, int unused) { return a + b; }";
+        assert_eq!(contents.lines().nth(2), Some(line));
+        assert!(contents.contains("+ add(0, 1, 2, 3) +"));
+
+        let edits = add_parameter(add, 3, "bool flag", "true").unwrap();
+        let contents = preview(&edits);
+        let line = "int add(int a, int b, int unused, bool flag) { return a + b; }";
+        assert_eq!(contents.lines().nth(2), Some(line));
+        assert!(contents.contains("+ add(1, 2, 3, true) +"));
+        assert_eq!(add_parameter(add, 4, "int d", "0").unwrap_err(), RefactorError::InvalidIndex);
+
+        edits.apply().unwrap();
+        let mut written = String::new();
+        fs::File::open(f).unwrap().read_to_string(&mut written).unwrap();
+        assert_eq!(written, contents);
+        assert!(EditSet::new().preview().unwrap().is_empty());
+    });
+
+    let source = "struct S { int x; };
+void f(int n) {
+    int count = 0;
+    struct S s = {0};
+    count += n;
+    s.x = count;
+    n = count + s.x;
+}
+";
+
+    super::with_translation_unit(clang, "test.c", source, &[], |_, f, tu| {
+        let file = tu.get_file(f).unwrap();
+        let range = SourceRange::new(file.get_location(5, 5), file.get_location(6, 16));
+        let edits = extract_function(&tu, range, "g").unwrap();
+        assert_eq!(preview(&edits), "struct S { int x; };
+static void g(int *count, int n, struct S *s) {
+    (*count) += n;
+    (*s).x = (*count);
+}
+
+void f(int n) {
+    int count = 0;
+    struct S s = {0};
+    g(&count, n, &s);
+    n = count + s.x;
+}
+");
+    });
+
+    let source = "struct P { int x; int get() const; void set(int v); };
+void f() {
+    P a, b;
+    int n = a.get();
+    b.set(n);
+}
+";
+
+    super::with_translation_unit(clang, "test.cpp", source, &[], |_, f, tu| {
+        let file = tu.get_file(f).unwrap();
+        let range = SourceRange::new(file.get_location(4, 5), file.get_location(5, 13));
+        let edits = extract_function(&tu, range, "g").unwrap();
+        assert_eq!(preview(&edits), "struct P { int x; int get() const; void set(int v); };
+static void g(const P &a, P &b) {
+    int n = a.get();
+    b.set(n);
+}
+
+void f() {
+    P a, b;
+    g(a, b);
+}
+");
+    });
+}
@@ -0,0 +1,91 @@
+use clang::*;
+use clang::rename::*;
+
+fn apply(rename: &Rename, name: &str, file: &str, contents: &str) -> String {
+    let edits = rename.get_edits(name);
+    let edits = edits.iter().find(|&(p, _)| p.ends_with(file)).unwrap().1;
+    let mut contents = contents.to_string();
+    for edit in edits.iter().rev() {
+        edit.apply(&mut contents);
+    }
+    contents
+}
+
+pub fn test(clang: &Clang) {
+    let header = "class Widget {
+public:
+    Widget();
+    ~Widget();
+    int size;
+};
+";
+    let system = "extern int sys_value;\n";
+    let source = "#include \"header.hpp\"
+#include <sys.hpp>
+Widget::Widget() : size(0) { }
+Widget::~Widget() { }
+int f(Widget* w) {
+    Widget local;
+    return w->size + local.size + sys_value;
+}
+int value = 0;
+#define GET() value
+int get() { return GET(); }
+";
+
+    let files = &[("header.hpp", header), ("sys.hpp", system), ("main.cpp", source)];
+    super::with_temporary_files(files, |d, fs| {
+        let index = Index::new(clang, false, false);
+        let argument = format!("-isystem{}", d.display());
+        let tu = index.parser(&fs[2]).arguments(&[argument]).parse().unwrap();
+        let file = tu.get_file(&fs[2]).unwrap();
+
+        let rename = Rename::from_location(file.get_location(3, 9)).unwrap();
+        assert_eq!(rename.get_entity().get_kind(), EntityKind::ClassDecl);
+        assert_eq!(rename.get_name(), "Widget");
+        assert_eq!(rename.get_ranges().len(), 9);
+        assert_eq!(rename.get_fix_its("Gadget").len(), 9);
+
+        assert_eq!(apply(&rename, "Gadget", "header.hpp", header), "class Gadget {
+public:
+    Gadget();
+    ~Gadget();
+    int size;
+};
+");
+        assert_eq!(apply(&rename, "Gadget", "main.cpp", source), "#include \"header.hpp\"
+#include <sys.hpp>
+Gadget::Gadget() : size(0) { }
+Gadget::~Gadget() { }
+int f(Gadget* w) {
+    Gadget local;
+    return w->size + local.size + sys_value;
+}
+int value = 0;
+#define GET() value
+int get() { return GET(); }
+");
+
+        let rename = Rename::from_location(file.get_location(7, 15)).unwrap();
+        assert_eq!(rename.get_entity().get_kind(), EntityKind::FieldDecl);
+        let ranges = rename.get_ranges().iter().map(|r| {
+            let location = r.get_start().get_file_location();
+            (location.line, location.column)
+        }).collect::<Vec<_>>();
+        assert_eq!(ranges, &[(5, 9), (3, 20), (7, 15), (7, 28)]);
+
+        let function = file.get_location(11, 5).get_entity().unwrap();
+        let rename = Rename::new(function).unwrap();
+        assert_eq!(rename.get_ranges().len(), 1);
+
+        let error = Rename::from_location(file.get_location(7, 35)).unwrap_err();
+        assert_eq!(error, RenameError::SystemHeader);
+
+        let error = Rename::from_location(file.get_location(9, 5)).unwrap_err();
+        assert_eq!(error, RenameError::MacroExpansion);
+        assert_eq!(error.to_string(), "a macro expansion makes the rename ambiguous");
+
+        let error = Rename::from_location(file.get_location(7, 5)).unwrap_err();
+        assert_eq!(error, RenameError::NotFound);
+    });
+}
@@ -0,0 +1,82 @@
+use std::path::{Path};
+
+use clang::*;
+use clang::symbol_index::*;
+
+pub fn test(clang: &Clang) {
+    let files = &[
+        ("main.cpp", "#include \"shape.h\"\nint main() { Square s; return s.area(); }\n"),
+        ("shape.h", concat!(
+            "struct Shape { virtual int area() const = 0; };\n",
+            "struct Square : Shape { int side; int area() const override; };\n",
+        )),
+        ("square.cpp", "#include \"shape.h\"\nint Square::area() const { return side * side; }\n"),
+    ];
+
+    super::with_temporary_files(files, |d, fs| {
+        let index = Index::new(&clang, false, false);
+        let mut symbols = SymbolIndex::new();
+        for f in &[&fs[0], &fs[2]] {
+            symbols.add(&index.parser(f).arguments(&["-std=c++11"]).parse().unwrap());
+        }
+
+        let occurrence = |path: &Path, line, column, role| {
+            Occurrence { path: path.into(), line: line, column: column, role: role }
+        };
+
+        let shape = symbols.find_symbols("Shape")[0].clone();
+        assert_eq!(shape.kind, EntityKind::StructDecl);
+        assert_eq!(symbols.get_symbol(&shape.usr), Some(&shape));
+        assert_eq!(symbols.find_references(&shape.usr), &[
+            &occurrence(&fs[1], 2, 17, Role::Reference)
+        ]);
+
+        let subclasses = symbols.find_subclasses(&shape.usr);
+        assert_eq!(subclasses.iter().map(|s| &s.name[..]).collect::<Vec<_>>(), &["Square"]);
+        let square = subclasses[0].clone();
+        assert_eq!(square.bases, &[shape.usr.clone()]);
+        assert_eq!(square.occurrences, &[
+            occurrence(&fs[0], 2, 14, Role::Reference),
+            occurrence(&fs[1], 2, 8, Role::Definition),
+            occurrence(&fs[2], 2, 5, Role::Reference),
+        ]);
+
+        let areas = symbols.find_symbols("area");
+        assert_eq!(areas.len(), 2);
+        let base = areas.iter().find(|a| a.overrides.is_empty()).unwrap();
+        let derived = areas.iter().find(|a| !a.overrides.is_empty()).unwrap();
+        assert_eq!(base.kind, EntityKind::Method);
+        assert_eq!(derived.overrides, &[base.usr.clone()]);
+        assert_eq!(symbols.find_overrides(&base.usr), &[*derived]);
+        assert_eq!(symbols.find_overrides(&derived.usr), &[] as &[&Symbol]);
+        assert_eq!(derived.occurrences, &[
+            occurrence(&fs[0], 2, 33, Role::Reference),
+            occurrence(&fs[1], 2, 39, Role::Declaration),
+            occurrence(&fs[2], 2, 13, Role::Definition),
+        ]);
+        assert_eq!(symbols.find_definition(&derived.usr), Some(&derived.occurrences[2]));
+        assert_eq!(symbols.find_declarations(&derived.usr), &[&derived.occurrences[1]]);
+        assert_eq!(symbols.find_definition(&base.usr), None);
+
+        let side = symbols.find_symbols("side")[0];
+        assert_eq!(symbols.find_references(&side.usr), &[
+            &occurrence(&fs[2], 2, 35, Role::Reference),
+            &occurrence(&fs[2], 2, 42, Role::Reference),
+        ]);
+
+        let file = d.join("index.bin");
+        symbols.save(&file).unwrap();
+        let loaded = SymbolIndex::load(&file).unwrap();
+        assert_eq!(loaded, symbols);
+
+        let mut merged = SymbolIndex::new();
+        merged.merge(loaded.clone());
+        merged.merge(loaded);
+        assert_eq!(merged, symbols);
+
+        symbols.remove_file(&fs[0]);
+        assert!(symbols.find_symbols("main").is_empty());
+        assert_eq!(symbols.get_symbol(&square.usr).unwrap().occurrences.len(), 2);
+        assert!(SymbolIndex::load(&fs[0]).is_err());
+    });
+}
@@ -4,12 +4,14 @@ extern crate lazy_static;
 extern crate clang;
 extern crate clang_sys;
 extern crate libc;
+extern crate rustc_serialize;
 
 use std::env;
 use std::fs;
 use std::mem;
 use std::io::{Write};
 use std::path::{Path, PathBuf};
+use std::sync::{Arc};
 use std::sync::atomic::{AtomicUsize, Ordering};
 
 use clang::*;
@@ -115,12 +117,50 @@ mod source_test;
 #[path="token.rs"]
 mod token_test;
 
+#[path="batch.rs"]
+mod batch_test;
+#[path="browser.rs"]
+mod browser_test;
+#[path="call_graph.rs"]
+mod call_graph_test;
+#[path="control_flow.rs"]
+mod control_flow_test;
+#[path="dedup.rs"]
+mod dedup_test;
+#[path="doccheck.rs"]
+mod doccheck_test;
+#[path="document.rs"]
+mod document_test;
+#[path="expression.rs"]
+mod expression_test;
+#[path="highlight.rs"]
+mod highlight_test;
+#[path="include_graph.rs"]
+mod include_graph_test;
+#[path="iwyu.rs"]
+mod iwyu_test;
+#[path="lint.rs"]
+mod lint_test;
+#[path="lsp.rs"]
+mod lsp_test;
+#[path="metrics.rs"]
+mod metrics_test;
+#[path="naming.rs"]
+mod naming_test;
+#[path="pch.rs"]
+mod pch_test;
+#[path="refactor.rs"]
+mod refactor_test;
+#[path="rename.rs"]
+mod rename_test;
 #[path="sonar.rs"]
 mod sonar_test;
+#[path="symbol_index.rs"]
+mod symbol_index_test;
 
 #[test]
 fn test() {
-    let clang = Clang::new().unwrap();
+    let clang = Arc::new(Clang::new().unwrap());
 
     println!("libclang: {}", get_version());
 
@@ -130,7 +170,26 @@ fn test() {
     source_test::test(&clang);
     token_test::test(&clang);
 
+    batch_test::test(&clang);
+    browser_test::test(&clang);
+    call_graph_test::test(&clang);
+    control_flow_test::test(&clang);
+    dedup_test::test(&clang);
+    doccheck_test::test(&clang);
+    document_test::test(&clang);
+    expression_test::test(&clang);
+    highlight_test::test(&clang);
+    include_graph_test::test(&clang);
+    iwyu_test::test(&clang);
+    lint_test::test(&clang);
+    lsp_test::test(&clang);
+    metrics_test::test(&clang);
+    naming_test::test(&clang);
+    pch_test::test(&clang);
+    refactor_test::test(&clang);
+    rename_test::test(&clang);
     sonar_test::test(&clang);
+    symbol_index_test::test(&clang);
 
     // Entity ____________________________________
 
@@ -699,6 +758,26 @@ fn test() {
     index.set_thread_options(options);
     assert_eq!(index.get_thread_options(), options);
 
+    //- parse_string -----------------------------
+
+    let tu = index.parse_string("test.c", "int a = 322;", Language::C, &["-std=c99"]).unwrap();
+    assert_eq!(tu.get_entity().get_name(), Some("/__clang_virtual__/test.c".into()));
+    assert!(tu.get_diagnostics().is_empty());
+    let children = tu.get_entity().get_children();
+    assert_eq!(children.len(), 1);
+    assert_eq!(children[0].get_language(), Some(Language::C));
+
+    let headers = &[("a.hpp", "#include <b/b.hpp>\nint a();\n"), ("b/b.hpp", "int b();\n")];
+    let source = "#include \"a.hpp\"\nint c() { return a() + b(); }\n";
+    let tu = index.parse_string_with_headers("test.cpp", source, headers, Language::Cpp, &["-Wall"]);
+    let tu = tu.unwrap();
+    assert!(tu.get_diagnostics().is_empty());
+    let file = tu.get_file("/__clang_virtual__/include/b/b.hpp").unwrap();
+    assert_eq!(file.get_path(), Path::new("/__clang_virtual__/include/b/b.hpp"));
+    let children = tu.get_entity().get_children();
+    assert_eq!(children[0].get_name(), Some("b".into()));
+    assert_eq!(children[2].get_language(), Some(Language::Cpp));
+
     // TranslationUnit ___________________________
 
     //- from_ast ---------------------------------
@@ -718,6 +797,18 @@ fn test() {
         let _ = index.parser(f).unsaved(&[Unsaved::new(f, "int a = 644;")]).parse().unwrap();
     });
 
+    //- get_default_parse_options ---------------
+
+    let options = TranslationUnit::get_default_parse_options();
+    assert!(options.precompiled_preamble);
+    assert!(options.cache_completion_results);
+    assert!(!options.skip_function_bodies);
+
+    with_temporary_file("test.cpp", "int a = 322;", |_, f| {
+        let index = Index::new(&clang, false, false);
+        let _ = index.parser(f).options(options).for_serialization(true).parse().unwrap();
+    });
+
     //- get_file ---------------------------------
 
     with_translation_unit(&clang, "test.cpp", "int a = 322;", &[], |d, _, tu| {
@@ -737,6 +828,13 @@ fn test() {
         let _ = tu.reparse(&[Unsaved::new(f, "int a = 644;")]).unwrap();
     });
 
+    with_temporary_file("test.cpp", "int a = 322;", |_, f| {
+        let index = Index::new(&clang, false, false);
+        let tu = index.parser(f).precompiled_preamble(true).parse().unwrap();
+        let tu = tu.reparse(&[Unsaved::new(f, "int a = 644;")]).unwrap();
+        let _ = tu.reparse(&[]).unwrap();
+    });
+
     // Type ______________________________________
 
     with_entity(&clang, "int a = 322;", |e| {
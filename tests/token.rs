@@ -42,4 +42,49 @@ pub fn test(clang: &Clang) {
 
         test_annotate(&tu, &tokens);
     });
+    // TokenStream _______________________________
+
+    fn trivia(trivia: &[Trivia]) -> Vec<(TriviaKind, &str)> {
+        trivia.iter().map(|t| (t.kind, &t.text[..])).collect()
+    }
+
+    macro_rules! assert_stream_eq {
+        ($stream:expr, $source:expr) => ({
+            let stream = $stream;
+            assert_eq!(stream.get_text(), $source);
+
+            let tokens = stream.tokens.iter().map(|t| &t.text[..]).collect::<Vec<_>>();
+            assert_eq!(tokens, &["int", "a", "=", "322", ";", "int", "b", ";"]);
+            assert_eq!(stream.tokens[1].offset, 16);
+            assert_eq!(stream.tokens[1].token.get_kind(), TokenKind::Identifier);
+
+            assert_eq!(trivia(&stream.tokens[0].leading), &[
+                (TriviaKind::Comment, "/* a */"),
+                (TriviaKind::Whitespace, " "),
+            ]);
+            assert_eq!(trivia(&stream.tokens[4].trailing), &[
+                (TriviaKind::Whitespace, " "),
+                (TriviaKind::Comment, "// b"),
+                (TriviaKind::Newline, "\r\n"),
+            ]);
+            assert_eq!(trivia(&stream.tokens[5].leading), &[
+                (TriviaKind::Whitespace, "\t"),
+            ]);
+            assert_eq!(trivia(&stream.tokens[7].trailing), &[(TriviaKind::Newline, "\n")]);
+            assert_eq!(trivia(&stream.trailing), &[(TriviaKind::Comment, "// c")]);
+
+            assert_eq!(stream.tokens[0].get_full_text(), "/* a */ int\n");
+        });
+    }
+
+    let source = "/* a */ int\n    a = 322; // b\r\n\tint b;\n// c";
+
+    super::with_translation_unit(&clang, "test.cpp", source, &[], |_, f, tu| {
+        assert_stream_eq!(tu.get_file(f).unwrap().tokenize().unwrap(), source);
+        assert_stream_eq!(tu.tokenize().unwrap(), source);
+    });
+
+    let index = Index::new(&clang, false, false);
+    let lexer = Lexer::new(&index, &Unsaved::new("unsaved.cpp", source), &["-std=c++11"]).unwrap();
+    assert_stream_eq!(lexer.tokenize(), source);
 }